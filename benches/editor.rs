@@ -0,0 +1,103 @@
+//! Frame cost of the editor on a feature-length script. The canvas redraws
+//! only what `lines_between` yields, so that walk plus the caret lookup is
+//! the per-frame work; layout is what an edit costs on top of it.
+
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use quill::document::ElementChanges;
+use quill::document::pagination::PageMap;
+use quill::document::template::StyleSheet;
+use quill::ui::editor_layout::{EditorLayout, Flow};
+use quill::{Element, ElementType, Screenplay};
+use std::hint::black_box;
+
+const PAGES: usize = 150;
+const VIEW_HEIGHT: f32 = 800.0;
+
+/// One scene: a heading, some action and a short exchange.
+fn scene(number: usize) -> Vec<Element> {
+    let element = |element_type, content: &str| Element::new(element_type, content.to_string());
+    vec![
+        element(ElementType::SceneHeading, &format!("INT. WAREHOUSE {} - NIGHT", number)),
+        element(
+            ElementType::Action,
+            "Rain hammers the skylights. Crates stacked to the rafters throw long shadows \
+             across the concrete, and somewhere in the dark a forklift idles.",
+        ),
+        element(ElementType::Character, "MARA"),
+        element(ElementType::Parenthetical, "under her breath"),
+        element(
+            ElementType::Dialogue,
+            "If they moved it tonight, they moved it through here. Check every manifest.",
+        ),
+        element(ElementType::Character, "DEV"),
+        element(ElementType::Dialogue, "Every one? There are thousands."),
+        element(ElementType::Action, "She doesn't answer. He starts counting."),
+        element(ElementType::Transition, "CUT TO:"),
+    ]
+}
+
+/// A script of at least `PAGES` pages.
+fn script() -> Vec<Element> {
+    let mut elements = Vec::new();
+    let mut number = 1;
+    while PageMap::new(&elements).page_count() < PAGES {
+        for _ in 0..20 {
+            elements.extend(scene(number));
+            number += 1;
+        }
+    }
+    elements
+}
+
+fn bench_editor(c: &mut Criterion) {
+    let elements = script();
+    let layout = EditorLayout::new(&elements);
+    let middle = elements.len() / 2;
+    let top = layout.caret(&elements, middle, 0).map_or(0.0, |caret| caret.y);
+
+    c.bench_function("layout/full", |b| b.iter(|| EditorLayout::new(black_box(&elements))));
+
+    let mut edited = elements.clone();
+    edited[middle].content.push_str(" More.");
+    let changes = ElementChanges {
+        start: middle,
+        tail: elements.len() - middle - 1,
+    };
+    c.bench_function("layout/one_edit", |b| {
+        b.iter_batched(
+            || layout.clone(),
+            |mut layout| {
+                layout.update(black_box(&edited), &StyleSheet::default(), Flow::Draft, 1, Some(changes));
+                layout
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    c.bench_function("frame/visible_lines", |b| {
+        b.iter(|| {
+            let lines = layout.lines_between(top, top + VIEW_HEIGHT).count();
+            let caret = layout.caret(&elements, middle, 0);
+            black_box((lines, caret))
+        })
+    });
+
+    c.bench_function("pagination/full", |b| b.iter(|| PageMap::new(black_box(&elements))));
+
+    let mut screenplay = Screenplay::empty();
+    screenplay.elements = edited;
+    let pages = PageMap::new(&elements);
+    c.bench_function("pagination/one_edit", |b| {
+        b.iter_batched(
+            || pages.clone(),
+            |mut pages| {
+                pages.update(black_box(&screenplay), Some(changes));
+                pages
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, bench_editor);
+criterion_main!(benches);
@@ -0,0 +1,104 @@
+use crate::document::flow::ElementFlow;
+use crate::document::{Element, ElementType, Screenplay, TextRange, flow, fountain};
+
+/// The last thing copied from Quill. The system clipboard only holds plain
+/// text, so the elements are kept here and used whenever the system
+/// clipboard still holds the text we put there.
+#[derive(Debug, Clone)]
+pub struct Clip {
+    pub text: String,
+    pub elements: Vec<Element>,
+}
+
+impl Clip {
+    pub fn new(screenplay: &Screenplay, range: TextRange) -> Self {
+        let elements = screenplay.slice(range);
+        let text = match elements.as_slice() {
+            [element] => element.content.clone(),
+            _ => fountain::write_elements(&elements),
+        };
+        Self { text, elements }
+    }
+}
+
+/// Elements to insert for pasted `text`, preferring the structured copy
+/// when the clipboard holds what Quill last copied.
+pub fn elements_for_paste(
+    clip: Option<&Clip>,
+    text: Option<&str>,
+    current: ElementType,
+    flow: impl Fn(ElementType) -> ElementFlow,
+) -> Vec<Element> {
+    match (clip, text) {
+        (Some(clip), Some(text)) if normalize(text) == normalize(&clip.text) => clip.elements.clone(),
+        (_, Some(text)) => split_plain_text(text, current, flow),
+        (Some(clip), None) => clip.elements.clone(),
+        (None, None) => Vec::new(),
+    }
+}
+
+/// Splits text from outside Quill into elements, one per non-blank line.
+/// The first line continues the element being edited; each later line takes
+/// the type Enter would give it after the line before, once that line has
+/// been retyped the way Enter retypes it. Names are not known yet in pasted
+/// text, so an action line in capitals with a line right below it is taken
+/// for a cue as well.
+pub fn split_plain_text(text: &str, current: ElementType, flow: impl Fn(ElementType) -> ElementFlow) -> Vec<Element> {
+    let text = normalize(text);
+    if !text.contains('\n') {
+        return vec![Element::new(current, text)];
+    }
+
+    let mut elements: Vec<Element> = Vec::new();
+    let mut previous_line = None;
+    for (number, line) in text.lines().map(str::trim).enumerate().filter(|(_, line)| !line.is_empty()) {
+        let mut element_type = current;
+        if let Some(previous) = elements.len().checked_sub(1) {
+            let speaks = previous_line == number.checked_sub(1) && flow::is_all_caps(&elements[previous].content);
+            let detected = flow::detect(&elements, previous).or_else(|| {
+                (speaks && elements[previous].element_type == ElementType::Action).then_some(ElementType::Character)
+            });
+            if let Some(detected) = detected {
+                elements[previous].element_type = detected;
+            }
+            element_type = flow(elements[previous].element_type).enter;
+        }
+        elements.push(Element::new(element_type, line.to_string()));
+        previous_line = Some(number);
+    }
+    elements
+}
+
+fn normalize(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_plain_text_uses_enter_heuristics() {
+        let text = "Rain hammers the roof.\r\n\r\nMARA\r\nWho's there?\n\nBOOM!\n\nCUT TO:\n\nint. cellar - night";
+        let elements = split_plain_text(text, ElementType::Action, ElementFlow::for_type);
+        let types: Vec<ElementType> = elements.iter().map(|e| e.element_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                ElementType::Action,
+                ElementType::Character,
+                ElementType::Dialogue,
+                ElementType::Action,
+                ElementType::Transition,
+                ElementType::SceneHeading,
+            ]
+        );
+        assert_eq!(elements[2].content, "Who's there?");
+    }
+
+    #[test]
+    fn test_single_line_stays_inline() {
+        let elements = split_plain_text("hello", ElementType::Dialogue, ElementFlow::for_type);
+        assert_eq!(elements, vec![Element::new(ElementType::Dialogue, "hello".to_string())]);
+    }
+}
@@ -0,0 +1,155 @@
+use super::message::Message;
+use crate::document::format::FILE_EXTENSION;
+use crate::document::{Screenplay, fdx, fountain, note, pdf, read_file};
+use crate::error::Result;
+use iced::Task;
+use rfd::{AsyncFileDialog, AsyncMessageDialog, MessageButtons, MessageDialogResult, MessageLevel};
+use std::fs;
+use std::path::Path;
+
+const FOUNTAIN_EXTENSION: &str = "fountain";
+const FINAL_DRAFT_EXTENSION: &str = "fdx";
+const PDF_EXTENSION: &str = "pdf";
+const TEXT_EXTENSION: &str = "txt";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Fountain,
+    FinalDraft,
+    Pdf,
+    /// Every note with where it sits in the script, as plain text.
+    NotesReport,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Fountain => FOUNTAIN_EXTENSION,
+            ExportFormat::FinalDraft => FINAL_DRAFT_EXTENSION,
+            ExportFormat::Pdf => PDF_EXTENSION,
+            ExportFormat::NotesReport => TEXT_EXTENSION,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Fountain => "Fountain",
+            ExportFormat::FinalDraft => "Final Draft",
+            ExportFormat::Pdf => "PDF",
+            ExportFormat::NotesReport => "Notes report",
+        }
+    }
+}
+
+/// A file action that would close the current script, held while the
+/// writer decides what to do with its unsaved changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PendingAction {
+    Open,
+    New,
+    Quit,
+}
+
+/// The writer's answer to the unsaved changes prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnsavedChoice {
+    Save,
+    Discard,
+    Cancel,
+}
+
+/// Opens a native `.quill` file, or imports any supported foreign format
+/// along with any warnings the import produced. Imported screenplays have no
+/// file path so the next save asks for one.
+pub fn open(path: &Path) -> Result<(Screenplay, Vec<String>)> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    match extension {
+        _ if extension.eq_ignore_ascii_case(FOUNTAIN_EXTENSION) => Ok((fountain::parse(&read_file(path)?), Vec::new())),
+        _ if extension.eq_ignore_ascii_case(FINAL_DRAFT_EXTENSION) => {
+            let import = fdx::parse(&read_file(path)?)?;
+            Ok((import.screenplay, import.warnings))
+        }
+        _ => Ok((Screenplay::load(path)?, Vec::new())),
+    }
+}
+
+pub fn export(screenplay: &Screenplay, format: ExportFormat, path: &Path) -> Result<()> {
+    let contents = match format {
+        ExportFormat::Fountain => fountain::write(screenplay).into_bytes(),
+        ExportFormat::FinalDraft => fdx::write(screenplay)?.into_bytes(),
+        ExportFormat::Pdf => pdf::write(screenplay),
+        ExportFormat::NotesReport => note::report(screenplay).into_bytes(),
+    };
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+pub fn pick_open_path() -> Task<Message> {
+    Task::perform(
+        async {
+            AsyncFileDialog::new()
+                .add_filter(
+                    "Screenplay",
+                    &[FILE_EXTENSION, FOUNTAIN_EXTENSION, FINAL_DRAFT_EXTENSION],
+                )
+                .pick_file()
+                .await
+                .map(|handle| handle.path().to_path_buf())
+        },
+        Message::OpenPathSelected,
+    )
+}
+
+pub fn pick_save_path(suggested_name: String) -> Task<Message> {
+    Task::perform(
+        async move {
+            AsyncFileDialog::new()
+                .add_filter("Quill screenplay", &[FILE_EXTENSION])
+                .set_file_name(format!("{}.{}", suggested_name, FILE_EXTENSION))
+                .save_file()
+                .await
+                .map(|handle| handle.path().to_path_buf())
+        },
+        Message::SavePathSelected,
+    )
+}
+
+pub fn pick_export_path(format: ExportFormat, suggested_name: String) -> Task<Message> {
+    Task::perform(
+        async move {
+            AsyncFileDialog::new()
+                .add_filter(format.as_str(), &[format.extension()])
+                .set_file_name(format!("{}.{}", suggested_name, format.extension()))
+                .save_file()
+                .await
+                .map(|handle| handle.path().to_path_buf())
+        },
+        move |path| Message::ExportPathSelected(format, path),
+    )
+}
+
+/// Asks whether to save the changes to `name` before `action` closes it.
+pub fn confirm_unsaved(action: PendingAction, name: String) -> Task<Message> {
+    Task::perform(
+        async move {
+            AsyncMessageDialog::new()
+                .set_level(MessageLevel::Warning)
+                .set_title("Unsaved changes")
+                .set_description(format!(
+                    "Save the changes to {} first? Choosing No discards them.",
+                    name
+                ))
+                .set_buttons(MessageButtons::YesNoCancel)
+                .show()
+                .await
+        },
+        move |result| {
+            let choice = match result {
+                MessageDialogResult::Yes => UnsavedChoice::Save,
+                MessageDialogResult::No => UnsavedChoice::Discard,
+                _ => UnsavedChoice::Cancel,
+            };
+            Message::UnsavedChangesAnswered(action, choice)
+        },
+    )
+}
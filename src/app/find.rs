@@ -0,0 +1,88 @@
+use crate::document::search::{Matcher, Query};
+use crate::document::{Screenplay, TextPosition, TextRange};
+
+/// The find and replace bar above the script.
+#[derive(Debug, Clone, Default)]
+pub struct Find {
+    pub open: bool,
+    pub query: Query,
+    pub replacement: String,
+    /// Every match of `query`, in script order.
+    pub matches: Vec<TextRange>,
+    /// Why `query` could not be compiled, shown in place of a match count.
+    pub error: Option<String>,
+    /// Bumped whenever `matches` changes, so the canvas knows to redraw.
+    pub generation: u64,
+    /// The screenplay revision `matches` were found in, or `None` after
+    /// the query changed.
+    searched: Option<u64>,
+}
+
+impl Find {
+    /// Marks the matches stale after the query changed.
+    pub fn invalidate(&mut self) {
+        self.searched = None;
+    }
+
+    /// Hides the bar and its highlights, keeping the query for next time.
+    pub fn close(&mut self) {
+        self.open = false;
+        self.matches.clear();
+        self.generation += 1;
+        self.invalidate();
+    }
+
+    pub fn matcher(&self) -> Option<Matcher> {
+        self.query.compile().ok().flatten()
+    }
+
+    /// Searches again if the query or the script changed since the last
+    /// search.
+    pub fn refresh(&mut self, screenplay: &Screenplay) {
+        if !self.open || self.searched == Some(screenplay.revision) {
+            return;
+        }
+        self.searched = Some(screenplay.revision);
+        self.generation += 1;
+        match self.query.compile() {
+            Ok(matcher) => {
+                self.matches = matcher.map(|matcher| matcher.find_all(&screenplay.elements)).unwrap_or_default();
+                self.error = None;
+            }
+            Err(e) => {
+                self.matches.clear();
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// The match after `selection`, or after `caret` when nothing is
+    /// selected, wrapping around at the end of the script.
+    pub fn next(&self, caret: TextPosition, selection: Option<TextRange>) -> Option<TextRange> {
+        let from = selection.map_or(caret, |selection| selection.end);
+        self.matches
+            .iter()
+            .find(|range| range.start >= from)
+            .or(self.matches.first())
+            .copied()
+    }
+
+    /// The match before `selection`, or before `caret`, wrapping around at
+    /// the start of the script.
+    pub fn previous(&self, caret: TextPosition, selection: Option<TextRange>) -> Option<TextRange> {
+        let from = selection.map_or(caret, |selection| selection.start);
+        self.matches
+            .iter()
+            .rev()
+            .find(|range| range.start < from)
+            .or(self.matches.last())
+            .copied()
+    }
+
+    /// Which match `selection` is, counting from 1.
+    pub fn position_of(&self, selection: Option<TextRange>) -> Option<usize> {
+        let selection = selection?;
+        let index = self.matches.binary_search_by_key(&selection.start, |range| range.start).ok()?;
+        (self.matches[index] == selection).then_some(index + 1)
+    }
+}
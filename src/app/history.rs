@@ -0,0 +1,246 @@
+use super::navigation::CursorState;
+use crate::document::{Edit, Screenplay, style};
+
+const MAX_STEPS: usize = 500;
+
+/// One undoable unit: the edits it made and where the cursor was on either
+/// side of it.
+struct Step {
+    /// Tells steps apart so the save point survives undo and redo.
+    id: u64,
+    edits: Vec<Edit>,
+    before: CursorState,
+    after: CursorState,
+}
+
+pub struct History {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+    sealed: bool,
+    next_id: u64,
+    /// Id of the step on top of the undo stack when the script was last
+    /// saved, 0 for an empty stack, or `None` once no amount of undoing or
+    /// redoing gets back there.
+    saved: Option<u64>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            sealed: false,
+            next_id: 1,
+            saved: Some(0),
+        }
+    }
+}
+
+impl History {
+    pub fn record(&mut self, edits: Vec<Edit>, before: CursorState, after: CursorState) {
+        if edits.is_empty() {
+            return;
+        }
+        self.redo.clear();
+
+        if !self.sealed
+            && let [edit] = edits.as_slice()
+            && let Some(step) = self.undo.last_mut()
+            && let [previous] = step.edits.as_mut_slice()
+            && coalesce(previous, edit)
+        {
+            step.after = after;
+            return;
+        }
+
+        self.undo.push(Step {
+            id: self.next_id,
+            edits,
+            before,
+            after,
+        });
+        self.next_id += 1;
+        if self.undo.len() > MAX_STEPS {
+            let dropped = self.undo.remove(0);
+            // Undoing everything left now stops just after the dropped step.
+            self.saved = match self.saved {
+                Some(0) => None,
+                Some(id) if id == dropped.id => Some(0),
+                saved => saved,
+            };
+        }
+        self.sealed = false;
+    }
+
+    /// Ends the current typing run so the next edit starts a new step.
+    pub fn seal(&mut self) {
+        self.sealed = true;
+    }
+
+    /// Remembers the current step as the one the script was saved at.
+    pub fn mark_save_point(&mut self) {
+        self.saved = Some(self.top());
+        // Typing on after a save must not extend the saved step.
+        self.sealed = true;
+    }
+
+    /// Forgets the save point after a change made outside the history,
+    /// which undo cannot take back.
+    pub fn forget_save_point(&mut self) {
+        self.saved = None;
+    }
+
+    fn top(&self) -> u64 {
+        self.undo.last().map_or(0, |step| step.id)
+    }
+
+    /// Marks the script modified unless undo or redo has brought it back
+    /// to the step it was saved at.
+    fn update_modified(&self, screenplay: &mut Screenplay) {
+        if self.saved == Some(self.top()) {
+            screenplay.mark_saved();
+        } else {
+            screenplay.modified = true;
+        }
+    }
+
+    pub fn undo(&mut self, screenplay: &mut Screenplay) -> Option<CursorState> {
+        let step = self.undo.pop()?;
+        for edit in step.edits.iter().rev() {
+            edit.inverse().apply(screenplay);
+        }
+        let cursor = step.before;
+        self.redo.push(step);
+        self.sealed = true;
+        self.update_modified(screenplay);
+        Some(cursor)
+    }
+
+    pub fn redo(&mut self, screenplay: &mut Screenplay) -> Option<CursorState> {
+        let step = self.redo.pop()?;
+        for edit in &step.edits {
+            edit.apply(screenplay);
+        }
+        let cursor = step.after;
+        self.undo.push(step);
+        self.sealed = true;
+        self.update_modified(screenplay);
+        Some(cursor)
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Folds `next` into `previous` when both belong to the same word being
+/// typed or erased, or both change the same note. A new step starts
+/// whenever a word boundary is crossed.
+fn coalesce(previous: &mut Edit, next: &Edit) -> bool {
+    match (previous, next) {
+        (
+            Edit::InsertText {
+                index,
+                offset,
+                text,
+                styles,
+            },
+            Edit::InsertText {
+                index: next_index,
+                offset: next_offset,
+                text: next_text,
+                styles: next_styles,
+            },
+        ) if index == next_index
+            && *offset + text.len() == *next_offset
+            && !starts_word_boundary(text, next_text) =>
+        {
+            style::insert(styles, text.len(), next_text.len(), next_styles);
+            text.push_str(next_text);
+            true
+        }
+        (
+            Edit::DeleteText {
+                index,
+                offset,
+                text,
+                styles,
+            },
+            Edit::DeleteText {
+                index: next_index,
+                offset: next_offset,
+                text: next_text,
+                styles: next_styles,
+            },
+        ) if index == next_index && *next_offset + next_text.len() == *offset => {
+            style::insert(styles, 0, next_text.len(), next_styles);
+            text.insert_str(0, next_text);
+            *offset = *next_offset;
+            true
+        }
+        (Edit::SetNote { to, .. }, Edit::SetNote { from, to: next_to }) if to == from => {
+            *to = next_to.clone();
+            true
+        }
+        _ => false,
+    }
+}
+
+fn starts_word_boundary(text: &str, next: &str) -> bool {
+    let previous_is_space = text.ends_with(char::is_whitespace);
+    let next_is_space = next.starts_with(char::is_whitespace);
+    next_is_space && !previous_is_space
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::ElementType;
+
+    fn cursor(offset: usize) -> CursorState {
+        CursorState {
+            position: 0,
+            offset,
+            desired_offset: offset,
+            element_type: ElementType::Action,
+            anchor: None,
+            typing_style: None,
+        }
+    }
+
+    fn typed(offset: usize, c: char) -> Vec<Edit> {
+        vec![Edit::InsertText {
+            index: 0,
+            offset,
+            text: c.to_string(),
+            styles: Vec::new(),
+        }]
+    }
+
+    #[test]
+    fn test_typing_coalesces_by_word() {
+        let mut history = History::default();
+        for (offset, c) in "hi there".chars().enumerate() {
+            history.record(typed(offset, c), cursor(offset), cursor(offset + 1));
+        }
+        assert_eq!(history.undo.len(), 2);
+        assert_eq!(
+            history.undo[1].edits,
+            vec![Edit::InsertText {
+                index: 0,
+                offset: 2,
+                text: " there".to_string(),
+                styles: Vec::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_seal_starts_new_step() {
+        let mut history = History::default();
+        history.record(typed(0, 'a'), cursor(0), cursor(1));
+        history.seal();
+        history.record(typed(1, 'b'), cursor(1), cursor(2));
+        assert_eq!(history.undo.len(), 2);
+    }
+}
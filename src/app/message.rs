@@ -1,5 +1,15 @@
+use super::Screen;
+use super::file::{ExportFormat, PendingAction, UnsavedChoice};
+use super::viewport::ViewMode;
+use crate::document::{ElementType, Emphasis, NoteColor, TextPosition};
+use crate::document::flow::ElementFlow;
+use crate::document::layout::{ElementFormat, Paper};
+use crate::document::template::Template;
+use crate::document::title_page::TitleField;
+use iced::keyboard::Modifiers;
 use iced::widget::text_editor;
 use iced::Event;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -7,12 +17,104 @@ pub enum Message {
     TabPressed,
     EnterPressed,
     CharacterTyped(char),
+    TextTyped(String),
     BackspacePressed,
     DeletePressed,
+    /// Dismisses the completion popup, or else closes the find bar.
+    EscapePressed,
+    /// Ctrl+B/I/U: emphasis on the selection, or on what is typed next.
+    ToggleEmphasis(Emphasis),
+    /// Ctrl+D: sets the speech at the caret beside its neighbour as dual
+    /// dialogue, or splits the pair it is in.
+    ToggleDualDialogue,
+    /// Numbers the scenes that have no number yet, giving insert numbers
+    /// such as 12A between scenes already numbered.
+    NumberScenes,
+    /// Numbers every scene by its place in the script. Refused while the
+    /// numbers are locked.
+    RenumberScenes,
+    /// Locks scene numbers, numbering any scene still without one, or
+    /// unlocks them.
+    LockScenes(bool),
+    /// Replaces the scene at the caret with an `OMITTED` heading that
+    /// keeps its number.
+    OmitScene,
     ArrowLeft,
     ArrowRight,
     ArrowUp,
     ArrowDown,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    /// Scroll the editor by this many pixels.
+    Scrolled(f32),
+    /// Scroll the editor so this layout offset is at the top.
+    ScrolledTo(f32),
+    ViewportResized(f32),
+    /// The window opened or changed size, so the editor is measured again.
+    WindowResized,
+    ToggleTypewriter,
+    ViewModeSelected(ViewMode),
+    PaperSelected(Paper),
+    /// Lays the script out with a bundled template, dropping any formats
+    /// changed from the current one.
+    TemplateSelected(Template),
+    ElementFormatChanged(ElementType, ElementFormat),
+    ElementFlowChanged(ElementType, ElementFlow),
+    ScreenSelected(Screen),
+    TitleChanged(String),
+    AuthorChanged(String),
+    ContactAction(text_editor::Action),
+    TitleFieldChanged(TitleField, String),
+    /// Leaves a note on the selection, or on the whole element.
+    AddNote,
+    NextNote,
+    PreviousNote,
+    NoteTextChanged(String),
+    NoteAuthorChanged(String),
+    NoteColorSelected(NoteColor),
+    NoteResolved(bool),
+    DeleteNote,
+    CloseNote,
+    /// Ctrl+F: shows the find bar and focuses its search field.
+    OpenFind,
+    CloseFind,
+    FindTextChanged(String),
+    ReplacementChanged(String),
+    FindCaseSensitiveToggled(bool),
+    FindWholeWordToggled(bool),
+    FindRegexToggled(bool),
+    /// Restricts the search to, or stops restricting it to, a type.
+    FindElementTypeToggled(ElementType, bool),
+    FindNext,
+    FindPrevious,
+    /// Replaces the selected match and moves on to the next.
+    Replace,
+    ReplaceAll,
+    ClickedAt(TextPosition),
+    DraggedTo(TextPosition),
+    /// The mouse button was let go after dragging out a selection.
+    DragEnded,
+    DoubleClickedAt(TextPosition),
+    ModifiersChanged(Modifiers),
+    Undo,
+    Redo,
+    Cut,
+    Copy,
+    Paste,
+    ClipboardRead(Option<String>),
     EventOccurred(Event),
     Tick,
+    OpenFile,
+    NewFile,
+    /// Closes the window, asking first about unsaved changes.
+    Quit,
+    UnsavedChangesAnswered(PendingAction, UnsavedChoice),
+    SaveFile,
+    SaveFileAs,
+    OpenPathSelected(Option<PathBuf>),
+    SavePathSelected(Option<PathBuf>),
+    Export(ExportFormat),
+    ExportPathSelected(ExportFormat, Option<PathBuf>),
 }
@@ -1,14 +1,55 @@
+mod clipboard;
+mod file;
+mod find;
+mod history;
 mod message;
 mod navigation;
+mod preferences;
 mod update;
+mod viewport;
 
+pub use clipboard::Clip;
+pub use file::{ExportFormat, PendingAction, UnsavedChoice};
+pub use find::Find;
 pub use message::Message;
+pub use preferences::Preferences;
+pub use viewport::{ViewMode, Viewport};
 
-use crate::document::{Element, ElementType, Screenplay};
-use iced::widget::text_editor;
-use iced::{Subscription, Task, time};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use iced::keyboard::Modifiers;
+use iced::widget::{container, text_editor, text_input};
+use iced::{Subscription, Task, clipboard as system_clipboard, time, window};
+
+use crate::document::flow::ElementFlow;
+use crate::document::pagination::PageMap;
+use crate::document::template::StyleSheet;
+use crate::document::{
+    Edit, Element, ElementType, Note, Screenplay, Style, TextPosition, TextRange, completion, dual, flow, scene_number,
+    title_page,
+};
+use crate::error::Result;
+use crate::ui::editor_layout::{EditorLayout, Flow};
+use history::History;
 use navigation::CursorState;
-use std::time::Duration;
+
+/// The note panel's text field, focused when a note is added.
+pub(crate) const NOTE_INPUT: &str = "note-text";
+/// The container around the script canvas, measured for the viewport.
+pub(crate) const EDITOR_AREA: &str = "editor-area";
+/// The find bar's search field.
+pub(crate) const FIND_INPUT: &str = "find-text";
+
+/// What fills the window below the toolbar.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Screen {
+    #[default]
+    Script,
+    TitlePage,
+    /// The template and element formats of the script.
+    Styles,
+}
 
 pub struct App {
     pub screenplay: Screenplay,
@@ -18,14 +59,61 @@ pub struct App {
     pub cursor_offset: usize,
     pub cursor_visible: bool,
     pub desired_cursor_offset: usize,
+    pub selection_anchor: Option<TextPosition>,
+    /// Emphasis toggled with nothing selected, for the next text typed.
+    pub typing_style: Option<Style>,
+    pub modifiers: Modifiers,
+    pub status: Option<String>,
+    pub clipboard: Option<Clip>,
+    pub page_map: PageMap,
+    pub viewport: Viewport,
+    pub view_mode: ViewMode,
+    pub screen: Screen,
+    /// The contact block while the title page is being edited.
+    pub contact: text_editor::Content,
+    /// Id of the note open in the note panel.
+    pub current_note: Option<u64>,
+    /// Signed on new notes.
+    pub note_author: String,
+    pub find: Find,
+    /// Completions offered for the cue or heading being typed, best first.
+    pub completions: Vec<String>,
+    /// Index into `completions` of the one Tab or Enter accepts.
+    pub selected_completion: usize,
+    /// The writer's own settings, shared by every script.
+    pub preferences: Preferences,
+    /// Where `preferences` are saved, or `None` to keep them for this
+    /// session only.
+    preferences_path: Option<PathBuf>,
+    editor_layout: EditorLayout,
+    history: History,
+    /// The action to finish once a save asked for by the unsaved changes
+    /// prompt has picked its path.
+    pending: Option<PendingAction>,
 }
 
 impl Default for App {
     fn default() -> Self {
-        let mut screenplay = Screenplay::new("Untitled".to_string());
+        let mut screenplay = Screenplay::empty();
         screenplay.add_element(Element::new(ElementType::Action, String::new()));
+        screenplay.mark_saved();
 
         Self {
+            page_map: PageMap::new(&screenplay.elements),
+            viewport: Viewport::default(),
+            view_mode: ViewMode::default(),
+            screen: Screen::default(),
+            contact: text_editor::Content::new(),
+            current_note: None,
+            note_author: std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .unwrap_or_default(),
+            find: Find::default(),
+            completions: Vec::new(),
+            selected_completion: 0,
+            preferences: Preferences::default(),
+            preferences_path: None,
+            editor_layout: EditorLayout::new(&screenplay.elements),
             screenplay,
             content: text_editor::Content::new(),
             current_element_type: ElementType::Action,
@@ -33,17 +121,238 @@ impl Default for App {
             cursor_offset: 0,
             cursor_visible: true,
             desired_cursor_offset: 0,
+            selection_anchor: None,
+            typing_style: None,
+            modifiers: Modifiers::default(),
+            status: None,
+            clipboard: None,
+            history: History::default(),
+            pending: None,
         }
     }
 }
 
 impl App {
+    /// The app as it starts up, with the writer's saved preferences.
+    pub fn new() -> (Self, Task<Message>) {
+        let mut app = Self {
+            preferences_path: preferences::path(),
+            ..Self::default()
+        };
+        if let Some(path) = &app.preferences_path {
+            match Preferences::load(path) {
+                Ok(preferences) => app.preferences = preferences,
+                Err(e) => app.status = Some(format!("Could not read preferences: {}", e)),
+            }
+        }
+        (app, Task::none())
+    }
+
+    pub fn title(&self) -> String {
+        let marker = if self.screenplay.modified { "*" } else { "" };
+        format!("{}{} - Quill", self.screenplay.display_name(), marker)
+    }
+
+    fn load_screenplay(&mut self, mut screenplay: Screenplay) {
+        if screenplay.is_empty() {
+            screenplay.elements.push(Element::empty(ElementType::Action));
+        }
+        let element_type = screenplay.elements[0].element_type;
+
+        self.contact = text_editor::Content::with_text(screenplay.contact.as_deref().unwrap_or_default());
+        self.screenplay = screenplay;
+        self.current_note = None;
+        self.history.clear();
+        self.refresh_layout();
+        self.viewport.offset = 0.0;
+        self.apply_cursor_state(CursorState {
+            position: 0,
+            offset: 0,
+            desired_offset: 0,
+            element_type,
+            anchor: None,
+            typing_style: None,
+        });
+    }
+
+    pub fn current_page(&self) -> usize {
+        self.page_map.page_of(self.cursor_position).unwrap_or(1)
+    }
+
+    pub fn editor_layout(&self) -> &EditorLayout {
+        &self.editor_layout
+    }
+
+    /// Recomputes pagination and the editor layout after the script changed.
+    fn refresh_layout(&mut self) {
+        let changes = self.screenplay.take_changes();
+        self.page_map.update(&self.screenplay, changes);
+        let paper = self.screenplay.paper;
+        let title_page = (self.view_mode == ViewMode::Page && title_page::has_title_page(&self.screenplay))
+            .then(|| title_page::lines(&self.screenplay, paper.lines_per_page()));
+        let flow = match self.view_mode {
+            ViewMode::Draft => Flow::Draft,
+            ViewMode::Page => Flow::Pages {
+                pages: &self.page_map,
+                paper,
+                title_page: title_page.as_deref(),
+            },
+        };
+        self.editor_layout.update(
+            &self.screenplay.elements,
+            &self.screenplay.style_sheet,
+            flow,
+            self.screenplay.revision,
+            changes,
+        );
+    }
+
+    fn follow_caret(&mut self) {
+        if let Some(caret) =
+            self.editor_layout
+                .caret(&self.screenplay.elements, self.cursor_position, self.cursor_offset)
+        {
+            self.viewport.follow(caret.y, self.editor_layout.height());
+        }
+    }
+
+    /// Moves the caret a screen up or down, keeping it at the same height
+    /// in the view, and scrolls by the same amount.
+    fn page(&mut self, direction: f32) {
+        let Some(caret) =
+            self.editor_layout
+                .caret(&self.screenplay.elements, self.cursor_position, self.cursor_offset)
+        else {
+            return;
+        };
+        let distance = direction * self.viewport.page();
+        let target = iced::Point::new(caret.x, caret.y + distance);
+        if let Some(position) = self.editor_layout.hit_test(&self.screenplay.elements, target) {
+            self.move_cursor(|cursor, screenplay| cursor.move_to(screenplay, position));
+        }
+        self.viewport.scroll_by(distance, self.editor_layout.height());
+    }
+
+    /// Selects the next note after the current one, or after the caret
+    /// when none is open, wrapping around at either end of the script.
+    fn jump_to_note(&mut self, forward: bool) {
+        let reference = match self.current_note.and_then(|id| self.screenplay.note(id)) {
+            Some(note) => (note.anchor.start, note.id),
+            None => (self.cursor_state().text_position(), 0),
+        };
+        let mut notes: Vec<_> = self
+            .screenplay
+            .notes
+            .iter()
+            .map(|note| ((note.anchor.start, note.id), note.anchor, note.id))
+            .collect();
+        notes.sort_by_key(|&(key, ..)| key);
+
+        let target = if forward {
+            notes.iter().find(|(key, ..)| *key > reference).or(notes.first())
+        } else {
+            notes.iter().rev().find(|(key, ..)| *key < reference).or(notes.last())
+        };
+        let Some(&(_, anchor, id)) = target else {
+            self.status = Some("No notes".to_string());
+            return;
+        };
+
+        self.select(anchor);
+        self.current_note = Some(id);
+    }
+
+    /// Selects `range`, with the caret at its end.
+    fn select(&mut self, range: TextRange) {
+        let mut cursor = self.cursor_state();
+        cursor.move_to(&self.screenplay, range.end);
+        cursor.anchor = (!range.is_empty()).then_some(range.start);
+        cursor.typing_style = None;
+        self.apply_cursor_state(cursor);
+        self.history.seal();
+    }
+
+    /// Selects the next or previous match of the find bar's query.
+    fn find_match(&mut self, forward: bool) {
+        self.find.open = true;
+        self.find.refresh(&self.screenplay);
+        let caret = self.cursor_state().text_position();
+        let selection = self.selection();
+        let target = if forward {
+            self.find.next(caret, selection)
+        } else {
+            self.find.previous(caret, selection)
+        };
+        match target {
+            Some(range) => self.select(range),
+            None if self.find.query.text.is_empty() => {}
+            None => self.status = Some("No matches".to_string()),
+        }
+    }
+
+    /// Offers completions while a character cue or scene heading is typed
+    /// at its end.
+    fn refresh_completions(&mut self) {
+        self.completions.clear();
+        self.selected_completion = 0;
+        let cursor = self.cursor_state();
+        let Some(element) = self.screenplay.get_element(cursor.position) else {
+            return;
+        };
+        if cursor.anchor.is_some() || cursor.offset != element.content.len() {
+            return;
+        }
+        let elements = &self.screenplay.elements;
+        self.completions = match element.element_type {
+            ElementType::Character => completion::complete_cue(elements, cursor.position, &element.content),
+            ElementType::SceneHeading => completion::complete_heading(elements, cursor.position, &element.content),
+            _ => Vec::new(),
+        };
+    }
+
+    /// Replaces the element being typed with the selected completion, and
+    /// offers the next stage when it only completed part of a heading.
+    fn accept_completion(&mut self) {
+        let Some(completion) = self.completions.get(self.selected_completion).cloned() else {
+            return;
+        };
+        self.completions.clear();
+        self.edit(|cursor, screenplay| {
+            let end = screenplay.get_element(cursor.position).map_or(0, |e| e.content.len());
+            cursor.anchor = Some(TextPosition::new(cursor.position, 0));
+            cursor.offset = end;
+            let mut edits = cursor.delete_selection(screenplay);
+            edits.extend(cursor.insert_text(screenplay, &completion));
+            edits
+        });
+        self.history.seal();
+        if completion.ends_with(' ') {
+            self.refresh_completions();
+        }
+    }
+
+    /// Records edits made outside `CursorState` as one undoable step, with
+    /// the caret moved to `caret`.
+    fn record_edits(&mut self, edits: Vec<Edit>, caret: TextPosition) {
+        let before = self.cursor_state();
+        let mut cursor = before;
+        cursor.move_to(&self.screenplay, caret);
+        cursor.anchor = None;
+        cursor.typing_style = None;
+        self.apply_cursor_state(cursor);
+        self.history.seal();
+        self.history.record(edits, before, cursor);
+        self.history.seal();
+    }
+
     fn cursor_state(&self) -> CursorState {
         CursorState {
             position: self.cursor_position,
             offset: self.cursor_offset,
             desired_offset: self.desired_cursor_offset,
             element_type: self.current_element_type,
+            anchor: self.selection_anchor,
+            typing_style: self.typing_style,
         }
     }
 
@@ -52,9 +361,147 @@ impl App {
         self.cursor_offset = cursor.offset;
         self.desired_cursor_offset = cursor.desired_offset;
         self.current_element_type = cursor.element_type;
+        self.selection_anchor = cursor.anchor;
+        self.typing_style = cursor.typing_style;
+    }
+
+    pub fn selection(&self) -> Option<TextRange> {
+        self.cursor_state().selection()
+    }
+
+    /// Runs an editing operation against the cursor and records the edits
+    /// it made as one undoable step. In a locked script, numbered scene
+    /// headings the operation removed are kept as omitted scenes.
+    fn edit(&mut self, operation: impl FnOnce(&mut CursorState, &mut Screenplay) -> Vec<Edit>) {
+        let before = self.cursor_state();
+        let mut cursor = before;
+        let mut edits = operation(&mut cursor, &mut self.screenplay);
+        if self.screenplay.scenes_locked {
+            let kept = scene_number::keep_omitted(&self.screenplay.elements, cursor.position, &edits);
+            kept.iter().for_each(|edit| edit.apply(&mut self.screenplay));
+            edits.extend(kept);
+        }
+        cursor.anchor = None;
+        cursor.typing_style = None;
+        self.apply_cursor_state(cursor);
+        self.history.record(edits, before, cursor);
+    }
+
+    /// What Tab and Enter do after an element of `element_type`, as the
+    /// writer has set them for the script's template.
+    pub fn flow(&self, element_type: ElementType) -> ElementFlow {
+        self.preferences.flow(self.screenplay.style_sheet.template, element_type)
+    }
+
+    /// What is shown around the script; the editor's height changes with
+    /// it.
+    fn chrome(&self) -> (Screen, bool, bool) {
+        (self.screen, self.find.open, self.current_note.is_some())
+    }
+
+    /// Marks a change that undo cannot take back, after which undoing no
+    /// longer returns the script to how it was saved.
+    fn changed_outside_history(&mut self) {
+        self.screenplay.mark_modified();
+        self.history.forget_save_point();
+    }
+
+    /// Saves the script, to `path` when given, and remembers the undo step
+    /// it was saved at.
+    fn save(&mut self, path: Option<&Path>) -> Result<()> {
+        match path {
+            Some(path) => self.screenplay.save_as(path)?,
+            None => self.screenplay.save()?,
+        }
+        self.history.mark_save_point();
+        Ok(())
+    }
+
+    /// Applies a change to the notes and records it for undo. Adding or
+    /// removing a note is a step of its own, while changes to one note run
+    /// together like typing.
+    fn edit_notes(&mut self, edit: Edit) {
+        let cursor = self.cursor_state();
+        let separate = !matches!(edit, Edit::SetNote { .. });
+        if separate {
+            self.history.seal();
+        }
+        edit.apply(&mut self.screenplay);
+        self.history.record(vec![edit], cursor, cursor);
+        if separate {
+            self.history.seal();
+        }
+    }
+
+    /// Runs `change` on the open note, recording it only if it changed
+    /// anything.
+    fn change_note(&mut self, change: impl FnOnce(&mut Note)) {
+        let Some(from) = self.current_note.and_then(|id| self.screenplay.note(id)).cloned() else {
+            return;
+        };
+        let mut to = from.clone();
+        change(&mut to);
+        if to != from {
+            self.edit_notes(Edit::SetNote { from, to });
+        }
+    }
+
+    /// Numbers the scenes that have none as one undoable step.
+    fn number_scenes(&mut self) {
+        self.edit(|_, screenplay| {
+            let edits = scene_number::number(&screenplay.elements);
+            edits.iter().for_each(|edit| edit.apply(screenplay));
+            edits
+        });
+    }
+
+    /// Moves the caret, extending the selection while Shift is held and
+    /// dropping it otherwise.
+    fn move_cursor(&mut self, movement: impl FnOnce(&mut CursorState, &Screenplay)) {
+        let mut cursor = self.cursor_state();
+        let origin = cursor.text_position();
+        movement(&mut cursor, &self.screenplay);
+        cursor.typing_style = None;
+        cursor.anchor = if self.modifiers.shift() {
+            cursor.anchor.or(Some(origin))
+        } else {
+            None
+        };
+        self.apply_cursor_state(cursor);
+        self.history.seal();
     }
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
+        let caret = self.cursor_state().text_position();
+        let revision = self.screenplay.revision;
+        let typing = matches!(
+            message,
+            Message::CharacterTyped(_) | Message::TextTyped(_) | Message::BackspacePressed
+        );
+        let accepting =
+            !self.completions.is_empty() && matches!(message, Message::TabPressed | Message::EnterPressed);
+
+        let chrome = self.chrome();
+        let mut task = self.handle_message(message);
+        if self.chrome() != chrome {
+            task = Task::batch([task, measure_editor()]);
+        }
+        if self.screenplay.revision != self.editor_layout.revision() {
+            self.refresh_layout();
+        }
+        self.find.refresh(&self.screenplay);
+        if self.cursor_state().text_position() != caret || self.screenplay.revision != revision {
+            self.follow_caret();
+            if typing {
+                self.refresh_completions();
+            } else if !accepting {
+                self.completions.clear();
+            }
+        }
+        task
+    }
+
+    fn handle_message(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::Tick => {
                 self.cursor_visible = !self.cursor_visible;
@@ -62,72 +509,575 @@ impl App {
             Message::EditorAction(action) => {
                 self.content.perform(action);
             }
-            Message::TabPressed => {
-                self.current_element_type = update::next_element_type(self.current_element_type);
-                if let Some(element) = self.screenplay.elements.get_mut(self.cursor_position) {
-                    element.element_type = self.current_element_type;
+            Message::TabPressed | Message::EnterPressed if !self.completions.is_empty() => {
+                self.accept_completion();
+            }
+            Message::ArrowUp if !self.completions.is_empty() => {
+                let count = self.completions.len();
+                self.selected_completion = (self.selected_completion + count - 1) % count;
+            }
+            Message::ArrowDown if !self.completions.is_empty() => {
+                self.selected_completion = (self.selected_completion + 1) % self.completions.len();
+            }
+            Message::EscapePressed => {
+                if self.completions.is_empty() {
+                    self.find.close();
+                } else {
+                    self.completions.clear();
                 }
             }
+            Message::TabPressed => {
+                let element_type = self.flow(self.current_element_type).tab;
+                self.edit(|cursor, screenplay| cursor.set_element_type(screenplay, element_type));
+            }
             Message::CharacterTyped(c) => {
-                let mut cursor = self.cursor_state();
-                cursor.insert_char(&mut self.screenplay, c);
-                self.apply_cursor_state(cursor);
+                self.edit(|cursor, screenplay| {
+                    let mut edits = cursor.delete_selection(screenplay);
+                    edits.extend(cursor.insert_char(screenplay, c));
+                    edits
+                });
+            }
+            Message::TextTyped(text) => {
+                self.edit(|cursor, screenplay| {
+                    let mut edits = cursor.delete_selection(screenplay);
+                    edits.extend(cursor.insert_text(screenplay, &text));
+                    edits
+                });
             }
             Message::BackspacePressed => {
-                let mut cursor = self.cursor_state();
-                cursor.backspace(&mut self.screenplay);
-                self.apply_cursor_state(cursor);
+                self.edit(|cursor, screenplay| match cursor.selection() {
+                    Some(_) => cursor.delete_selection(screenplay),
+                    None => cursor.backspace(screenplay),
+                });
             }
             Message::DeletePressed => {
-                let mut cursor = self.cursor_state();
-                cursor.delete(&mut self.screenplay);
-                self.apply_cursor_state(cursor);
+                self.edit(|cursor, screenplay| match cursor.selection() {
+                    Some(_) => cursor.delete_selection(screenplay),
+                    None => cursor.delete(screenplay),
+                });
             }
             Message::ArrowLeft => {
-                let mut cursor = self.cursor_state();
-                cursor.move_left();
-                self.apply_cursor_state(cursor);
+                self.move_cursor(|cursor, screenplay| cursor.move_left(screenplay));
             }
             Message::ArrowRight => {
-                let mut cursor = self.cursor_state();
-                cursor.move_right(&self.screenplay);
-                self.apply_cursor_state(cursor);
+                self.move_cursor(|cursor, screenplay| cursor.move_right(screenplay));
             }
             Message::ArrowUp => {
+                self.move_cursor(|cursor, screenplay| cursor.move_up(screenplay));
+            }
+            Message::ArrowDown => {
+                self.move_cursor(|cursor, screenplay| cursor.move_down(screenplay));
+            }
+            Message::Home => {
+                self.move_cursor(|cursor, screenplay| cursor.move_home(screenplay));
+            }
+            Message::End => {
+                self.move_cursor(|cursor, screenplay| cursor.move_end(screenplay));
+            }
+            Message::ClickedAt(position) => {
+                self.move_cursor(|cursor, screenplay| cursor.move_to(screenplay, position));
+            }
+            Message::DraggedTo(position) => {
                 let mut cursor = self.cursor_state();
-                cursor.move_up(&self.screenplay);
+                let origin = cursor.text_position();
+                cursor.move_to(&self.screenplay, position);
+                cursor.anchor = cursor.anchor.or(Some(origin));
                 self.apply_cursor_state(cursor);
             }
-            Message::ArrowDown => {
+            Message::DragEnded => {
+                self.history.seal();
+            }
+            Message::DoubleClickedAt(position) => {
                 let mut cursor = self.cursor_state();
-                cursor.move_down(&self.screenplay);
+                cursor.anchor = None;
+                cursor.select_word(&self.screenplay, position);
                 self.apply_cursor_state(cursor);
+                self.history.seal();
             }
-            Message::EnterPressed => {
-                if let Some(current_element) = self.screenplay.elements.get(self.cursor_position) {
-                    self.current_element_type =
-                        update::detect_next_element_type(self.current_element_type, &current_element.content);
+            Message::ToggleEmphasis(emphasis) => {
+                let before = self.cursor_state();
+                let mut cursor = before;
+                let edits = cursor.toggle_emphasis(&mut self.screenplay, emphasis);
+                self.apply_cursor_state(cursor);
+                self.history.record(edits, before, cursor);
+            }
+            Message::ToggleDualDialogue => {
+                let Some(index) = dual::toggle_target(&self.screenplay.elements, self.cursor_position) else {
+                    self.status = Some("Dual dialogue needs two speeches in a row".to_string());
+                    return Task::none();
+                };
+                self.edit(|_, screenplay| {
+                    let from = screenplay.elements[index].dual;
+                    let edit = Edit::SetDual { index, from, to: !from };
+                    edit.apply(screenplay);
+                    vec![edit]
+                });
+            }
+            Message::NumberScenes => {
+                self.number_scenes();
+            }
+            Message::RenumberScenes => {
+                if self.screenplay.scenes_locked {
+                    self.status = Some("Unlock scene numbers to renumber".to_string());
+                    return Task::none();
+                }
+                self.edit(|_, screenplay| {
+                    let edits = scene_number::renumber(&screenplay.elements);
+                    edits.iter().for_each(|edit| edit.apply(screenplay));
+                    edits
+                });
+            }
+            Message::LockScenes(locked) => {
+                if locked {
+                    self.number_scenes();
+                }
+                if self.screenplay.scenes_locked != locked {
+                    self.screenplay.scenes_locked = locked;
+                    self.changed_outside_history();
+                }
+            }
+            Message::OmitScene => {
+                let Some(edits) = scene_number::omit(&self.screenplay.elements, self.cursor_position) else {
+                    self.status = Some("Only a numbered scene can be omitted".to_string());
+                    return Task::none();
+                };
+                self.edit(|cursor, screenplay| {
+                    edits.iter().for_each(|edit| edit.apply(screenplay));
+                    if let Some(Edit::InsertElement { index, .. }) = edits.last() {
+                        cursor.move_to(screenplay, TextPosition::new(*index, 0));
+                    }
+                    edits
+                });
+            }
+            Message::AddNote => {
+                let anchor = self.selection().unwrap_or_else(|| {
+                    let end = self
+                        .screenplay
+                        .get_element(self.cursor_position)
+                        .map_or(0, |element| element.content.len());
+                    TextRange::new(
+                        TextPosition::new(self.cursor_position, 0),
+                        TextPosition::new(self.cursor_position, end),
+                    )
+                });
+                let created = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |elapsed| elapsed.as_secs());
+                let note = Note::new(self.screenplay.next_note_id(), anchor, self.note_author.clone(), created);
+                self.current_note = Some(note.id);
+                self.edit_notes(Edit::AddNote { note });
+                return text_input::focus(NOTE_INPUT);
+            }
+            Message::NextNote => {
+                self.jump_to_note(true);
+            }
+            Message::PreviousNote => {
+                self.jump_to_note(false);
+            }
+            Message::NoteTextChanged(text) => {
+                self.change_note(|note| note.text = text);
+            }
+            Message::NoteAuthorChanged(author) => {
+                self.change_note(|note| note.author = author);
+            }
+            Message::NoteColorSelected(color) => {
+                self.change_note(|note| note.color = color);
+            }
+            Message::NoteResolved(resolved) => {
+                self.change_note(|note| note.resolved = resolved);
+            }
+            Message::DeleteNote => {
+                if let Some(note) = self.current_note.take().and_then(|id| self.screenplay.note(id)).cloned() {
+                    self.edit_notes(Edit::RemoveNote { note });
+                }
+            }
+            Message::CloseNote => {
+                // A note closed without any text was added by mistake.
+                if let Some(note) = self.current_note.take().and_then(|id| self.screenplay.note(id)).cloned()
+                    && note.text.trim().is_empty()
+                {
+                    self.edit_notes(Edit::RemoveNote { note });
+                }
+            }
+            Message::OpenFind => {
+                self.find.open = true;
+                // Searching for the selected text is the usual way in.
+                if let Some(range) = self.selection()
+                    && range.start.element == range.end.element
+                    && let Some(element) = self.screenplay.get_element(range.start.element)
+                {
+                    self.find.query.text = element.content[range.start.offset..range.end.offset].to_string();
+                    self.find.invalidate();
+                }
+                return text_input::focus(FIND_INPUT);
+            }
+            Message::CloseFind => {
+                self.find.close();
+            }
+            Message::FindTextChanged(text) => {
+                self.find.query.text = text;
+                self.find.invalidate();
+            }
+            Message::ReplacementChanged(replacement) => {
+                self.find.replacement = replacement;
+            }
+            Message::FindCaseSensitiveToggled(on) => {
+                self.find.query.case_sensitive = on;
+                self.find.invalidate();
+            }
+            Message::FindWholeWordToggled(on) => {
+                self.find.query.whole_word = on;
+                self.find.invalidate();
+            }
+            Message::FindRegexToggled(on) => {
+                self.find.query.regex = on;
+                self.find.invalidate();
+            }
+            Message::FindElementTypeToggled(element_type, on) => {
+                let types = &mut self.find.query.element_types;
+                types.retain(|&t| t != element_type);
+                if on {
+                    types.push(element_type);
+                }
+                self.find.invalidate();
+            }
+            Message::FindNext => {
+                self.find_match(true);
+            }
+            Message::FindPrevious => {
+                self.find_match(false);
+            }
+            Message::Replace => {
+                self.find.refresh(&self.screenplay);
+                let selected = self.selection().filter(|&range| self.find.position_of(Some(range)).is_some());
+                if let (Some(range), Some(matcher)) = (selected, self.find.matcher()) {
+                    let replacement = self.find.replacement.clone();
+                    let edits = matcher.replace(&mut self.screenplay, range, &replacement);
+                    let inserted = match edits.last() {
+                        Some(Edit::InsertText { text, .. }) => text.len(),
+                        _ => 0,
+                    };
+                    let caret = TextPosition::new(range.start.element, range.start.offset + inserted);
+                    self.record_edits(edits, caret);
+                    self.find.refresh(&self.screenplay);
+                }
+                self.find_match(true);
+            }
+            Message::ReplaceAll => {
+                let Some(matcher) = self.find.matcher() else {
+                    return Task::none();
+                };
+                let replacement = self.find.replacement.clone();
+                let edits = matcher.replace_all(&mut self.screenplay, &replacement);
+                let count = edits.iter().filter(|edit| matches!(edit, Edit::DeleteText { .. })).count();
+                self.status = Some(match count {
+                    0 => "No matches".to_string(),
+                    1 => "Replaced 1 match".to_string(),
+                    count => format!("Replaced {} matches", count),
+                });
+                let caret = self.cursor_state().text_position();
+                self.record_edits(edits, caret);
+            }
+            Message::PageUp => {
+                self.page(-1.0);
+            }
+            Message::PageDown => {
+                self.page(1.0);
+            }
+            Message::Scrolled(delta) => {
+                self.viewport.scroll_by(delta, self.editor_layout.height());
+            }
+            Message::ScrolledTo(offset) => {
+                self.viewport.scroll_to(offset, self.editor_layout.height());
+            }
+            Message::WindowResized => {
+                return measure_editor();
+            }
+            Message::ViewportResized(height) => {
+                self.viewport.height = height;
+                self.viewport.scroll_by(0.0, self.editor_layout.height());
+                if self.viewport.typewriter {
+                    self.follow_caret();
+                }
+            }
+            Message::ToggleTypewriter => {
+                self.viewport.typewriter = !self.viewport.typewriter;
+                self.follow_caret();
+            }
+            Message::ViewModeSelected(mode) => {
+                self.view_mode = mode;
+                self.refresh_layout();
+                self.viewport.scroll_by(0.0, self.editor_layout.height());
+                self.follow_caret();
+            }
+            Message::ScreenSelected(screen) => {
+                if screen == Screen::TitlePage {
+                    self.contact = text_editor::Content::with_text(self.screenplay.contact.as_deref().unwrap_or_default());
+                }
+                self.screen = screen;
+            }
+            Message::TitleChanged(title) => {
+                self.screenplay.title = if title.trim().is_empty() {
+                    Screenplay::UNTITLED.to_string()
+                } else {
+                    title
+                };
+                self.changed_outside_history();
+            }
+            Message::AuthorChanged(author) => {
+                self.screenplay.author = Some(author).filter(|author| !author.trim().is_empty());
+                self.changed_outside_history();
+            }
+            Message::ContactAction(action) => {
+                let edited = action.is_edit();
+                self.contact.perform(action);
+                if edited {
+                    let contact = self.contact.text();
+                    let contact = contact.trim_end_matches('\n');
+                    self.screenplay.contact = Some(contact.to_string()).filter(|contact| !contact.trim().is_empty());
+                    self.changed_outside_history();
+                }
+            }
+            Message::TitleFieldChanged(field, value) => {
+                let revision = self.screenplay.revision;
+                self.screenplay.set_title_field(field, &value);
+                if self.screenplay.revision != revision {
+                    self.history.forget_save_point();
+                }
+            }
+            Message::PaperSelected(paper) => {
+                if self.screenplay.paper != paper {
+                    self.screenplay.paper = paper;
+                    self.changed_outside_history();
+                }
+            }
+            Message::TemplateSelected(template) => {
+                if self.screenplay.style_sheet != StyleSheet::new(template) {
+                    self.screenplay.style_sheet = StyleSheet::new(template);
+                    self.changed_outside_history();
                 }
+            }
+            Message::ElementFormatChanged(element_type, format) => {
+                if self.screenplay.style_sheet.format(element_type) != format {
+                    self.screenplay.style_sheet.set_format(element_type, format);
+                    self.changed_outside_history();
+                }
+            }
+            Message::ElementFlowChanged(element_type, flow) => {
+                if self.flow(element_type) != flow {
+                    let template = self.screenplay.style_sheet.template;
+                    self.preferences.set_flow(template, element_type, flow);
+                    if let Some(path) = &self.preferences_path
+                        && let Err(e) = self.preferences.save(path)
+                    {
+                        self.status = Some(format!("Could not save preferences: {}", e));
+                    }
+                }
+            }
+            Message::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers;
+            }
+            Message::EnterPressed => {
+                let template = self.screenplay.style_sheet.template;
+                let preferences = self.preferences.clone();
+                self.edit(|cursor, screenplay| {
+                    let mut edits = cursor.delete_selection(screenplay);
+                    let index = cursor.position;
+                    if let Some(element) = screenplay.elements.get(index) {
+                        let from = element.element_type;
+                        // Enter on a blank element breaks out of the type
+                        // it was given and makes it action instead of
+                        // starting another.
+                        if element.is_empty() && from != ElementType::Action {
+                            let edit = Edit::SetElementType {
+                                index,
+                                from,
+                                to: ElementType::Action,
+                            };
+                            edit.apply(screenplay);
+                            cursor.element_type = ElementType::Action;
+                            edits.push(edit);
+                            return edits;
+                        }
+                        let detected = flow::detect(&screenplay.elements, index);
+                        if let Some(to) = detected {
+                            let edit = Edit::SetElementType { index, from, to };
+                            edit.apply(screenplay);
+                            edits.push(edit);
+                        }
+                        cursor.element_type = preferences.flow(template, detected.unwrap_or(from)).enter;
+                    }
 
-                let element = Element::new(self.current_element_type, String::new());
-                self.screenplay.insert_element(self.cursor_position + 1, element);
-                self.cursor_position += 1;
-                self.cursor_offset = 0;
-                self.desired_cursor_offset = 0;
+                    let edit = Edit::InsertElement {
+                        index: cursor.position + 1,
+                        element: Element::new(cursor.element_type, String::new()),
+                    };
+                    edit.apply(screenplay);
+                    cursor.position += 1;
+                    cursor.offset = 0;
+                    cursor.desired_offset = 0;
+                    edits.push(edit);
+                    edits
+                });
+            }
+            Message::Copy | Message::Cut => {
+                let Some(range) = self.selection() else {
+                    return Task::none();
+                };
+                let clip = Clip::new(&self.screenplay, range);
+                let text = clip.text.clone();
+                self.clipboard = Some(clip);
+                if matches!(message, Message::Cut) {
+                    self.edit(|cursor, screenplay| cursor.delete_selection(screenplay));
+                }
+                return system_clipboard::write(text);
+            }
+            Message::Paste => {
+                return system_clipboard::read().map(Message::ClipboardRead);
+            }
+            Message::ClipboardRead(text) => {
+                let elements = clipboard::elements_for_paste(
+                    self.clipboard.as_ref(),
+                    text.as_deref(),
+                    self.current_element_type,
+                    |element_type| self.flow(element_type),
+                );
+                let locked = self.screenplay.scenes_locked;
+                self.edit(|cursor, screenplay| {
+                    let mut edits = cursor.paste(screenplay, &elements);
+                    // Pasted scenes in a locked script are numbered as
+                    // inserts between the scenes around them.
+                    if locked {
+                        let numbers = scene_number::number(&screenplay.elements);
+                        numbers.iter().for_each(|edit| edit.apply(screenplay));
+                        edits.extend(numbers);
+                    }
+                    edits
+                });
+                self.history.seal();
+            }
+            Message::Undo => {
+                if let Some(cursor) = self.history.undo(&mut self.screenplay) {
+                    self.apply_cursor_state(cursor);
+                }
+            }
+            Message::Redo => {
+                if let Some(cursor) = self.history.redo(&mut self.screenplay) {
+                    self.apply_cursor_state(cursor);
+                }
             }
             Message::EventOccurred(event) => {
-                if let Some(task) = update::handle_event(event) {
+                if let Some(task) = update::handle_event(event, self.screen) {
                     return task;
                 }
             }
+            Message::OpenFile => {
+                return self.settle_unsaved(PendingAction::Open);
+            }
+            Message::NewFile => {
+                return self.settle_unsaved(PendingAction::New);
+            }
+            Message::Quit => {
+                return self.settle_unsaved(PendingAction::Quit);
+            }
+            Message::UnsavedChangesAnswered(action, choice) => match choice {
+                UnsavedChoice::Save if self.screenplay.file_path.is_none() => {
+                    self.pending = Some(action);
+                    return file::pick_save_path(self.screenplay.title.clone());
+                }
+                UnsavedChoice::Save => match self.save(None) {
+                    Ok(()) => return self.proceed(action),
+                    Err(e) => self.status = Some(e.to_string()),
+                },
+                UnsavedChoice::Discard => return self.proceed(action),
+                UnsavedChoice::Cancel => {}
+            },
+            Message::SaveFile => {
+                if self.screenplay.file_path.is_none() {
+                    return file::pick_save_path(self.screenplay.title.clone());
+                }
+                self.status = match self.save(None) {
+                    Ok(()) => Some(format!("Saved {}", self.screenplay.display_name())),
+                    Err(e) => Some(e.to_string()),
+                };
+            }
+            Message::SaveFileAs => {
+                return file::pick_save_path(self.screenplay.title.clone());
+            }
+            Message::Export(format) => {
+                return file::pick_export_path(format, self.screenplay.title.clone());
+            }
+            Message::ExportPathSelected(format, Some(path)) => {
+                self.status = match file::export(&self.screenplay, format, &path) {
+                    Ok(()) => Some(format!("Exported {}", path.display())),
+                    Err(e) => Some(e.to_string()),
+                };
+            }
+            Message::OpenPathSelected(Some(path)) => match file::open(&path) {
+                Ok((screenplay, warnings)) => {
+                    self.load_screenplay(screenplay);
+                    let opened = format!("Opened {}", self.screenplay.display_name());
+                    self.status = Some(if warnings.is_empty() {
+                        opened
+                    } else {
+                        format!("{} with warnings: {}", opened, warnings.join("; "))
+                    });
+                }
+                Err(e) => self.status = Some(e.to_string()),
+            },
+            Message::SavePathSelected(Some(path)) => {
+                let pending = self.pending.take();
+                match self.save(Some(&path)) {
+                    Ok(()) => {
+                        self.status = Some(format!("Saved {}", self.screenplay.display_name()));
+                        if let Some(action) = pending {
+                            return self.proceed(action);
+                        }
+                    }
+                    Err(e) => self.status = Some(e.to_string()),
+                }
+            }
+            Message::SavePathSelected(None) => self.pending = None,
+            Message::OpenPathSelected(None) | Message::ExportPathSelected(_, None) => {}
         }
         Task::none()
     }
 
+    /// Runs `action` once the script's unsaved changes, if any, have been
+    /// saved or discarded.
+    fn settle_unsaved(&mut self, action: PendingAction) -> Task<Message> {
+        if self.screenplay.modified {
+            return file::confirm_unsaved(action, self.screenplay.display_name());
+        }
+        self.proceed(action)
+    }
+
+    fn proceed(&mut self, action: PendingAction) -> Task<Message> {
+        match action {
+            PendingAction::Open => file::pick_open_path(),
+            PendingAction::New => {
+                self.load_screenplay(Screenplay::empty());
+                self.status = None;
+                Task::none()
+            }
+            PendingAction::Quit => iced::exit(),
+        }
+    }
+
     pub fn subscription(&self) -> Subscription<Message> {
         Subscription::batch(vec![
             iced::event::listen().map(Message::EventOccurred),
+            window::close_requests().map(|_| Message::Quit),
+            window::open_events().map(|_| Message::WindowResized),
+            window::resize_events().map(|_| Message::WindowResized),
             time::every(Duration::from_millis(500)).map(|_| Message::Tick),
         ])
     }
 }
+
+/// Measures the script canvas once the view has been laid out, for the
+/// viewport to scroll by.
+fn measure_editor() -> Task<Message> {
+    container::visible_bounds(container::Id::new(EDITOR_AREA))
+        .and_then(|bounds| Task::done(Message::ViewportResized(bounds.height)))
+}
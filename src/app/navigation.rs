@@ -1,25 +1,312 @@
-use crate::document::{Element, ElementType, Screenplay};
+use crate::document::{
+    Edit, Element, ElementType, Emphasis, Screenplay, Style, StyleSpan, TextPosition, TextRange, layout, style, text,
+};
+use unicode_segmentation::UnicodeSegmentation;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct CursorState {
     pub position: usize,
+    /// Byte offset into the element's content, always on a grapheme
+    /// cluster boundary.
     pub offset: usize,
+    /// Column, in grapheme clusters from the start of the wrapped line, that
+    /// vertical movement aims for.
     pub desired_offset: usize,
     pub element_type: ElementType,
+    /// The fixed end of the selection; the caret is the moving end.
+    pub anchor: Option<TextPosition>,
+    /// Emphasis toggled with nothing selected, given to the next text typed
+    /// at the caret instead of the style of the text before it.
+    pub typing_style: Option<Style>,
 }
 
 impl CursorState {
-    pub fn move_left(&mut self) {
-        if self.offset > 0 {
-            self.offset -= 1;
-            self.desired_offset = self.offset;
+    pub fn text_position(&self) -> TextPosition {
+        TextPosition::new(self.position, self.offset)
+    }
+
+    pub fn selection(&self) -> Option<TextRange> {
+        self.anchor
+            .map(|anchor| TextRange::new(anchor, self.text_position()))
+            .filter(|range| !range.is_empty())
+    }
+
+    pub fn move_to(&mut self, screenplay: &Screenplay, target: TextPosition) {
+        let Some(element) = screenplay.elements.get(target.element) else {
+            return;
+        };
+        self.position = target.element;
+        self.offset = text::floor_boundary(&element.content, target.offset);
+        self.element_type = element.element_type;
+        self.remember_column(screenplay);
+    }
+
+    fn remember_column(&mut self, screenplay: &Screenplay) {
+        if let Some(element) = screenplay.elements.get(self.position) {
+            let lines = layout::content_lines_at(screenplay, self.position);
+            let line = &lines[layout::line_at(&lines, self.offset)];
+            let offset = self.offset.max(line.start);
+            self.desired_offset = text::column(&element.content[line.start..], offset - line.start);
+        }
+    }
+
+    /// Moves to the start of the wrapped line the caret is on.
+    pub fn move_home(&mut self, screenplay: &Screenplay) {
+        if self.position < screenplay.elements.len() {
+            let lines = layout::content_lines_at(screenplay, self.position);
+            self.offset = lines[layout::line_at(&lines, self.offset)].start;
+            self.desired_offset = 0;
+        }
+    }
+
+    /// Moves to the end of the wrapped line the caret is on.
+    pub fn move_end(&mut self, screenplay: &Screenplay) {
+        if let Some(element) = screenplay.elements.get(self.position) {
+            let lines = layout::content_lines_at(screenplay, self.position);
+            let line = layout::line_at(&lines, self.offset);
+            self.offset = layout::offset_on_line(&element.content, &lines, line, usize::MAX);
+            self.remember_column(screenplay);
+        }
+    }
+
+    /// Selects the word around `target`, or just places the caret there
+    /// when it is not on a word.
+    pub fn select_word(&mut self, screenplay: &Screenplay, target: TextPosition) {
+        self.move_to(screenplay, target);
+        let Some(element) = screenplay.elements.get(self.position) else {
+            return;
+        };
+
+        let word = element
+            .content
+            .split_word_bound_indices()
+            .find(|&(start, word)| start <= self.offset && self.offset < start + word.len())
+            .filter(|(_, word)| word.chars().any(char::is_alphanumeric));
+
+        if let Some((start, word)) = word {
+            self.anchor = Some(TextPosition::new(self.position, start));
+            self.offset = start + word.len();
+            self.remember_column(screenplay);
+        }
+    }
+
+    /// Removes the selected text, joining the elements at either end of a
+    /// multi-element selection, and leaves the caret where it began.
+    pub fn delete_selection(&mut self, screenplay: &mut Screenplay) -> Vec<Edit> {
+        let Some(range) = self.selection() else {
+            self.anchor = None;
+            return Vec::new();
+        };
+        self.anchor = None;
+
+        let (start, end) = (range.start, range.end);
+        let Some(first) = screenplay.get_element(start.element) else {
+            return Vec::new();
+        };
+        let mut edits = Vec::new();
+
+        if start.element == end.element {
+            let removed = first.slice(start.offset..end.offset);
+            edits.push(perform(
+                screenplay,
+                Edit::DeleteText {
+                    index: start.element,
+                    offset: start.offset,
+                    text: removed.content,
+                    styles: removed.styles,
+                },
+            ));
+        } else {
+            let tail = screenplay
+                .get_element(end.element)
+                .map(|last| last.slice(end.offset.min(last.content.len())..last.content.len()));
+
+            let removed = first.slice(start.offset..first.content.len());
+            edits.push(perform(
+                screenplay,
+                Edit::DeleteText {
+                    index: start.element,
+                    offset: start.offset,
+                    text: removed.content,
+                    styles: removed.styles,
+                },
+            ));
+
+            for _ in start.element + 1..=end.element.min(screenplay.elements.len() - 1) {
+                let element = screenplay.elements[start.element + 1].clone();
+                edits.push(perform(
+                    screenplay,
+                    Edit::RemoveElement {
+                        index: start.element + 1,
+                        element,
+                    },
+                ));
+            }
+
+            if let Some(tail) = tail.filter(|tail| !tail.content.is_empty()) {
+                edits.push(perform(
+                    screenplay,
+                    Edit::InsertText {
+                        index: start.element,
+                        offset: start.offset,
+                        text: tail.content,
+                        styles: tail.styles,
+                    },
+                ));
+            }
+        }
+
+        self.move_to(screenplay, start);
+        edits
+    }
+
+    /// Inserts `elements` at the caret, replacing any selection. The first
+    /// element's text joins the current element and the rest of the current
+    /// element follows the last pasted one. An empty current element takes
+    /// the first pasted element's type.
+    pub fn paste(&mut self, screenplay: &mut Screenplay, elements: &[Element]) -> Vec<Edit> {
+        let mut edits = self.delete_selection(screenplay);
+        let Some((first, rest)) = elements.split_first() else {
+            return edits;
+        };
+        let Some(current) = screenplay.get_element(self.position) else {
+            return edits;
+        };
+        let index = self.position;
+        let current_type = current.element_type;
+        let was_empty = current.content.is_empty();
+        let tail = current.slice(self.offset..current.content.len());
+
+        if rest.is_empty() {
+            if !first.content.is_empty() && current_type != ElementType::PageBreak {
+                edits.push(perform(
+                    screenplay,
+                    Edit::InsertText {
+                        index,
+                        offset: self.offset,
+                        text: first.content.clone(),
+                        styles: first.styles.clone(),
+                    },
+                ));
+                self.offset += first.content.len();
+                self.remember_column(screenplay);
+            }
+            return edits;
+        }
+
+        if was_empty && current_type != first.element_type {
+            edits.push(perform(
+                screenplay,
+                Edit::SetElementType {
+                    index,
+                    from: current_type,
+                    to: first.element_type,
+                },
+            ));
+        }
+        if !tail.content.is_empty() {
+            edits.push(perform(
+                screenplay,
+                Edit::DeleteText {
+                    index,
+                    offset: self.offset,
+                    text: tail.content.clone(),
+                    styles: tail.styles.clone(),
+                },
+            ));
+        }
+        if !first.content.is_empty() {
+            edits.push(perform(
+                screenplay,
+                Edit::InsertText {
+                    index,
+                    offset: self.offset,
+                    text: first.content.clone(),
+                    styles: first.styles.clone(),
+                },
+            ));
+        }
+        for (n, element) in rest.iter().enumerate() {
+            edits.push(perform(
+                screenplay,
+                Edit::InsertElement {
+                    index: index + 1 + n,
+                    element: element.clone(),
+                },
+            ));
+        }
+
+        let last = index + rest.len();
+        let end = rest.last().map_or(0, |element| element.content.len());
+        if !tail.content.is_empty() {
+            edits.push(perform(
+                screenplay,
+                Edit::InsertText {
+                    index: last,
+                    offset: end,
+                    text: tail.content,
+                    styles: tail.styles,
+                },
+            ));
+        }
+
+        self.move_to(screenplay, TextPosition::new(last, end));
+        edits
+    }
+
+    /// Toggles `emphasis` across the selection, removing it only when all
+    /// of the selected text already has it. With nothing selected the
+    /// toggle applies to the text typed next.
+    pub fn toggle_emphasis(&mut self, screenplay: &mut Screenplay, emphasis: Emphasis) -> Vec<Edit> {
+        let Some(range) = self.selection() else {
+            let current = self
+                .typing_style
+                .or_else(|| screenplay.get_element(self.position).map(|e| e.style_before(self.offset)))
+                .unwrap_or_default();
+            self.typing_style = Some(current.with(emphasis, !current.has(emphasis)));
+            return Vec::new();
+        };
+        self.typing_style = None;
+
+        let spans: Vec<_> = (range.start.element..=range.end.element)
+            .filter_map(|index| {
+                let element = screenplay.get_element(index)?;
+                let (from, to) = range.span_in(index, element.content.len())?;
+                (from < to).then_some((index, from..to))
+            })
+            .collect();
+        let on = !spans.iter().all(|(index, span)| {
+            style::runs(&screenplay.elements[*index].styles, span.clone())
+                .iter()
+                .all(|(_, style)| style.has(emphasis))
+        });
+
+        let mut edits = Vec::new();
+        for (index, span) in spans {
+            let from = screenplay.elements[index].styles.clone();
+            let mut to = from.clone();
+            style::restyle(&mut to, span, |style| style.with(emphasis, on));
+            if to != from {
+                edits.push(perform(screenplay, Edit::SetStyles { index, from, to }));
+            }
+        }
+        edits
+    }
+
+    pub fn move_left(&mut self, screenplay: &Screenplay) {
+        if let Some(element) = screenplay.elements.get(self.position)
+            && self.offset > 0
+        {
+            self.offset = text::previous_boundary(&element.content, self.offset);
+            self.remember_column(screenplay);
         }
     }
 
     pub fn move_right(&mut self, screenplay: &Screenplay) {
         if let Some(element) = screenplay.elements.get(self.position) {
             if self.offset < element.content.len() {
-                self.offset += 1;
-                self.desired_offset = self.offset;
+                self.offset = text::next_boundary(&element.content, self.offset);
+                self.remember_column(screenplay);
             } else if self.position < screenplay.elements.len() - 1 {
                 self.position += 1;
                 self.offset = 0;
@@ -31,76 +318,200 @@ impl CursorState {
         }
     }
 
+    /// Moves to the wrapped line above, which may be the last line of the
+    /// previous element.
     pub fn move_up(&mut self, screenplay: &Screenplay) {
-        if self.position > 0 {
+        let Some(element) = screenplay.elements.get(self.position) else {
+            return;
+        };
+        let lines = layout::content_lines_at(screenplay, self.position);
+        let line = layout::line_at(&lines, self.offset);
+        if line > 0 {
+            self.offset = layout::offset_on_line(&element.content, &lines, line - 1, self.desired_offset);
+        } else if self.position > 0 {
             self.position -= 1;
-            if let Some(element) = screenplay.elements.get(self.position) {
-                self.offset = self.desired_offset.min(element.content.len());
-                self.element_type = element.element_type;
-            }
+            let element = &screenplay.elements[self.position];
+            let lines = layout::content_lines_at(screenplay, self.position);
+            self.offset = layout::offset_on_line(&element.content, &lines, lines.len() - 1, self.desired_offset);
+            self.element_type = element.element_type;
         }
     }
 
+    /// Moves to the wrapped line below, which may be the first line of the
+    /// next element.
     pub fn move_down(&mut self, screenplay: &Screenplay) {
-        if self.position < screenplay.elements.len() - 1 {
+        let Some(element) = screenplay.elements.get(self.position) else {
+            return;
+        };
+        let lines = layout::content_lines_at(screenplay, self.position);
+        let line = layout::line_at(&lines, self.offset);
+        if line + 1 < lines.len() {
+            self.offset = layout::offset_on_line(&element.content, &lines, line + 1, self.desired_offset);
+        } else if self.position + 1 < screenplay.elements.len() {
             self.position += 1;
-            if let Some(element) = screenplay.elements.get(self.position) {
-                self.offset = self.desired_offset.min(element.content.len());
-                self.element_type = element.element_type;
-            }
+            let element = &screenplay.elements[self.position];
+            let lines = layout::content_lines_at(screenplay, self.position);
+            self.offset = layout::offset_on_line(&element.content, &lines, 0, self.desired_offset);
+            self.element_type = element.element_type;
         }
     }
 
-    pub fn insert_char(&mut self, screenplay: &mut Screenplay, c: char) {
+    /// Gives the element at the caret a new type. A page break holds no
+    /// text, so an element becoming one loses its content in the same
+    /// edits, and undo brings the text back with the type.
+    pub fn set_element_type(&mut self, screenplay: &mut Screenplay, to: ElementType) -> Vec<Edit> {
+        self.element_type = to;
+        let Some(element) = screenplay.get_element(self.position) else {
+            return Vec::new();
+        };
+        let index = self.position;
+        let from = element.element_type;
+        let mut edits = Vec::new();
+        if to == ElementType::PageBreak && !element.content.is_empty() {
+            let removed = element.slice(0..element.content.len());
+            edits.push(perform(
+                screenplay,
+                Edit::DeleteText {
+                    index,
+                    offset: 0,
+                    text: removed.content,
+                    styles: removed.styles,
+                },
+            ));
+            self.offset = 0;
+            self.desired_offset = 0;
+        }
+        edits.push(perform(screenplay, Edit::SetElementType { index, from, to }));
+        edits
+    }
+
+    /// Whether text typed or pasted at the caret has anywhere to go: a page
+    /// break takes none.
+    fn takes_text(&self, screenplay: &Screenplay) -> bool {
+        let element_type = screenplay
+            .get_element(self.position)
+            .map_or(self.element_type, |element| element.element_type);
+        element_type != ElementType::PageBreak
+    }
+
+    pub fn insert_char(&mut self, screenplay: &mut Screenplay, c: char) -> Vec<Edit> {
+        self.insert_text(screenplay, c.encode_utf8(&mut [0; 4]))
+    }
+
+    pub fn insert_text(&mut self, screenplay: &mut Screenplay, inserted: &str) -> Vec<Edit> {
+        let mut edits = Vec::new();
+        if !self.takes_text(screenplay) {
+            return edits;
+        }
+
         if self.position >= screenplay.elements.len() {
             let element = Element::new(self.element_type, String::new());
-            screenplay.add_element(element);
+            edits.push(perform(
+                screenplay,
+                Edit::InsertElement {
+                    index: screenplay.elements.len(),
+                    element,
+                },
+            ));
         }
 
-        if let Some(element) = screenplay.elements.get_mut(self.position) {
-            element.content.insert(self.offset, c);
-            self.offset += 1;
-            self.desired_offset = self.offset;
+        if let Some(element) = screenplay.get_element(self.position) {
+            let style = self
+                .typing_style
+                .unwrap_or_else(|| element.style_before(self.offset));
+            let styles = if style.is_plain() {
+                Vec::new()
+            } else {
+                vec![StyleSpan::new(0..inserted.len(), style)]
+            };
+            edits.push(perform(
+                screenplay,
+                Edit::InsertText {
+                    index: self.position,
+                    offset: self.offset,
+                    text: inserted.to_string(),
+                    styles,
+                },
+            ));
+            // Text typed before a combining mark joins its cluster; the
+            // caret goes after the whole cluster.
+            let content = &screenplay.elements[self.position].content;
+            self.offset += inserted.len();
+            if text::floor_boundary(content, self.offset) != self.offset {
+                self.offset = text::next_boundary(content, self.offset);
+            }
+            self.remember_column(screenplay);
         }
+        edits
     }
 
-    pub fn backspace(&mut self, screenplay: &mut Screenplay) {
+    pub fn backspace(&mut self, screenplay: &mut Screenplay) -> Vec<Edit> {
+        let mut edits = Vec::new();
+
         if self.offset > 0 {
-            if let Some(element) = screenplay.elements.get_mut(self.position) {
-                self.offset -= 1;
-                element.content.remove(self.offset);
-                self.desired_offset = self.offset;
+            if let Some(element) = screenplay.get_element(self.position) {
+                let end = self.offset;
+                self.offset = text::previous_boundary(&element.content, end);
+                let removed = element.slice(self.offset..end);
+                edits.push(perform(
+                    screenplay,
+                    Edit::DeleteText {
+                        index: self.position,
+                        offset: self.offset,
+                        text: removed.content,
+                        styles: removed.styles,
+                    },
+                ));
+                self.remember_column(screenplay);
             }
         } else if self.position > 0 {
-            let current_is_empty = screenplay.elements
-                .get(self.position)
-                .map(|e| e.content.is_empty())
-                .unwrap_or(false);
-
-            if current_is_empty {
-                screenplay.elements.remove(self.position);
-                self.position -= 1;
-                if let Some(element) = screenplay.elements.get(self.position) {
-                    self.offset = element.content.len();
-                    self.element_type = element.element_type;
-                    self.desired_offset = self.offset;
-                }
-            } else {
-                self.position -= 1;
-                if let Some(element) = screenplay.elements.get(self.position) {
-                    self.offset = element.content.len();
-                    self.element_type = element.element_type;
-                    self.desired_offset = self.offset;
-                }
+            let current = screenplay
+                .get_element(self.position)
+                .filter(|e| e.content.is_empty())
+                .cloned();
+
+            if let Some(element) = current {
+                edits.push(perform(
+                    screenplay,
+                    Edit::RemoveElement {
+                        index: self.position,
+                        element,
+                    },
+                ));
             }
-        }
-    }
 
-    pub fn delete(&mut self, screenplay: &mut Screenplay) {
-        if let Some(element) = screenplay.elements.get_mut(self.position) {
-            if self.offset < element.content.len() {
-                element.content.remove(self.offset);
+            self.position -= 1;
+            if let Some(element) = screenplay.elements.get(self.position) {
+                self.offset = element.content.len();
+                self.element_type = element.element_type;
+                self.remember_column(screenplay);
             }
         }
+        edits
     }
+
+    pub fn delete(&mut self, screenplay: &mut Screenplay) -> Vec<Edit> {
+        let Some(removed) = screenplay
+            .get_element(self.position)
+            .map(|e| e.slice(self.offset..text::next_boundary(&e.content, self.offset)))
+            .filter(|removed| !removed.content.is_empty())
+        else {
+            return Vec::new();
+        };
+
+        vec![perform(
+            screenplay,
+            Edit::DeleteText {
+                index: self.position,
+                offset: self.offset,
+                text: removed.content,
+                styles: removed.styles,
+            },
+        )]
+    }
+}
+
+fn perform(screenplay: &mut Screenplay, edit: Edit) -> Edit {
+    edit.apply(screenplay);
+    edit
 }
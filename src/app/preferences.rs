@@ -0,0 +1,108 @@
+//! Settings that belong to the writer rather than to a script.
+//!
+//! The element flow is one: how Tab and Enter move between element types
+//! is a habit of the hand, so a change made to a template's flow applies
+//! to every script written with that template. Preferences are kept as
+//! JSON in the user's configuration directory.
+
+use crate::document::ElementType;
+use crate::document::flow::ElementFlow;
+use crate::document::template::Template;
+use crate::error::{Result, ScreenplayError};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+const FILE_NAME: &str = "preferences.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Preferences {
+    /// Flows that differ from the template's own.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    flows: Vec<(Template, ElementType, ElementFlow)>,
+}
+
+impl Preferences {
+    /// Reads the preferences at `path`, or the defaults when none have been
+    /// saved yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).map_err(|e| ScreenplayError::InvalidFormat(e.to_string())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn flow(&self, template: Template, element_type: ElementType) -> ElementFlow {
+        self.flows
+            .iter()
+            .find(|&&(changed_in, changed, _)| changed_in == template && changed == element_type)
+            .map_or_else(|| template.flow(element_type), |&(_, _, flow)| flow)
+    }
+
+    /// Gives `element_type` its own flow in `template`, or returns it to
+    /// the template's when `flow` is the same.
+    pub fn set_flow(&mut self, template: Template, element_type: ElementType, flow: ElementFlow) {
+        self.flows
+            .retain(|&(changed_in, changed, _)| changed_in != template || changed != element_type);
+        if flow != template.flow(element_type) {
+            self.flows.push((template, element_type, flow));
+        }
+    }
+
+    /// Whether any element type has a flow of its own in `template`.
+    pub fn is_customized(&self, template: Template) -> bool {
+        self.flows.iter().any(|&(changed_in, _, _)| changed_in == template)
+    }
+}
+
+/// Where preferences are kept: `quill/preferences.json` in the platform's
+/// configuration directory, or `None` when it cannot be found.
+pub fn path() -> Option<PathBuf> {
+    let env = |name| std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+    let config = if cfg!(windows) {
+        env("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        env("XDG_CONFIG_HOME").or_else(|| env("HOME").map(|home| home.join(".config")))
+    };
+    config.map(|config| config.join("quill").join(FILE_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flows_are_kept_per_template() {
+        let mut preferences = Preferences::default();
+        let flow = preferences.flow(Template::FeatureFilm, ElementType::Dialogue);
+        let changed = ElementFlow {
+            enter: ElementType::Character,
+            ..flow
+        };
+        preferences.set_flow(Template::FeatureFilm, ElementType::Dialogue, changed);
+        assert_eq!(preferences.flow(Template::FeatureFilm, ElementType::Dialogue), changed);
+        assert!(preferences.is_customized(Template::FeatureFilm));
+        assert!(!preferences.is_customized(Template::MultiCamera));
+
+        let path = std::env::temp_dir().join(format!("quill-preferences-{}.json", std::process::id()));
+        preferences.save(&path).unwrap();
+        assert_eq!(Preferences::load(&path).unwrap(), preferences);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(Preferences::load(&path).unwrap(), Preferences::default());
+
+        preferences.set_flow(Template::FeatureFilm, ElementType::Dialogue, flow);
+        assert_eq!(preferences, Preferences::default());
+    }
+}
@@ -1,11 +1,33 @@
+use super::Screen;
 use super::message::Message;
-use crate::document::ElementType;
-use iced::keyboard::Key;
+use crate::document::Emphasis;
 use iced::keyboard::key::Named;
+use iced::keyboard::{Key, Modifiers};
 use iced::{Event, Task, keyboard};
 
-pub fn handle_event(event: Event) -> Option<Task<Message>> {
-    if let Event::Keyboard(keyboard::Event::KeyPressed { key, text, .. }) = event {
+/// Turns keyboard events no widget used into editing messages. Keys go to
+/// the script only while it is on screen; elsewhere just the file
+/// shortcuts and Escape, which returns to the script, are handled.
+pub fn handle_event(event: Event, screen: Screen) -> Option<Task<Message>> {
+    if let Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) = event {
+        return Some(Task::done(Message::ModifiersChanged(modifiers)));
+    }
+
+    if let Event::Keyboard(keyboard::Event::KeyPressed {
+        key, modifiers, text, ..
+    }) = event
+    {
+        if modifiers.command() {
+            return handle_shortcut(&key, modifiers, screen);
+        }
+        if screen != Screen::Script {
+            return matches!(key, Key::Named(Named::Escape))
+                .then(|| Task::done(Message::ScreenSelected(Screen::Script)));
+        }
+        if let Key::Named(Named::Escape) = key {
+            return Some(Task::done(Message::EscapePressed));
+        }
+
         match key {
             Key::Named(Named::Tab) => {
                 return Some(Task::done(Message::TabPressed));
@@ -31,57 +53,71 @@ pub fn handle_event(event: Event) -> Option<Task<Message>> {
             Key::Named(Named::ArrowDown) => {
                 return Some(Task::done(Message::ArrowDown));
             }
+            Key::Named(Named::Home) => {
+                return Some(Task::done(Message::Home));
+            }
+            Key::Named(Named::End) => {
+                return Some(Task::done(Message::End));
+            }
+            Key::Named(Named::PageUp) => {
+                return Some(Task::done(Message::PageUp));
+            }
+            Key::Named(Named::PageDown) => {
+                return Some(Task::done(Message::PageDown));
+            }
             Key::Named(Named::Space) => {
                 return Some(Task::done(Message::CharacterTyped(' ')));
             }
             _ => {}
         }
 
-        if let Some(text) = text {
-            for ch in text.chars() {
-                return Some(Task::done(Message::CharacterTyped(ch)));
-            }
+        // Input methods and some layouts deliver several characters at once,
+        // e.g. an emoji with a skin tone modifier.
+        if let Some(text) = text.filter(|text| !text.is_empty() && !text.chars().any(char::is_control)) {
+            let mut chars = text.chars();
+            return match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(Task::done(Message::CharacterTyped(c))),
+                _ => Some(Task::done(Message::TextTyped(text.to_string()))),
+            };
         }
 
-        if let Key::Character(ref smol_str) = key {
-            if let Some(c) = smol_str.chars().next() {
-                return Some(Task::done(Message::CharacterTyped(c)));
-            }
+        if let Key::Character(ref smol_str) = key
+            && let Some(c) = smol_str.chars().next()
+        {
+            return Some(Task::done(Message::CharacterTyped(c)));
         }
     }
     None
 }
 
-pub fn next_element_type(current: ElementType) -> ElementType {
-    match current {
-        ElementType::Action => ElementType::SceneHeading,
-        ElementType::SceneHeading => ElementType::Character,
-        ElementType::Character => ElementType::Dialogue,
-        ElementType::Dialogue => ElementType::Parenthetical,
-        ElementType::Parenthetical => ElementType::Transition,
-        ElementType::Transition => ElementType::Action,
-    }
-}
+fn handle_shortcut(key: &Key, modifiers: Modifiers, screen: Screen) -> Option<Task<Message>> {
+    let Key::Character(c) = key else {
+        return None;
+    };
 
-pub fn detect_next_element_type(current: ElementType, text: &str) -> ElementType {
-    match current {
-        ElementType::SceneHeading => ElementType::Action,
-        ElementType::Character => ElementType::Dialogue,
-        ElementType::Dialogue => ElementType::Action,
-        ElementType::Parenthetical => ElementType::Dialogue,
-        ElementType::Action => {
-            if text
-                .chars()
-                .filter(|c| c.is_alphabetic())
-                .all(|c| c.is_uppercase())
-                && !text.starts_with("INT.")
-                && !text.starts_with("EXT.")
-            {
-                ElementType::Character
-            } else {
-                ElementType::Action
-            }
-        }
-        ElementType::Transition => ElementType::SceneHeading,
-    }
+    let message = match (c.to_lowercase().as_str(), modifiers.shift()) {
+        ("o", _) => Message::OpenFile,
+        ("n", false) => Message::NewFile,
+        ("q", false) => Message::Quit,
+        ("s", false) => Message::SaveFile,
+        ("s", true) => Message::SaveFileAs,
+        _ if screen != Screen::Script => return None,
+        ("z", false) => Message::Undo,
+        ("z", true) | ("y", false) => Message::Redo,
+        ("x", false) => Message::Cut,
+        ("c", false) => Message::Copy,
+        ("v", false) => Message::Paste,
+        ("b", false) => Message::ToggleEmphasis(Emphasis::Bold),
+        ("i", false) => Message::ToggleEmphasis(Emphasis::Italic),
+        ("u", false) => Message::ToggleEmphasis(Emphasis::Underline),
+        ("d", false) => Message::ToggleDualDialogue,
+        ("m", false) => Message::AddNote,
+        ("]", false) => Message::NextNote,
+        ("[", false) => Message::PreviousNote,
+        ("f", false) => Message::OpenFind,
+        ("g", false) => Message::FindNext,
+        ("g", true) => Message::FindPrevious,
+        _ => return None,
+    };
+    Some(Task::done(message))
 }
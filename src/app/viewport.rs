@@ -0,0 +1,114 @@
+use crate::ui::editor_layout::LINE_HEIGHT;
+
+/// Space kept between the caret and the edge of the view when following it.
+const CARET_MARGIN: f32 = 2.0 * LINE_HEIGHT;
+
+/// How the editor presents the script.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ViewMode {
+    /// One continuous column, without page breaks.
+    #[default]
+    Draft,
+    /// Paper pages as they will print.
+    Page,
+}
+
+impl ViewMode {
+    pub const ALL: [ViewMode; 2] = [ViewMode::Draft, ViewMode::Page];
+}
+
+impl std::fmt::Display for ViewMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ViewMode::Draft => "Draft",
+            ViewMode::Page => "Page",
+        })
+    }
+}
+
+/// The visible slice of the editor canvas, in layout coordinates.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Viewport {
+    /// Layout y shown at the top of the canvas.
+    pub offset: f32,
+    /// Height of the canvas on screen, 0 until the window has been laid
+    /// out and measured.
+    pub height: f32,
+    /// Keep the caret's line vertically centered.
+    pub typewriter: bool,
+}
+
+impl Viewport {
+    /// The offsets scrolling may reach for content `content_height` tall.
+    /// Typewriter mode can scroll half a view past either end so the first
+    /// and last lines can still be centered.
+    pub fn range(&self, content_height: f32) -> (f32, f32) {
+        if self.typewriter {
+            let half = self.height / 2.0;
+            (-half, (content_height - half).max(-half))
+        } else {
+            (0.0, (content_height - self.height).max(0.0))
+        }
+    }
+
+    pub fn scroll_to(&mut self, offset: f32, content_height: f32) {
+        let (min, max) = self.range(content_height);
+        self.offset = offset.clamp(min, max);
+    }
+
+    pub fn scroll_by(&mut self, delta: f32, content_height: f32) {
+        self.scroll_to(self.offset + delta, content_height);
+    }
+
+    /// Scrolls as little as needed to keep a line at `caret_y` in view, or
+    /// centers it in typewriter mode.
+    pub fn follow(&mut self, caret_y: f32, content_height: f32) {
+        let target = if self.typewriter {
+            caret_y + LINE_HEIGHT / 2.0 - self.height / 2.0
+        } else {
+            let margin = CARET_MARGIN.min((self.height - LINE_HEIGHT).max(0.0) / 2.0);
+            let top = caret_y - margin;
+            let bottom = caret_y + LINE_HEIGHT + margin - self.height;
+            self.offset.min(top).max(bottom)
+        };
+        self.scroll_to(target, content_height);
+    }
+
+    /// Distance PageUp and PageDown move: one view less a line of overlap.
+    pub fn page(&self) -> f32 {
+        (self.height - LINE_HEIGHT).max(LINE_HEIGHT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewport(typewriter: bool) -> Viewport {
+        Viewport {
+            offset: 0.0,
+            height: 200.0,
+            typewriter,
+        }
+    }
+
+    #[test]
+    fn test_follow_scrolls_only_when_needed() {
+        let mut view = viewport(false);
+        view.follow(100.0, 1000.0);
+        assert_eq!(view.offset, 0.0);
+
+        view.follow(500.0, 1000.0);
+        assert_eq!(view.offset, 500.0 + LINE_HEIGHT + CARET_MARGIN - 200.0);
+
+        view.follow(990.0, 1000.0);
+        assert_eq!(view.offset, 800.0);
+    }
+
+    #[test]
+    fn test_typewriter_centers_caret() {
+        let mut view = viewport(true);
+        view.follow(20.0, 1000.0);
+        assert_eq!(view.offset, 20.0 + LINE_HEIGHT / 2.0 - 100.0);
+    }
+}
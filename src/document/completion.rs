@@ -0,0 +1,228 @@
+//! Completions for character cues and scene headings.
+//!
+//! Names and locations are harvested from the script itself, so each only
+//! has to be typed out in full once. They are offered most frequent first,
+//! with ties going to the one used most recently before the element being
+//! typed. Once an opening parenthesis is typed in a cue the standard
+//! extensions are offered instead.
+//!
+//! Headings complete in stages: the prefix, then the location, then the
+//! time of day. A completion that ends a stage ends with the separator to
+//! the next, so the next stage can be offered straight away.
+
+use super::heading::{self, Heading};
+use super::pagination::CONTINUED;
+use super::{Element, ElementType};
+use std::collections::HashMap;
+
+/// Extensions offered after a name, in the order they are offered.
+pub const EXTENSIONS: [&str; 4] = ["(V.O.)", "(O.S.)", "(O.C.)", CONTINUED];
+
+/// Most completions offered at once.
+const MAX_COMPLETIONS: usize = 8;
+
+/// The name in a cue, without extensions: `MARY` for `Mary (V.O.)`.
+pub fn character_name(cue: &str) -> String {
+    let name = cue.find('(').map_or(cue, |paren| &cue[..paren]);
+    name.trim().to_uppercase()
+}
+
+/// Every character name in `elements`, most frequent first, ties broken
+/// by the cue closest before element `index`. The cue at `index` itself
+/// is left out, so a half-typed name is never offered back.
+pub fn characters(elements: &[Element], index: usize) -> Vec<String> {
+    rank(harvest(elements, index, ElementType::Character, character_name), index)
+}
+
+/// Every place, a location with any sub-location, used in the headings of
+/// `elements`, ranked like `characters`.
+pub fn places(elements: &[Element], index: usize) -> Vec<String> {
+    rank(
+        harvest(elements, index, ElementType::SceneHeading, |text| Heading::parse(text).place()),
+        index,
+    )
+}
+
+/// `part` of every element of `element_type` but the one at `index`,
+/// with the element it came from.
+fn harvest(
+    elements: &[Element],
+    index: usize,
+    element_type: ElementType,
+    part: impl Fn(&str) -> String,
+) -> Vec<(usize, String)> {
+    elements
+        .iter()
+        .enumerate()
+        .filter(|&(i, element)| element.element_type == element_type && i != index)
+        .map(|(i, element)| (i, part(&element.content)))
+        .filter(|(_, value)| !value.is_empty())
+        .collect()
+}
+
+/// Distinct values, most frequent first, ties going to the one closest
+/// before element `index`.
+fn rank(values: Vec<(usize, String)>, index: usize) -> Vec<String> {
+    let mut seen: HashMap<String, (usize, usize)> = HashMap::new();
+    for (i, value) in values {
+        // Values before `index` rank by closeness; later ones rank below
+        // all of them.
+        let recency = if i < index { i + 1 } else { 0 };
+        let entry = seen.entry(value).or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 = entry.1.max(recency);
+    }
+
+    let mut ranked: Vec<_> = seen.into_iter().collect();
+    ranked.sort_by(|(a, (a_count, a_recency)), (b, (b_count, b_recency))| {
+        b_count.cmp(a_count).then(b_recency.cmp(a_recency)).then(a.cmp(b))
+    });
+    ranked.into_iter().map(|(value, _)| value).collect()
+}
+
+/// Completed cues for the text typed so far into the cue at `index`, each
+/// to replace the whole of it.
+pub fn complete_cue(elements: &[Element], index: usize, typed: &str) -> Vec<String> {
+    let typed_upper = typed.to_uppercase();
+    if let Some(paren) = typed_upper.find('(') {
+        let name = typed_upper[..paren].trim();
+        let extension = typed_upper[paren..].trim_end();
+        if name.is_empty() {
+            return Vec::new();
+        }
+        return EXTENSIONS
+            .iter()
+            .filter(|candidate| candidate.starts_with(extension) && **candidate != extension)
+            .map(|candidate| format!("{} {}", name, candidate))
+            .collect();
+    }
+
+    let prefix = typed_upper.trim_start();
+    if prefix.is_empty() {
+        return Vec::new();
+    }
+    characters(elements, index)
+        .into_iter()
+        .filter(|name| name.starts_with(prefix) && name != prefix)
+        .take(MAX_COMPLETIONS)
+        .collect()
+}
+
+/// Completed headings for the text typed so far into the heading at
+/// `index`, each to replace the whole of it.
+pub fn complete_heading(elements: &[Element], index: usize, typed: &str) -> Vec<String> {
+    let typed = typed.trim_start().to_uppercase();
+    if typed.is_empty() {
+        return Vec::new();
+    }
+    let (prefix, rest) = heading::split_prefix(&typed);
+    let Some(prefix) = prefix.filter(|_| typed.contains(char::is_whitespace)) else {
+        // Still typing the prefix.
+        return heading::PREFIXES
+            .iter()
+            .filter(|candidate| candidate.starts_with(typed.as_str()) && **candidate != typed)
+            .map(|candidate| format!("{} ", candidate))
+            .collect();
+    };
+
+    let places = places(elements, index);
+    let mut completions: Vec<String> = places
+        .iter()
+        .filter(|place| place.starts_with(rest) && place.as_str() != rest && !rest.is_empty())
+        .map(|place| format!("{} {}{}", prefix, place, heading::SEPARATOR))
+        .collect();
+
+    // Past a separator the last part may be a time of day.
+    let marker = heading::SEPARATOR.trim_end();
+    if let Some(separator) = rest.rfind(marker) {
+        let before = rest[..separator].trim_end();
+        let time = rest[separator + marker.len()..].trim_start();
+        let used = harvest(elements, index, ElementType::SceneHeading, |text| {
+            Heading::parse(text).time.unwrap_or_default()
+        });
+        let mut times: Vec<String> = heading::TIMES.iter().map(|time| time.to_string()).collect();
+        for used in rank(used, index) {
+            if !times.contains(&used) {
+                times.push(used);
+            }
+        }
+        completions.extend(
+            times
+                .into_iter()
+                .filter(|candidate| candidate.starts_with(time) && candidate != time)
+                .map(|candidate| format!("{} {}{}{}", prefix, before, heading::SEPARATOR, candidate)),
+        );
+    }
+    completions.truncate(MAX_COMPLETIONS);
+    completions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cue(name: &str) -> Element {
+        Element::new(ElementType::Character, name.to_string())
+    }
+
+    #[test]
+    fn test_names_rank_by_frequency_then_recency() {
+        let elements = vec![
+            cue("MARTHA"),
+            cue("MARK"),
+            cue("Martha (V.O.)"),
+            cue("MARY"),
+            cue("MA"),
+            cue("MARK"),
+        ];
+        assert_eq!(characters(&elements, 4), ["MARTHA", "MARK", "MARY"]);
+        assert_eq!(complete_cue(&elements, 4, "ma"), ["MARTHA", "MARK", "MARY"]);
+        assert_eq!(complete_cue(&elements, 4, "Mary"), Vec::<String>::new());
+        assert_eq!(complete_cue(&elements, 6, "MAR"), ["MARK", "MARTHA", "MARY"]);
+    }
+
+    #[test]
+    fn test_extensions_follow_an_open_paren() {
+        let elements = vec![cue("MARY")];
+        assert_eq!(complete_cue(&elements, 1, "mary (o"), ["MARY (O.S.)", "MARY (O.C.)"]);
+        assert_eq!(complete_cue(&elements, 1, "MARY (").len(), EXTENSIONS.len());
+        assert!(complete_cue(&elements, 1, "MARY (V.O.)").is_empty());
+        assert!(complete_cue(&elements, 1, "(").is_empty());
+    }
+
+    #[test]
+    fn test_headings_complete_in_stages() {
+        let heading = |text: &str| Element::new(ElementType::SceneHeading, text.to_string());
+        let elements = vec![
+            heading("INT. HOUSE - KITCHEN - NIGHT"),
+            heading("EXT. HOUSE - DAY"),
+            heading("INT. HOUSE - KITCHEN - DAY"),
+            heading("INT. HOSPITAL - LOBBY - SUNDOWN"),
+            heading(""),
+        ];
+        assert_eq!(complete_heading(&elements, 4, "e"), ["EXT. ", "EXT./INT. "]);
+        assert_eq!(complete_heading(&elements, 4, "INT"), ["INT. ", "INT./EXT. "]);
+        assert_eq!(
+            complete_heading(&elements, 4, "int. ho"),
+            ["INT. HOUSE - KITCHEN - ", "INT. HOSPITAL - LOBBY - ", "INT. HOUSE - "]
+        );
+        assert_eq!(
+            complete_heading(&elements, 4, "int. house - "),
+            ["INT. HOUSE - KITCHEN - "]
+                .into_iter()
+                .map(str::to_string)
+                .chain(heading::TIMES.iter().map(|time| format!("INT. HOUSE - {}", time)))
+                .take(MAX_COMPLETIONS)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            complete_heading(&elements, 4, "INT. HOUSE - KITCHEN - d"),
+            ["INT. HOUSE - KITCHEN - DAY", "INT. HOUSE - KITCHEN - DAWN", "INT. HOUSE - KITCHEN - DUSK"]
+        );
+        assert_eq!(
+            complete_heading(&elements, 4, "INT. HOSPITAL - SUN"),
+            ["INT. HOSPITAL - SUNRISE", "INT. HOSPITAL - SUNSET", "INT. HOSPITAL - SUNDOWN"]
+        );
+        assert!(complete_heading(&elements, 4, "INT. HOUSE - DAY").is_empty());
+    }
+}
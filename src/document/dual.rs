@@ -0,0 +1,135 @@
+//! Dual dialogue: two speeches printed side by side, for characters who
+//! talk at once.
+//!
+//! A speech is a character cue and the parentheticals, dialogue and lyrics
+//! right after it. A cue marked `dual` prints its speech in the right-hand
+//! half of the line, beside the speech that ends just before it, which
+//! moves to the left-hand half. Only ordinary speeches pair up, so a dual
+//! cue after another dual speech, or with no speech before it, prints on
+//! its own.
+
+use super::layout::Column;
+use super::{Element, ElementType};
+
+fn is_speech_part(element: &Element) -> bool {
+    matches!(
+        element.element_type,
+        ElementType::Dialogue | ElementType::Parenthetical | ElementType::Lyrics
+    )
+}
+
+/// Index just past the speech led by the cue at `cue`.
+pub fn speech_end(elements: &[Element], cue: usize) -> usize {
+    elements[cue + 1..]
+        .iter()
+        .position(|element| !is_speech_part(element))
+        .map_or(elements.len(), |offset| cue + 1 + offset)
+}
+
+/// The cue leading the speech that element `index` belongs to.
+pub fn cue_of(elements: &[Element], index: usize) -> Option<usize> {
+    let cue = elements[..=index].iter().rposition(|element| !is_speech_part(element))?;
+    (elements[cue].element_type == ElementType::Character).then_some(cue)
+}
+
+/// The cue of the left-hand speech paired with the dual cue at `cue`.
+pub fn partner(elements: &[Element], cue: usize) -> Option<usize> {
+    if !elements[cue].dual || elements[cue].element_type != ElementType::Character || cue == 0 {
+        return None;
+    }
+    cue_of(elements, cue - 1).filter(|&left| !elements[left].dual)
+}
+
+/// Where element `index` prints.
+pub fn column_of(elements: &[Element], index: usize) -> Column {
+    let Some(cue) = cue_of(elements, index) else {
+        return Column::Full;
+    };
+    if partner(elements, cue).is_some() {
+        return Column::Right;
+    }
+    let end = speech_end(elements, cue);
+    if end < elements.len() && partner(elements, end) == Some(cue) {
+        Column::Left
+    } else {
+        Column::Full
+    }
+}
+
+/// Where every element prints.
+pub fn columns(elements: &[Element]) -> Vec<Column> {
+    (0..elements.len()).map(|index| column_of(elements, index)).collect()
+}
+
+/// The cue whose `dual` flag joins or splits the speech around element
+/// `index` and a neighbour: the right-hand cue of a pair it is already in,
+/// else its own cue when a speech ends just before it, else the cue of the
+/// speech just after it. `None` outside a speech or with no neighbour.
+pub fn toggle_target(elements: &[Element], index: usize) -> Option<usize> {
+    let cue = cue_of(elements, index)?;
+    let end = speech_end(elements, cue);
+    let next = (end < elements.len() && elements[end].element_type == ElementType::Character).then_some(end);
+    match column_of(elements, index) {
+        Column::Right => Some(cue),
+        Column::Left => next,
+        Column::Full => {
+            let after_speech = cue > 0 && cue_of(elements, cue - 1).is_some_and(|left| !elements[left].dual);
+            if after_speech {
+                Some(cue)
+            } else {
+                next.filter(|_| !elements[cue].dual)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Column::{Full, Left, Right};
+
+    fn element(element_type: ElementType, text: &str) -> Element {
+        Element::new(element_type, text.to_string())
+    }
+
+    fn script() -> Vec<Element> {
+        vec![
+            element(ElementType::Action, "They both talk."),
+            element(ElementType::Character, "ANN"),
+            element(ElementType::Dialogue, "Stop."),
+            element(ElementType::Character, "BOB"),
+            element(ElementType::Parenthetical, "shouting"),
+            element(ElementType::Dialogue, "No, you stop."),
+            element(ElementType::Character, "CAL"),
+            element(ElementType::Dialogue, "Both of you."),
+        ]
+    }
+
+    #[test]
+    fn test_columns_follow_the_dual_cue() {
+        let mut elements = script();
+        assert!(columns(&elements).iter().all(|&column| column == Column::Full));
+
+        elements[3].dual = true;
+        assert_eq!(columns(&elements), [Full, Left, Left, Right, Right, Right, Full, Full]);
+        assert_eq!(partner(&elements, 3), Some(1));
+
+        // A third speaker cannot join the pair.
+        elements[6].dual = true;
+        assert_eq!(column_of(&elements, 6), Full);
+        assert_eq!(partner(&elements, 6), None);
+    }
+
+    #[test]
+    fn test_toggle_targets() {
+        let mut elements = script();
+        assert_eq!(toggle_target(&elements, 0), None);
+        assert_eq!(toggle_target(&elements, 2), Some(3));
+        assert_eq!(toggle_target(&elements, 4), Some(3));
+
+        elements[3].dual = true;
+        assert_eq!(toggle_target(&elements, 2), Some(3));
+        assert_eq!(toggle_target(&elements, 5), Some(3));
+        assert_eq!(toggle_target(&elements, 7), None);
+    }
+}
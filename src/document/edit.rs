@@ -0,0 +1,229 @@
+use super::{Element, ElementType, Note, Screenplay, StyleSpan};
+
+/// A single reversible change to a screenplay. Every edit made through the
+/// editor is expressed as one of these so it can be undone, redone and
+/// observed by anything that tracks positions in the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    /// Inserts `text` carrying exactly `styles`, measured from `offset`.
+    InsertText {
+        index: usize,
+        offset: usize,
+        text: String,
+        styles: Vec<StyleSpan>,
+    },
+    /// Removes `text`, remembering its `styles` so undo restores them.
+    DeleteText {
+        index: usize,
+        offset: usize,
+        text: String,
+        styles: Vec<StyleSpan>,
+    },
+    InsertElement {
+        index: usize,
+        element: Element,
+    },
+    RemoveElement {
+        index: usize,
+        element: Element,
+    },
+    SetElementType {
+        index: usize,
+        from: ElementType,
+        to: ElementType,
+    },
+    /// Replaces an element's emphasis without touching its text.
+    SetStyles {
+        index: usize,
+        from: Vec<StyleSpan>,
+        to: Vec<StyleSpan>,
+    },
+    /// Pairs a character cue's speech with the one before it, or splits
+    /// the pair.
+    SetDual {
+        index: usize,
+        from: bool,
+        to: bool,
+    },
+    /// Numbers a scene heading, or takes its number away.
+    SetSceneNumber {
+        index: usize,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    AddNote {
+        note: Note,
+    },
+    RemoveNote {
+        note: Note,
+    },
+    /// Replaces a note's text, author, color or resolution; `from` and
+    /// `to` share an id.
+    SetNote {
+        from: Note,
+        to: Note,
+    },
+}
+
+impl Edit {
+    pub fn inverse(&self) -> Edit {
+        match self.clone() {
+            Edit::InsertText {
+                index,
+                offset,
+                text,
+                styles,
+            } => Edit::DeleteText {
+                index,
+                offset,
+                text,
+                styles,
+            },
+            Edit::DeleteText {
+                index,
+                offset,
+                text,
+                styles,
+            } => Edit::InsertText {
+                index,
+                offset,
+                text,
+                styles,
+            },
+            Edit::InsertElement { index, element } => Edit::RemoveElement { index, element },
+            Edit::RemoveElement { index, element } => Edit::InsertElement { index, element },
+            Edit::SetElementType { index, from, to } => Edit::SetElementType {
+                index,
+                from: to,
+                to: from,
+            },
+            Edit::SetStyles { index, from, to } => Edit::SetStyles {
+                index,
+                from: to,
+                to: from,
+            },
+            Edit::SetDual { index, from, to } => Edit::SetDual {
+                index,
+                from: to,
+                to: from,
+            },
+            Edit::SetSceneNumber { index, from, to } => Edit::SetSceneNumber {
+                index,
+                from: to,
+                to: from,
+            },
+            Edit::AddNote { note } => Edit::RemoveNote { note },
+            Edit::RemoveNote { note } => Edit::AddNote { note },
+            Edit::SetNote { from, to } => Edit::SetNote { from: to, to: from },
+        }
+    }
+
+    pub fn apply(&self, screenplay: &mut Screenplay) {
+        match self {
+            Edit::InsertText {
+                index,
+                offset,
+                text,
+                styles,
+            } => {
+                if let Some(element) = screenplay.get_element_mut(*index) {
+                    element.insert_text(*offset, text, styles);
+                }
+            }
+            Edit::DeleteText {
+                index, offset, text, ..
+            } => {
+                if let Some(element) = screenplay.get_element_mut(*index) {
+                    element.delete_text(*offset..*offset + text.len());
+                }
+            }
+            Edit::InsertElement { index, element } => {
+                screenplay.insert_element(*index, element.clone());
+            }
+            Edit::RemoveElement { index, .. } => {
+                screenplay.remove_element(*index);
+            }
+            Edit::SetElementType { index, to, .. } => {
+                if let Some(element) = screenplay.get_element_mut(*index) {
+                    element.element_type = *to;
+                }
+            }
+            Edit::SetStyles { index, to, .. } => {
+                if let Some(element) = screenplay.get_element_mut(*index) {
+                    element.styles = to.clone();
+                }
+            }
+            Edit::SetDual { index, to, .. } => {
+                if let Some(element) = screenplay.get_element_mut(*index) {
+                    element.dual = *to;
+                }
+            }
+            Edit::SetSceneNumber { index, to, .. } => {
+                if let Some(element) = screenplay.get_element_mut(*index) {
+                    element.scene_number = to.clone();
+                }
+            }
+            Edit::AddNote { note } => {
+                screenplay.insert_note(note.clone());
+            }
+            Edit::RemoveNote { note } => {
+                screenplay.remove_note(note.id);
+            }
+            Edit::SetNote { to, .. } => {
+                screenplay.update_note(to.id, |note| *note = to.clone());
+            }
+        }
+
+        for note in &mut screenplay.notes {
+            note.follow(self, &screenplay.elements);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Style;
+
+    #[test]
+    fn test_inverse_restores_content() {
+        let mut screenplay = Screenplay::empty();
+        screenplay.add_element(Element::new(ElementType::Action, "Hello".to_string()));
+
+        let edit = Edit::InsertText {
+            index: 0,
+            offset: 5,
+            text: " there".to_string(),
+            styles: Vec::new(),
+        };
+        edit.apply(&mut screenplay);
+        assert_eq!(screenplay.elements[0].content, "Hello there");
+
+        edit.inverse().apply(&mut screenplay);
+        assert_eq!(screenplay.elements[0].content, "Hello");
+    }
+
+    #[test]
+    fn test_undoing_a_delete_restores_emphasis() {
+        let bold = Style {
+            bold: true,
+            ..Style::PLAIN
+        };
+        let mut screenplay = Screenplay::empty();
+        screenplay.add_element(
+            Element::new(ElementType::Action, "Say no.".to_string()).with_styles(vec![StyleSpan::new(4..6, bold)]),
+        );
+
+        let edit = Edit::DeleteText {
+            index: 0,
+            offset: 3,
+            text: " no".to_string(),
+            styles: screenplay.elements[0].styles_in(3..6),
+        };
+        edit.apply(&mut screenplay);
+        assert!(screenplay.elements[0].styles.is_empty());
+
+        edit.inverse().apply(&mut screenplay);
+        assert_eq!(screenplay.elements[0].styles, vec![StyleSpan::new(4..6, bold)]);
+    }
+}
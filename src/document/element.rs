@@ -1,23 +1,62 @@
+use super::layout::ElementFormat;
+use super::style::{self, Style, StyleSpan};
 use serde::{Deserialize, Serialize};
+use std::ops::Range;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ElementType {
     SceneHeading,
+    #[default]
     Action,
     Character,
     Dialogue,
     Parenthetical,
     Transition,
+    /// A camera direction such as `CLOSE ON THE KEY`.
+    Shot,
+    /// Text centered on the page, such as `THE END`.
+    Centered,
+    /// Sung lines, printed in italics by the standard format.
+    Lyrics,
+    /// Forces the next element onto a new page; prints nothing itself.
+    PageBreak,
+    /// Outline headers that structure the script in the editor but never
+    /// print.
+    Act,
+    Sequence,
+    Section,
 }
 
 impl ElementType {
+    pub const ALL: [ElementType; 13] = [
+        ElementType::SceneHeading,
+        ElementType::Action,
+        ElementType::Character,
+        ElementType::Dialogue,
+        ElementType::Parenthetical,
+        ElementType::Transition,
+        ElementType::Shot,
+        ElementType::Centered,
+        ElementType::Lyrics,
+        ElementType::PageBreak,
+        ElementType::Act,
+        ElementType::Sequence,
+        ElementType::Section,
+    ];
+
+    /// Whether the standard format prints this type in capitals.
     pub fn is_uppercase(&self) -> bool {
         matches!(
             self,
-            ElementType::SceneHeading | ElementType::Character | ElementType::Transition
+            ElementType::SceneHeading | ElementType::Character | ElementType::Transition | ElementType::Shot
         )
     }
 
+    /// Whether this is an outline header, which never prints.
+    pub fn is_structural(&self) -> bool {
+        matches!(self, ElementType::Act | ElementType::Sequence | ElementType::Section)
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
             ElementType::SceneHeading => "Scene Heading",
@@ -26,20 +65,39 @@ impl ElementType {
             ElementType::Dialogue => "Dialogue",
             ElementType::Parenthetical => "Parenthetical",
             ElementType::Transition => "Transition",
+            ElementType::Shot => "Shot",
+            ElementType::Centered => "Centered",
+            ElementType::Lyrics => "Lyrics",
+            ElementType::PageBreak => "Page Break",
+            ElementType::Act => "Act",
+            ElementType::Sequence => "Sequence",
+            ElementType::Section => "Section",
         }
     }
 }
 
-impl Default for ElementType {
-    fn default() -> Self {
-        ElementType::Action
+impl std::fmt::Display for ElementType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Element {
     pub element_type: ElementType,
     pub content: String,
+    /// Inline emphasis over byte ranges of `content`, kept normalised by
+    /// the functions in `style`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub styles: Vec<StyleSpan>,
+    /// On a character cue, prints this speech beside the one before it as
+    /// dual dialogue. See `dual`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dual: bool,
+    /// On a scene heading, the production number printed in both
+    /// margins. See `scene_number`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scene_number: Option<String>,
 }
 
 impl Element {
@@ -47,23 +105,83 @@ impl Element {
         Self {
             element_type,
             content,
+            styles: Vec::new(),
+            dual: false,
+            scene_number: None,
         }
     }
 
+    pub fn with_styles(mut self, styles: Vec<StyleSpan>) -> Self {
+        self.styles = styles;
+        self
+    }
+
     pub fn empty(element_type: ElementType) -> Self {
         Self::new(element_type, String::new())
     }
 
+    /// Whether the element has nothing to print or write out. A page break
+    /// never has text but is never empty.
     pub fn is_empty(&self) -> bool {
-        self.content.trim().is_empty()
+        self.element_type != ElementType::PageBreak && self.content.trim().is_empty()
     }
 
     pub fn formatted_content(&self) -> String {
-        if self.element_type.is_uppercase() {
-            self.content.to_uppercase()
-        } else {
-            self.content.clone()
-        }
+        self.formatted_content_with(&ElementFormat::for_type(self.element_type))
+    }
+
+    /// The content as displayed in `format`, such as a style sheet's.
+    pub fn formatted_content_with(&self, format: &ElementFormat) -> String {
+        self.formatted_runs(0..self.content.len(), format)
+            .into_iter()
+            .map(|(text, _)| text)
+            .collect()
+    }
+
+    /// The text of `range` as displayed in `format`, split into runs of
+    /// one style. Case is changed run by run, so a character whose
+    /// uppercase form is longer cannot shift text across a style boundary.
+    pub fn formatted_runs(&self, range: Range<usize>, format: &ElementFormat) -> Vec<(String, Style)> {
+        style::runs(&self.styles, range)
+            .into_iter()
+            .map(|(run, style)| {
+                let text = &self.content[run];
+                let text = if format.uppercase {
+                    text.to_uppercase()
+                } else {
+                    text.to_string()
+                };
+                (text, style.union(format.style))
+            })
+            .collect()
+    }
+
+    /// Style that text typed at `offset` picks up: that of the character
+    /// before it, or of the first character at the start.
+    pub fn style_before(&self, offset: usize) -> Style {
+        style::style_at(&self.styles, offset.saturating_sub(1))
+    }
+
+    /// The styles of the text in `range`, measured from its start.
+    pub fn styles_in(&self, range: Range<usize>) -> Vec<StyleSpan> {
+        style::slice(&self.styles, range)
+    }
+
+    /// Copies the text in `range` with its emphasis.
+    pub fn slice(&self, range: Range<usize>) -> Element {
+        Element::new(self.element_type, self.content[range.clone()].to_string()).with_styles(self.styles_in(range))
+    }
+
+    /// Inserts `text` at `offset`, styled exactly by `styles` (measured
+    /// from `offset`).
+    pub fn insert_text(&mut self, offset: usize, text: &str, styles: &[StyleSpan]) {
+        self.content.insert_str(offset, text);
+        style::insert(&mut self.styles, offset, text.len(), styles);
+    }
+
+    pub fn delete_text(&mut self, range: Range<usize>) {
+        self.content.replace_range(range.clone(), "");
+        style::remove(&mut self.styles, range);
     }
 }
 
@@ -78,6 +196,26 @@ mod tests {
         assert_eq!(element.content, "Test");
     }
 
+    #[test]
+    fn test_uppercasing_keeps_span_boundaries() {
+        let bold = Style {
+            bold: true,
+            ..Style::PLAIN
+        };
+        let element = Element::new(ElementType::SceneHeading, "ext. straße".to_string())
+            .with_styles(vec![StyleSpan::new(5..11, bold)]);
+        let format = ElementFormat::for_type(ElementType::SceneHeading);
+        assert_eq!(
+            element.formatted_runs(0..element.content.len(), &format),
+            vec![
+                ("EXT. ".to_string(), Style::PLAIN),
+                ("STRASS".to_string(), bold),
+                ("E".to_string(), Style::PLAIN),
+            ]
+        );
+        assert_eq!(element.formatted_content_with(&format), "EXT. STRASSE");
+    }
+
     #[test]
     fn test_uppercase_detection() {
         assert!(ElementType::SceneHeading.is_uppercase());
@@ -0,0 +1,549 @@
+//! Import and export of Final Draft (`.fdx`) XML.
+//!
+//! Only the script body and title page are read, with the bold, italic
+//! and underline styles of the body's text runs. Formatting settings,
+//! headers and footers are skipped silently; anything that carries script
+//! content Quill cannot represent yet is reported as a warning.
+//!
+//! Dual dialogue is a `DualDialogue` element inside an otherwise empty
+//! paragraph, holding both speeches in order; the second cue in it is the
+//! one marked `dual`.
+//!
+//! Centered text is a centered Action paragraph and a page break is the
+//! `StartsNewPage` flag on the paragraph after it. Acts are `New Act`
+//! paragraphs; sequences and sections have no Final Draft type and are
+//! written under their own names, which Quill reads back.
+//!
+//! A scene heading's number is the `Number` attribute of its paragraph.
+
+use super::title_page::{DEFAULT_CREDIT, TitleField};
+use super::style::{self, Emphasis, Style, StyleSpan};
+use super::layout::Column;
+use super::{Element, ElementType, Screenplay, dual, scene_number};
+use crate::error::{Result, ScreenplayError};
+use quick_xml::Reader;
+use quick_xml::Writer;
+use quick_xml::events::{BytesDecl, BytesStart, BytesText, Event};
+use std::fmt::Display;
+use std::io;
+use std::ops::Range;
+
+/// Marks a paragraph that starts a new page.
+const NEW_PAGE: (&str, &str) = ("StartsNewPage", "Yes");
+
+pub struct Import {
+    pub screenplay: Screenplay,
+    pub warnings: Vec<String>,
+}
+
+struct Paragraph {
+    kind: Option<String>,
+    centered: bool,
+    text: String,
+    styles: Vec<StyleSpan>,
+    in_title_page: bool,
+    dual_dialogue: bool,
+    /// Starts a new page, read as a page break before it.
+    new_page: bool,
+    number: Option<String>,
+}
+
+pub fn parse(xml: &str) -> Result<Import> {
+    let mut reader = Reader::from_str(xml);
+    let mut warnings = Vec::new();
+    let mut elements = Vec::new();
+    let mut title_page = Vec::new();
+    let mut paragraphs: Vec<Paragraph> = Vec::new();
+    let mut saw_root = false;
+    let mut closed_root = false;
+    let mut in_title_page = false;
+    let mut in_text = false;
+    let mut text_style = Style::PLAIN;
+    // Character cues read so far inside a `DualDialogue`.
+    let mut dual_cues: Option<usize> = None;
+
+    loop {
+        match reader.read_event().map_err(invalid)? {
+            Event::Start(e) => match e.name().as_ref() {
+                b"FinalDraft" => saw_root = true,
+                b"Content" => {}
+                b"TitlePage" => in_title_page = true,
+                b"Paragraph" => paragraphs.push(Paragraph::new(&e, in_title_page)?),
+                b"Text" => {
+                    in_text = true;
+                    text_style = Style::PLAIN;
+                    for name in attribute(&e, "Style")?.iter().flat_map(|style| style.split('+')) {
+                        match emphasis(name) {
+                            Some(emphasis) => text_style = text_style.with(emphasis, true),
+                            None if name.is_empty() => {}
+                            None => warn(
+                                &mut warnings,
+                                "Text styles other than bold, italic and underline were dropped",
+                            ),
+                        }
+                    }
+                }
+                b"DualDialogue" => {
+                    if let Some(paragraph) = paragraphs.last_mut() {
+                        paragraph.dual_dialogue = true;
+                        if paragraph.new_page && !paragraph.in_title_page {
+                            elements.push(Element::empty(ElementType::PageBreak));
+                        }
+                    }
+                    dual_cues = Some(0);
+                }
+                name => {
+                    if matches!(name, b"ScriptNote" | b"ScriptNotes") {
+                        warn(&mut warnings, "Script notes were not imported");
+                    }
+                    reader.read_to_end(e.to_end().name()).map_err(invalid)?;
+                }
+            },
+            Event::Empty(e) if e.name().as_ref() == b"Paragraph" => {
+                let paragraph = Paragraph::new(&e, in_title_page)?;
+                paragraph.finish(&mut elements, &mut title_page, &mut warnings);
+            }
+            Event::End(e) => match e.name().as_ref() {
+                b"FinalDraft" => closed_root = true,
+                b"TitlePage" => in_title_page = false,
+                b"Text" => in_text = false,
+                b"DualDialogue" => dual_cues = None,
+                b"Paragraph" => {
+                    if let Some(paragraph) = paragraphs.pop() {
+                        let count = elements.len();
+                        paragraph.finish(&mut elements, &mut title_page, &mut warnings);
+                        if let Some(cues) = dual_cues.as_mut()
+                            && elements.len() > count
+                            && let Some(cue) = elements.last_mut()
+                            && cue.element_type == ElementType::Character
+                        {
+                            *cues += 1;
+                            cue.dual = *cues > 1;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            Event::Text(text) if in_text => {
+                if let Some(paragraph) = paragraphs.last_mut() {
+                    paragraph.push_text(&text.unescape().map_err(invalid)?, text_style);
+                }
+            }
+            Event::CData(text) if in_text => {
+                if let Some(paragraph) = paragraphs.last_mut() {
+                    paragraph.push_text(&String::from_utf8_lossy(&text), text_style);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !saw_root {
+        return Err(ScreenplayError::InvalidFormat(
+            "missing <FinalDraft> root element".to_string(),
+        ));
+    }
+    if !closed_root {
+        return Err(ScreenplayError::InvalidFormat(
+            "unexpected end of file inside <FinalDraft>".to_string(),
+        ));
+    }
+
+    let mut screenplay = Screenplay::empty();
+    apply_title_page(&mut screenplay, title_page, &mut warnings);
+    screenplay.elements = elements;
+    Ok(Import {
+        screenplay,
+        warnings,
+    })
+}
+
+pub fn write(screenplay: &Screenplay) -> Result<String> {
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), Some("no"))))?;
+
+    writer
+        .create_element("FinalDraft")
+        .with_attributes([("DocumentType", "Script"), ("Template", "No"), ("Version", "5")])
+        .write_inner_content(|writer| {
+            writer.create_element("Content").write_inner_content(|writer| {
+                let elements = &screenplay.elements;
+                let mut index = 0;
+                let mut new_page = false;
+                while index < elements.len() {
+                    if elements[index].element_type == ElementType::PageBreak {
+                        new_page = true;
+                        index += 1;
+                        continue;
+                    }
+                    if dual::column_of(elements, index) != Column::Left {
+                        write_element(writer, &elements[index], new_page)?;
+                        new_page = false;
+                        index += 1;
+                        continue;
+                    }
+                    let end = dual::speech_end(elements, dual::speech_end(elements, index));
+                    let paragraph = writer.create_element("Paragraph");
+                    let paragraph = if new_page {
+                        paragraph.with_attribute(NEW_PAGE)
+                    } else {
+                        paragraph
+                    };
+                    paragraph.write_inner_content(|writer| {
+                        writer.create_element("DualDialogue").write_inner_content(|writer| {
+                            elements[index..end]
+                                .iter()
+                                .try_for_each(|element| write_element(writer, element, false))
+                        })?;
+                        Ok(())
+                    })?;
+                    new_page = false;
+                    index = end;
+                }
+                // A page break at the very end needs a paragraph to carry it.
+                if new_page {
+                    write_element(writer, &Element::empty(ElementType::Action), true)?;
+                }
+                Ok(())
+            })?;
+
+            writer.create_element("TitlePage").write_inner_content(|writer| {
+                writer
+                    .create_element("Content")
+                    .write_inner_content(|writer| write_title_page(writer, screenplay))?;
+                Ok(())
+            })?;
+            Ok(())
+        })?;
+
+    String::from_utf8(writer.into_inner()).map_err(invalid)
+}
+
+impl Paragraph {
+    fn new(start: &BytesStart, in_title_page: bool) -> Result<Self> {
+        Ok(Self {
+            kind: attribute(start, "Type")?,
+            centered: attribute(start, "Alignment")?.as_deref() == Some("Center"),
+            text: String::new(),
+            styles: Vec::new(),
+            in_title_page,
+            dual_dialogue: false,
+            new_page: attribute(start, NEW_PAGE.0)?.as_deref() == Some(NEW_PAGE.1),
+            number: attribute(start, "Number")?.filter(|number| !number.trim().is_empty()),
+        })
+    }
+
+    fn push_text(&mut self, text: &str, style: Style) {
+        let start = self.text.len();
+        self.text.push_str(text);
+        if !style.is_plain() {
+            style::restyle(&mut self.styles, start..self.text.len(), |_| style);
+        }
+    }
+
+    fn finish(
+        self,
+        elements: &mut Vec<Element>,
+        title_page: &mut Vec<(String, bool)>,
+        warnings: &mut Vec<String>,
+    ) {
+        if self.dual_dialogue {
+            return;
+        }
+
+        if self.in_title_page {
+            if !self.text.trim().is_empty() {
+                title_page.push((self.text.trim().to_string(), self.centered));
+            }
+            return;
+        }
+
+        if self.new_page {
+            elements.push(Element::empty(ElementType::PageBreak));
+        }
+        let kind = self.kind.as_deref().unwrap_or("Action");
+        let element_type = match element_type(kind) {
+            Some(ElementType::Action) if self.centered => ElementType::Centered,
+            Some(element_type) => element_type,
+            None => {
+                warn(
+                    warnings,
+                    &format!("Paragraph type \"{}\" is not supported and was imported as Action", kind),
+                );
+                ElementType::Action
+            }
+        };
+
+        let range = match element_type {
+            ElementType::Parenthetical => parenthetical_range(&self.text),
+            _ => 0..self.text.len(),
+        };
+        let mut element = Element::new(element_type, self.text).with_styles(self.styles).slice(range);
+        if element_type == ElementType::SceneHeading {
+            element.scene_number = self.number;
+        }
+        elements.push(element);
+    }
+}
+
+fn element_type(paragraph_type: &str) -> Option<ElementType> {
+    match paragraph_type {
+        "Scene Heading" => Some(ElementType::SceneHeading),
+        "Action" | "General" => Some(ElementType::Action),
+        "Character" => Some(ElementType::Character),
+        "Dialogue" => Some(ElementType::Dialogue),
+        "Parenthetical" => Some(ElementType::Parenthetical),
+        "Transition" => Some(ElementType::Transition),
+        "Shot" => Some(ElementType::Shot),
+        "Lyrics" => Some(ElementType::Lyrics),
+        "New Act" => Some(ElementType::Act),
+        "Sequence" => Some(ElementType::Sequence),
+        "Section" => Some(ElementType::Section),
+        _ => None,
+    }
+}
+
+fn paragraph_type(element_type: ElementType) -> &'static str {
+    match element_type {
+        ElementType::SceneHeading => "Scene Heading",
+        ElementType::Action => "Action",
+        ElementType::Character => "Character",
+        ElementType::Dialogue => "Dialogue",
+        ElementType::Parenthetical => "Parenthetical",
+        ElementType::Transition => "Transition",
+        ElementType::Shot => "Shot",
+        ElementType::Lyrics => "Lyrics",
+        ElementType::Act => "New Act",
+        ElementType::Sequence => "Sequence",
+        ElementType::Section => "Section",
+        // Told apart by their alignment and page flag instead.
+        ElementType::Centered | ElementType::PageBreak => "Action",
+    }
+}
+
+/// The part of a parenthetical's text inside its parentheses, or all of
+/// it when it has none.
+fn parenthetical_range(text: &str) -> Range<usize> {
+    let trimmed = text.trim();
+    match trimmed.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+        Some(inner) => {
+            let start = text.len() - text.trim_start().len() + 1;
+            start..start + inner.len()
+        }
+        None => 0..text.len(),
+    }
+}
+
+fn emphasis(style: &str) -> Option<Emphasis> {
+    match style {
+        "Bold" => Some(Emphasis::Bold),
+        "Italic" => Some(Emphasis::Italic),
+        "Underline" => Some(Emphasis::Underline),
+        _ => None,
+    }
+}
+
+/// The `Style` attribute for `style`, e.g. `Bold+Underline`.
+fn style_attribute(style: Style) -> String {
+    [
+        (Emphasis::Bold, "Bold"),
+        (Emphasis::Italic, "Italic"),
+        (Emphasis::Underline, "Underline"),
+    ]
+    .into_iter()
+    .filter(|&(emphasis, _)| style.has(emphasis))
+    .map(|(_, name)| name)
+    .collect::<Vec<_>>()
+    .join("+")
+}
+
+/// Final Draft title pages are free-form paragraphs, so fields are
+/// recovered by position: the first centered line is the title, an
+/// optional "... by" credit line precedes the author, further centered
+/// `Key: value` lines are metadata and left-aligned lines are contact
+/// details.
+fn apply_title_page(
+    screenplay: &mut Screenplay,
+    lines: Vec<(String, bool)>,
+    warnings: &mut Vec<String>,
+) {
+    let (centered, contact): (Vec<_>, Vec<_>) = lines.into_iter().partition(|(_, c)| *c);
+    let mut centered = centered.into_iter().map(|(text, _)| text).peekable();
+
+    if let Some(title) = centered.next() {
+        screenplay.title = title;
+    }
+
+    if let Some(line) = centered.next_if(|line| key_value(line).is_none()) {
+        let is_credit = line.to_lowercase().ends_with("by");
+        match centered.next_if(|next| is_credit && key_value(next).is_none()) {
+            Some(author) => {
+                if !line.eq_ignore_ascii_case(DEFAULT_CREDIT) {
+                    screenplay.metadata.push((TitleField::Credit.key().to_string(), line));
+                }
+                screenplay.author = Some(author);
+            }
+            None => screenplay.author = Some(line),
+        }
+    }
+
+    for line in centered {
+        match key_value(&line) {
+            Some((key, value)) => screenplay.metadata.push((key.to_string(), value.to_string())),
+            None => warn(warnings, &format!("Title page line \"{}\" was not imported", line)),
+        }
+    }
+
+    if !contact.is_empty() {
+        let lines: Vec<String> = contact.into_iter().map(|(text, _)| text).collect();
+        screenplay.contact = Some(lines.join("\n"));
+    }
+}
+
+fn write_title_page(writer: &mut Writer<Vec<u8>>, screenplay: &Screenplay) -> io::Result<()> {
+    let centered = [("Alignment", "Center"), ("Type", "Title Page")];
+    let left = [("Alignment", "Left"), ("Type", "Title Page")];
+
+    write_paragraph(writer, &centered, &screenplay.title)?;
+
+    if let Some(author) = &screenplay.author {
+        let credit = screenplay.title_field(TitleField::Credit).unwrap_or(DEFAULT_CREDIT);
+        write_paragraph(writer, &centered, "")?;
+        write_paragraph(writer, &centered, credit)?;
+        write_paragraph(writer, &centered, author)?;
+    }
+
+    for (key, value) in &screenplay.metadata {
+        if !TitleField::Credit.matches(key) {
+            write_paragraph(writer, &centered, &format!("{}: {}", key, value))?;
+        }
+    }
+
+    if let Some(contact) = &screenplay.contact {
+        write_paragraph(writer, &left, "")?;
+        for line in contact.lines() {
+            write_paragraph(writer, &left, line)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_element(writer: &mut Writer<Vec<u8>>, element: &Element, new_page: bool) -> io::Result<()> {
+    let mut runs: Vec<(String, Style)> = style::runs(&element.styles, 0..element.content.len())
+        .into_iter()
+        .map(|(range, style)| (element.content[range].to_string(), style))
+        .collect();
+    if element.element_type == ElementType::Parenthetical {
+        runs.insert(0, ("(".to_string(), Style::PLAIN));
+        runs.push((")".to_string(), Style::PLAIN));
+    }
+    let mut attributes = vec![("Type", paragraph_type(element.element_type))];
+    if element.element_type == ElementType::Centered {
+        attributes.push(("Alignment", "Center"));
+    }
+    if new_page {
+        attributes.push(NEW_PAGE);
+    }
+    if let Some(number) = scene_number::printed(element) {
+        attributes.push(("Number", number));
+    }
+    write_runs(writer, &attributes, &runs)
+}
+
+fn write_paragraph(
+    writer: &mut Writer<Vec<u8>>,
+    attributes: &[(&str, &str)],
+    text: &str,
+) -> io::Result<()> {
+    write_runs(writer, attributes, &[(text.to_string(), Style::PLAIN)])
+}
+
+/// A paragraph with one `Text` element per run; neighbouring runs of the
+/// same style are joined.
+fn write_runs(
+    writer: &mut Writer<Vec<u8>>,
+    attributes: &[(&str, &str)],
+    runs: &[(String, Style)],
+) -> io::Result<()> {
+    let mut merged: Vec<(String, Style)> = Vec::new();
+    for (text, style) in runs.iter().filter(|(text, _)| !text.is_empty()) {
+        match merged.last_mut() {
+            Some((last, last_style)) if last_style == style => last.push_str(text),
+            _ => merged.push((text.clone(), *style)),
+        }
+    }
+    if merged.is_empty() {
+        merged.push((String::new(), Style::PLAIN));
+    }
+
+    writer
+        .create_element("Paragraph")
+        .with_attributes(attributes.iter().copied())
+        .write_inner_content(|writer| {
+            for (text, style) in &merged {
+                let element = writer.create_element("Text");
+                let element = if style.is_plain() {
+                    element
+                } else {
+                    element.with_attribute(("Style", style_attribute(*style).as_str()))
+                };
+                element.write_text_content(BytesText::new(text))?;
+            }
+            Ok(())
+        })?;
+    Ok(())
+}
+
+fn key_value(line: &str) -> Option<(&str, &str)> {
+    line.split_once(": ")
+        .filter(|(key, _)| !key.is_empty() && key.chars().all(|c| c.is_alphabetic() || c == ' '))
+}
+
+fn attribute(start: &BytesStart, name: &str) -> Result<Option<String>> {
+    match start.try_get_attribute(name).map_err(invalid)? {
+        Some(attribute) => Ok(Some(attribute.unescape_value().map_err(invalid)?.into_owned())),
+        None => Ok(None),
+    }
+}
+
+fn warn(warnings: &mut Vec<String>, message: &str) {
+    if !warnings.iter().any(|w| w == message) {
+        warnings.push(message.to_string());
+    }
+}
+
+fn invalid(error: impl Display) -> ScreenplayError {
+    ScreenplayError::InvalidFormat(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paragraph_type_mapping_is_symmetric() {
+        for kind in [
+            ElementType::SceneHeading,
+            ElementType::Action,
+            ElementType::Character,
+            ElementType::Dialogue,
+            ElementType::Parenthetical,
+            ElementType::Transition,
+            ElementType::Shot,
+            ElementType::Lyrics,
+            ElementType::Act,
+            ElementType::Sequence,
+            ElementType::Section,
+        ] {
+            assert_eq!(element_type(paragraph_type(kind)), Some(kind));
+        }
+    }
+
+    #[test]
+    fn test_parenthetical_range_skips_padding() {
+        let text = "  (beat)  ";
+        assert_eq!(&text[parenthetical_range(text)], "beat");
+        assert_eq!(parenthetical_range("beat"), 0..4);
+    }
+}
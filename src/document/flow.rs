@@ -0,0 +1,106 @@
+//! Which element follows which while typing.
+//!
+//! Tab steps the element being edited on to another type, and Enter starts
+//! a new element whose type depends on the one just finished. Both come
+//! from a table each template fills in and the writer may change for every
+//! script using it; see `app::Preferences::flow`.
+//!
+//! Action is what an element is typed as when nothing else fits, so an
+//! action line is looked at again when Enter finishes it: one that opens
+//! with a scene heading prefix, ends a transition with `TO:`, or names a
+//! character already speaking in the script is retyped to match.
+
+use super::{Element, ElementType, completion, heading};
+use serde::{Deserialize, Serialize};
+
+/// The types that follow one element type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ElementFlow {
+    /// What Tab turns the element into.
+    pub tab: ElementType,
+    /// What the element Enter starts after it is.
+    pub enter: ElementType,
+}
+
+impl ElementFlow {
+    /// The flow of the standard feature film template. Tab cycles through
+    /// every type, starting from the common ones.
+    pub fn for_type(element_type: ElementType) -> Self {
+        let (tab, enter) = match element_type {
+            ElementType::Action => (ElementType::SceneHeading, ElementType::Action),
+            ElementType::SceneHeading => (ElementType::Character, ElementType::Action),
+            ElementType::Character => (ElementType::Dialogue, ElementType::Dialogue),
+            ElementType::Dialogue => (ElementType::Parenthetical, ElementType::Action),
+            ElementType::Parenthetical => (ElementType::Transition, ElementType::Dialogue),
+            ElementType::Transition => (ElementType::Shot, ElementType::SceneHeading),
+            ElementType::Shot => (ElementType::Centered, ElementType::Action),
+            ElementType::Centered => (ElementType::Lyrics, ElementType::Action),
+            ElementType::Lyrics => (ElementType::PageBreak, ElementType::Lyrics),
+            ElementType::PageBreak => (ElementType::Act, ElementType::Action),
+            ElementType::Act => (ElementType::Sequence, ElementType::SceneHeading),
+            ElementType::Sequence => (ElementType::Section, ElementType::SceneHeading),
+            ElementType::Section => (ElementType::Action, ElementType::SceneHeading),
+        };
+        Self { tab, enter }
+    }
+}
+
+/// Whether `text` is a transition such as `CUT TO:`.
+pub fn is_transition(text: &str) -> bool {
+    let text = text.trim();
+    text.ends_with("TO:") && !text.chars().any(char::is_lowercase)
+}
+
+/// Whether `text` has letters and all of them are capitals.
+pub fn is_all_caps(text: &str) -> bool {
+    text.chars().any(char::is_alphabetic) && !text.chars().any(char::is_lowercase)
+}
+
+/// The type the action line at `index` reads as, or `None` when it reads
+/// as action. A line in capitals is only taken for a cue when it names
+/// someone who already speaks elsewhere in the script, so a sound like
+/// `BOOM!` stays action.
+pub fn detect(elements: &[Element], index: usize) -> Option<ElementType> {
+    let element = &elements[index];
+    if element.element_type != ElementType::Action {
+        return None;
+    }
+    let text = element.content.trim();
+    if let (Some(_), rest) = heading::split_prefix(text)
+        && !rest.is_empty()
+    {
+        return Some(ElementType::SceneHeading);
+    }
+    if is_transition(text) {
+        return Some(ElementType::Transition);
+    }
+    let name = completion::character_name(text);
+    (is_all_caps(text) && completion::characters(elements, index).contains(&name)).then_some(ElementType::Character)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detected(known: &[&str], line: &str) -> Option<ElementType> {
+        let mut elements: Vec<Element> = known
+            .iter()
+            .map(|name| Element::new(ElementType::Character, name.to_string()))
+            .collect();
+        elements.push(Element::new(ElementType::Action, line.to_string()));
+        detect(&elements, elements.len() - 1)
+    }
+
+    #[test]
+    fn test_detects_headings_transitions_and_known_names() {
+        assert_eq!(detected(&[], "int. house - day"), Some(ElementType::SceneHeading));
+        assert_eq!(detected(&[], "I/E. CAR - MOVING"), Some(ElementType::SceneHeading));
+        assert_eq!(detected(&[], "EST. CITY SKYLINE"), Some(ElementType::SceneHeading));
+        assert_eq!(detected(&[], "SMASH CUT TO:"), Some(ElementType::Transition));
+        assert_eq!(detected(&["NORA"], "NORA (V.O.)"), Some(ElementType::Character));
+
+        assert_eq!(detected(&["NORA"], "BOOM!"), None);
+        assert_eq!(detected(&["NORA"], "Nora waits."), None);
+        assert_eq!(detected(&[], "INT."), None);
+    }
+}
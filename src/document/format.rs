@@ -0,0 +1,177 @@
+use super::{Screenplay, style};
+use crate::error::{Result, ScreenplayError};
+use serde_json::{Map, Value, json};
+
+/// Version written by this build. Bump it whenever the on-disk shape of
+/// `Screenplay` changes and add a matching step to `migrate`.
+pub const FORMAT_VERSION: u64 = 7;
+
+pub const FILE_EXTENSION: &str = "quill";
+
+const VERSION_KEY: &str = "format_version";
+const SCREENPLAY_KEY: &str = "screenplay";
+
+pub fn encode(screenplay: &Screenplay) -> Result<String> {
+    let document = json!({
+        VERSION_KEY: FORMAT_VERSION,
+        SCREENPLAY_KEY: serde_json::to_value(screenplay)?,
+    });
+    Ok(serde_json::to_string_pretty(&document)?)
+}
+
+pub fn decode(json: &str) -> Result<Screenplay> {
+    let value: Value = serde_json::from_str(json)?;
+    let (version, screenplay) = split_envelope(value)?;
+
+    if version > FORMAT_VERSION {
+        return Err(ScreenplayError::InvalidFormat(format!(
+            "file format version {} is newer than this build supports ({})",
+            version, FORMAT_VERSION
+        )));
+    }
+
+    let screenplay = migrate(screenplay, version)?;
+    let mut screenplay: Screenplay =
+        serde_json::from_value(screenplay).map_err(|e| ScreenplayError::InvalidFormat(e.to_string()))?;
+    // Spans are byte offsets, so a hand-edited or damaged file could
+    // otherwise slice text mid-character.
+    for element in &mut screenplay.elements {
+        style::sanitize(&mut element.styles, &element.content);
+    }
+    Ok(screenplay)
+}
+
+/// Files written before the envelope existed are a bare `Screenplay`
+/// object and are treated as version 0.
+fn split_envelope(value: Value) -> Result<(u64, Value)> {
+    let Value::Object(mut object) = value else {
+        return Err(ScreenplayError::InvalidFormat(
+            "expected a JSON object at the top level".to_string(),
+        ));
+    };
+
+    match object.remove(VERSION_KEY) {
+        Some(version) => {
+            let version = version.as_u64().ok_or_else(|| {
+                ScreenplayError::InvalidFormat(format!("`{}` must be an integer", VERSION_KEY))
+            })?;
+            let screenplay = object.remove(SCREENPLAY_KEY).ok_or_else(|| {
+                ScreenplayError::InvalidFormat(format!("missing `{}` section", SCREENPLAY_KEY))
+            })?;
+            Ok((version, screenplay))
+        }
+        None => Ok((0, Value::Object(object))),
+    }
+}
+
+fn migrate(mut screenplay: Value, from: u64) -> Result<Value> {
+    for version in from..FORMAT_VERSION {
+        let object = screenplay.as_object_mut().ok_or_else(|| {
+            ScreenplayError::InvalidFormat("screenplay section must be an object".to_string())
+        })?;
+
+        match version {
+            0 => migrate_v0_to_v1(object),
+            1 => migrate_v1_to_v2(object),
+            2 => migrate_v2_to_v3(object),
+            3 => migrate_v3_to_v4(object),
+            4 => migrate_v4_to_v5(object),
+            5 => migrate_v5_to_v6(object),
+            6 => migrate_v6_to_v7(object),
+            _ => {
+                return Err(ScreenplayError::InvalidFormat(format!(
+                    "no migration from file format version {}",
+                    version
+                )));
+            }
+        }
+    }
+    Ok(screenplay)
+}
+
+/// Version 0 and 1 share the same screenplay layout; only the envelope
+/// was introduced.
+fn migrate_v0_to_v1(_screenplay: &mut Map<String, Value>) {}
+
+/// Version 2 adds optional emphasis spans to elements; a version 1 file
+/// simply has none. The bump keeps older builds from opening a styled
+/// script and silently dropping its emphasis on the next save.
+fn migrate_v1_to_v2(_screenplay: &mut Map<String, Value>) {}
+
+/// Version 3 adds script notes, which older files have none of.
+fn migrate_v2_to_v3(_screenplay: &mut Map<String, Value>) {}
+
+/// Version 4 adds the dual dialogue flag to character cues; older files
+/// have no dual dialogue.
+fn migrate_v3_to_v4(_screenplay: &mut Map<String, Value>) {}
+
+/// Version 5 adds shots, centered text, lyrics, page breaks and outline
+/// headers, none of which an older file uses. The bump makes older builds
+/// report such a file as too new rather than as having an unknown element
+/// type.
+fn migrate_v4_to_v5(_screenplay: &mut Map<String, Value>) {}
+
+/// Version 6 adds the style sheet; an older file is laid out with the
+/// feature film template, which is what the default sheet holds.
+fn migrate_v5_to_v6(_screenplay: &mut Map<String, Value>) {}
+
+/// Version 7 adds scene numbers and the scene lock; an older script has
+/// unnumbered, unlocked scenes.
+fn migrate_v6_to_v7(_screenplay: &mut Map<String, Value>) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_file_loads() {
+        let legacy = r#"{
+            "title": "Old Script",
+            "author": null,
+            "contact": null,
+            "elements": [{ "element_type": "Action", "content": "Hello." }]
+        }"#;
+
+        let screenplay = decode(legacy).unwrap();
+        assert_eq!(screenplay.title, "Old Script");
+        assert_eq!(screenplay.element_count(), 1);
+    }
+
+    #[test]
+    fn test_malformed_style_spans_are_repaired() {
+        let bold = json!({ "bold": true, "italic": false, "underline": false });
+        let italic = json!({ "bold": false, "italic": true, "underline": false });
+        let file = json!({
+            VERSION_KEY: FORMAT_VERSION,
+            SCREENPLAY_KEY: {
+                "title": "Damaged",
+                "author": null,
+                "contact": null,
+                "elements": [{
+                    "element_type": "Action",
+                    "content": "Café noir",
+                    "styles": [
+                        { "start": 4, "end": 40, "style": italic },
+                        { "start": 0, "end": 4, "style": bold },
+                        { "start": 9, "end": 2, "style": bold },
+                    ],
+                }],
+            },
+        })
+        .to_string();
+
+        let screenplay = decode(&file).unwrap();
+        let element = &screenplay.elements[0];
+        let ranges: Vec<_> = element.styles.iter().map(|span| span.range()).collect();
+        assert_eq!(ranges, [0..3, 3..10]);
+        for range in ranges {
+            assert!(element.content.get(range).is_some());
+        }
+    }
+
+    #[test]
+    fn test_newer_version_rejected() {
+        let future = json!({ VERSION_KEY: FORMAT_VERSION + 1, SCREENPLAY_KEY: {} }).to_string();
+        assert!(matches!(decode(&future), Err(ScreenplayError::InvalidFormat(_))));
+    }
+}
@@ -0,0 +1,723 @@
+//! Import and export of the Fountain plain-text screenplay format.
+//!
+//! Centered text (`> THE END <`), lyrics (`~`), page breaks (`===`) and
+//! sections (`#` for an act, `##` for a sequence, `###` below that) map to
+//! their own element types. Fountain has no syntax for shots, so they are
+//! written as forced action, `!CLOSE ON THE KEY`, which other apps show as
+//! a plain line. Only such a forced line opening with a camera direction
+//! reads back as a shot; an uppercase line like `INSERT COIN` written
+//! without the `!` stays action. Constructs Quill has no element
+//! type for (deeper sections, synopses, boneyard) are kept verbatim as
+//! Action elements so they survive a round trip.
+//!
+//! Emphasis is written with Fountain's markers: `*italic*`, `**bold**`,
+//! `***bold italic***` and `_underline_`. Literal markers are escaped as
+//! `\*` and `\_`. Character names and the verbatim constructs above carry
+//! no emphasis.
+//!
+//! Open script notes are written as inline `[[notes]]` right after the
+//! text they are anchored to, or on a line of their own when that text is a
+//! character cue. Inline notes read back as notes on their whole element,
+//! and notes on lines of their own as notes on the element after them.
+//!
+//! A cue ending in `^` marks dual dialogue, as in `BOB ^`: its speech
+//! prints beside the one before it.
+//!
+//! Scene numbers follow their heading between hashes, as in
+//! `INT. HOUSE - DAY #12A#`.
+
+use super::title_page::TitleField;
+use super::style::{self, Emphasis, Style, StyleSpan};
+use super::flow::is_transition;
+use super::{Element, ElementType, Note, Screenplay, TextPosition, TextRange, heading, scene_number};
+
+/// Camera directions a line must open with to read as a shot.
+const SHOT_PREFIXES: &[&str] = &[
+    "ANGLE ON", "CLOSE ON", "CLOSE UP", "CLOSE-UP", "CLOSER ANGLE", "EXTREME CLOSE", "INSERT", "POV", "WIDE ON",
+    "WIDE SHOT", "MEDIUM SHOT", "TRACKING", "AERIAL", "REVERSE ANGLE", "BACK TO SCENE", "OVER THE SHOULDER",
+];
+
+/// Title page keys in the order they are written; any other keys follow in
+/// the order they were read.
+const TITLE_PAGE_ORDER: &[&str] = &[
+    "Title", "Credit", "Author", "Source", "Draft date", "Revision", "Copyright", "WGA", "Contact",
+];
+
+/// A title page must open with one of these keys, so that a script starting
+/// with something like `CUT TO:` is not mistaken for one.
+const TITLE_PAGE_KEYS: &[&str] = &[
+    "title", "credit", "author", "authors", "source", "draft date", "date", "contact", "copyright",
+    "notes", "revision", "wga",
+];
+
+pub fn parse(text: &str) -> Screenplay {
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    let lines: Vec<&str> = text.lines().collect();
+
+    let mut screenplay = Screenplay::empty();
+    let body_start = parse_title_page(&lines, &mut screenplay);
+    let body = parse_body(&lines[body_start..]);
+    screenplay.elements = body.elements;
+    for (id, (index, text)) in (1..).zip(body.notes) {
+        // Notes after the last element stay with it.
+        let index = index.min(screenplay.elements.len().saturating_sub(1));
+        let Some(element) = screenplay.elements.get(index) else {
+            break;
+        };
+        let end = element.content.len();
+        let anchor = TextRange::new(TextPosition::new(index, 0), TextPosition::new(index, end));
+        screenplay.notes.push(Note {
+            text,
+            ..Note::new(id, anchor, String::new(), 0)
+        });
+    }
+    screenplay
+}
+
+pub fn write(screenplay: &Screenplay) -> String {
+    let mut blocks = Vec::new();
+    if let Some(title_page) = write_title_page(screenplay) {
+        blocks.push(title_page);
+    }
+    blocks.extend(write_body(&screenplay.elements, &screenplay.notes));
+
+    let mut output = blocks.join("\n\n");
+    output.push('\n');
+    output
+}
+
+/// Writes a run of elements without a title page, as used for the text put
+/// on the system clipboard.
+pub fn write_elements(elements: &[Element]) -> String {
+    let mut output = write_body(elements, &[]).join("\n\n");
+    output.push('\n');
+    output
+}
+
+fn write_body(elements: &[Element], notes: &[Note]) -> Vec<String> {
+    let notes_on = |index: usize| -> Vec<&Note> {
+        notes
+            .iter()
+            .filter(|note| !note.resolved && !note.text.trim().is_empty() && note.anchor.end.element == index)
+            .collect()
+    };
+    let written: Vec<usize> = (0..elements.len()).filter(|&i| !elements[i].is_empty()).collect();
+    let mut blocks = Vec::new();
+    // First element whose standalone notes are not written yet.
+    let mut next = 0;
+    let mut position = 0;
+    while position < written.len() {
+        let index = written[position];
+        let element = &elements[index];
+        let end = if element.element_type == ElementType::Character {
+            written[position + 1..]
+                .iter()
+                .position(|&i| !is_dialogue_part(elements[i].element_type))
+                .map_or(written.len(), |offset| position + 1 + offset)
+        } else {
+            position + 1
+        };
+        let last = written[end - 1];
+
+        // Blank elements and character cues have no text for a note to sit
+        // in, so their notes go on lines of their own before the block.
+        let standalone = (next..=last).filter(|&i| {
+            elements[i].is_empty()
+                || matches!(elements[i].element_type, ElementType::Character | ElementType::PageBreak)
+        });
+        blocks.extend(standalone.flat_map(&notes_on).map(|note| note_markup(&note.text)));
+
+        if element.element_type == ElementType::Character {
+            let mut block = vec![write_character(element, end > position + 1)];
+            block.extend(written[position + 1..end].iter().map(|&i| write_element(&elements[i], &notes_on(i))));
+            blocks.push(block.join("\n"));
+        } else {
+            blocks.push(write_element(element, &notes_on(index)));
+        }
+        next = last + 1;
+        position = end;
+    }
+    blocks.extend((next..elements.len()).flat_map(notes_on).map(|note| note_markup(&note.text)));
+    blocks
+}
+
+pub fn is_scene_heading(line: &str) -> bool {
+    let (prefix, rest) = heading::split_prefix(line.trim());
+    prefix.is_some_and(|prefix| prefix.ends_with('.') || !rest.is_empty())
+}
+
+fn is_shot(line: &str) -> bool {
+    let line = line.trim();
+    !line.chars().any(char::is_lowercase)
+        && SHOT_PREFIXES.iter().any(|prefix| {
+            line.strip_prefix(prefix)
+                .is_some_and(|rest| !rest.starts_with(|c: char| c.is_alphanumeric()))
+        })
+}
+
+/// The section type for `#`, `##` or `###` at the start of `line`.
+fn section_type(line: &str) -> Option<ElementType> {
+    match line.len() - line.trim_start_matches('#').len() {
+        1 => Some(ElementType::Act),
+        2 => Some(ElementType::Sequence),
+        3 => Some(ElementType::Section),
+        _ => None,
+    }
+}
+
+fn section_marker(element_type: ElementType) -> &'static str {
+    match element_type {
+        ElementType::Act => "#",
+        ElementType::Sequence => "##",
+        _ => "###",
+    }
+}
+
+fn is_page_break(line: &str) -> bool {
+    line.len() >= 3 && line.chars().all(|c| c == '=')
+}
+
+/// The text of a centered line, `> THE END <`.
+fn centered(line: &str) -> Option<&str> {
+    Some(line.trim().strip_prefix('>')?.strip_suffix('<')?.trim())
+}
+
+/// The text of a lyric line, `~Row, row, row your boat`.
+fn lyric(line: &str) -> Option<&str> {
+    line.trim().strip_prefix('~').map(str::trim_start)
+}
+
+fn is_character(line: &str) -> bool {
+    let name = line.split('(').next().unwrap_or_default().trim();
+    name.chars().any(char::is_alphabetic) && !name.chars().any(char::is_lowercase)
+}
+
+/// Lines Quill keeps as-is rather than mapping to an element type.
+fn is_passthrough(line: &str) -> bool {
+    line.starts_with('#')
+        || line.starts_with('=')
+        || line.starts_with('~')
+        || line.starts_with("[[")
+        || (line.starts_with('>') && line.ends_with('<'))
+}
+
+fn is_dialogue_part(element_type: ElementType) -> bool {
+    matches!(
+        element_type,
+        ElementType::Dialogue | ElementType::Parenthetical | ElementType::Lyrics
+    )
+}
+
+fn parse_title_page(lines: &[&str], screenplay: &mut Screenplay) -> usize {
+    let mut entries: Vec<(String, String)> = Vec::new();
+    let mut index = 0;
+
+    while let Some(line) = lines.get(index) {
+        if line.trim().is_empty() {
+            break;
+        }
+
+        if line.starts_with("   ") || line.starts_with('\t') {
+            let Some((_, value)) = entries.last_mut() else {
+                return 0;
+            };
+            if !value.is_empty() {
+                value.push('\n');
+            }
+            value.push_str(line.trim());
+        } else {
+            let Some((key, value)) = line.split_once(':') else {
+                return 0;
+            };
+            if key.is_empty() || !key.chars().all(|c| c.is_alphabetic() || c == ' ') {
+                return 0;
+            }
+            if entries.is_empty() && !TITLE_PAGE_KEYS.contains(&key.trim().to_lowercase().as_str()) {
+                return 0;
+            }
+            entries.push((key.trim().to_string(), value.trim().to_string()));
+        }
+        index += 1;
+    }
+
+    if entries.is_empty() {
+        return 0;
+    }
+
+    for (key, value) in entries {
+        match key.to_lowercase().as_str() {
+            "title" => screenplay.title = value,
+            "author" | "authors" => screenplay.author = Some(value),
+            "contact" => screenplay.contact = Some(value),
+            _ => screenplay.metadata.push((key, value)),
+        }
+    }
+    index
+}
+
+/// Elements read so far, with the inline notes found in their text.
+#[derive(Default)]
+struct Body {
+    elements: Vec<Element>,
+    /// Element index and text of each `[[note]]`. A note on a line of its
+    /// own carries the index of the element after it.
+    notes: Vec<(usize, String)>,
+}
+
+impl Body {
+    /// Adds an element whose text may carry emphasis markup and notes.
+    fn push_styled(&mut self, element_type: ElementType, text: &str) {
+        let (text, notes) = take_notes(text);
+        let index = self.elements.len();
+        self.notes.extend(notes.into_iter().map(|note| (index, note)));
+        let (content, styles) = parse_emphasis(&text);
+        self.elements.push(Element::new(element_type, content).with_styles(styles));
+    }
+}
+
+fn parse_body(lines: &[&str]) -> Body {
+    let mut body = Body::default();
+    let mut index = 0;
+
+    while index < lines.len() {
+        if lines[index].trim().is_empty() {
+            index += 1;
+            continue;
+        }
+
+        let start = index;
+        if lines[index].trim_start().starts_with("/*") {
+            while index < lines.len() && !lines[index].contains("*/") {
+                index += 1;
+            }
+            index = (index + 1).min(lines.len());
+            body.elements.push(Element::new(ElementType::Action, lines[start..index].join("\n")));
+            continue;
+        }
+
+        while index < lines.len() && !lines[index].trim().is_empty() {
+            index += 1;
+        }
+        parse_paragraph(&lines[start..index], &mut body);
+    }
+
+    body
+}
+
+fn parse_paragraph(lines: &[&str], body: &mut Body) {
+    let first = lines[0].trim();
+    let single_line = lines.len() == 1;
+    // Each line's text when every line is in the form `line` reads.
+    let every = |line: fn(&str) -> Option<&str>| lines.iter().map(|l| line(l)).collect::<Option<Vec<_>>>();
+
+    if let Some(shot) = first.strip_prefix('!').filter(|shot| single_line && is_shot(shot)) {
+        body.push_styled(ElementType::Shot, shot);
+    } else if let Some(action) = first.strip_prefix('!') {
+        let mut content = vec![action];
+        content.extend(&lines[1..]);
+        body.push_styled(ElementType::Action, &content.join("\n"));
+    } else if let Some(notes) = standalone_notes(lines) {
+        let index = body.elements.len();
+        body.notes.extend(notes.into_iter().map(|note| (index, note)));
+    } else if single_line && is_page_break(first) {
+        body.elements.push(Element::empty(ElementType::PageBreak));
+    } else if let Some(section) = section_type(first).filter(|_| single_line) {
+        body.push_styled(section, first.trim_start_matches('#').trim());
+    } else if let Some(text) = every(centered) {
+        body.push_styled(ElementType::Centered, &text.join("\n"));
+    } else if let Some(text) = every(lyric) {
+        body.push_styled(ElementType::Lyrics, &text.join("\n"));
+    } else if is_passthrough(first) {
+        body.elements.push(Element::new(ElementType::Action, lines.join("\n")));
+    } else if single_line && first.starts_with('.') && !first.starts_with("..") {
+        push_scene_heading(first[1..].trim(), body);
+    } else if single_line && is_scene_heading(first) {
+        push_scene_heading(first, body);
+    } else if single_line && first.starts_with('>') {
+        body.push_styled(ElementType::Transition, first[1..].trim());
+    } else if single_line && is_transition(first) {
+        body.push_styled(ElementType::Transition, first);
+    } else if let Some(name) = first.strip_prefix('@') {
+        parse_dialogue_block(name.trim(), &lines[1..], body);
+    } else if !single_line && is_character(first) {
+        parse_dialogue_block(first, &lines[1..], body);
+    } else {
+        body.push_styled(ElementType::Action, &lines.join("\n"));
+    }
+}
+
+/// Adds a scene heading, taking a trailing `#12A#` as its scene number.
+fn push_scene_heading(line: &str, body: &mut Body) {
+    let (heading, number) = split_scene_number(line);
+    body.push_styled(ElementType::SceneHeading, heading);
+    if let Some(element) = body.elements.last_mut() {
+        element.scene_number = number.map(str::to_string);
+    }
+}
+
+fn split_scene_number(line: &str) -> (&str, Option<&str>) {
+    let number = line
+        .strip_suffix('#')
+        .and_then(|rest| rest.rsplit_once('#'))
+        .filter(|(_, number)| {
+            !number.is_empty() && number.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '.')
+        });
+    match number {
+        Some((heading, number)) => (heading.trim_end(), Some(number)),
+        None => (line, None),
+    }
+}
+
+fn parse_dialogue_block(name: &str, lines: &[&str], body: &mut Body) {
+    let mut cue = Element::new(ElementType::Character, name.to_string());
+    if let Some(name) = name.strip_suffix('^') {
+        cue.content = name.trim_end().to_string();
+        cue.dual = true;
+    }
+    body.elements.push(cue);
+
+    let mut dialogue: Vec<&str> = Vec::new();
+    let mut lyrics: Vec<&str> = Vec::new();
+    for line in lines.iter().map(|line| line.trim()) {
+        if let Some(line) = lyric(line) {
+            flush(&mut dialogue, ElementType::Dialogue, body);
+            lyrics.push(line);
+        } else if line.starts_with('(') && line.ends_with(')') {
+            flush(&mut dialogue, ElementType::Dialogue, body);
+            flush(&mut lyrics, ElementType::Lyrics, body);
+            let inner = &line[1..line.len() - 1];
+            body.push_styled(ElementType::Parenthetical, inner);
+        } else {
+            flush(&mut lyrics, ElementType::Lyrics, body);
+            dialogue.push(line);
+        }
+    }
+    flush(&mut dialogue, ElementType::Dialogue, body);
+    flush(&mut lyrics, ElementType::Lyrics, body);
+}
+
+fn flush(lines: &mut Vec<&str>, element_type: ElementType, body: &mut Body) {
+    if !lines.is_empty() {
+        body.push_styled(element_type, &lines.join("\n"));
+        lines.clear();
+    }
+}
+
+/// Removes inline `[[notes]]` from `text`, returning what is left and the
+/// text of each note.
+fn take_notes(text: &str) -> (String, Vec<String>) {
+    let mut rest = text;
+    let mut kept = String::with_capacity(text.len());
+    let mut notes = Vec::new();
+    while let Some(start) = rest.find("[[")
+        && let Some(length) = rest[start + 2..].find("]]")
+    {
+        kept.push_str(&rest[..start]);
+        notes.push(rest[start + 2..start + 2 + length].trim().to_string());
+        rest = &rest[start + 2 + length + 2..];
+    }
+    kept.push_str(rest);
+    (kept, notes)
+}
+
+/// The notes of a paragraph made of nothing but `[[notes]]`.
+fn standalone_notes(lines: &[&str]) -> Option<Vec<String>> {
+    let (rest, notes) = take_notes(&lines.join("\n"));
+    (!notes.is_empty() && rest.trim().is_empty()).then_some(notes)
+}
+
+/// A note as inline markup, on one line and unable to close early.
+fn note_markup(text: &str) -> String {
+    let text = text.replace("]]", "] ]");
+    format!("[[{}]]", text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Splits emphasis markers out of `text`. A marker opens emphasis only
+/// when text follows it directly and a closing marker comes later on the
+/// same line, so stray asterisks and underscores stay literal.
+fn parse_emphasis(text: &str) -> (String, Vec<StyleSpan>) {
+    let mut content = String::with_capacity(text.len());
+    let mut runs: Vec<(std::ops::Range<usize>, Style)> = Vec::new();
+    let mut current = Style::PLAIN;
+    let mut run_start = 0;
+    let mut index = 0;
+
+    while index < text.len() {
+        let rest = &text[index..];
+        if let Some(escaped) = rest.strip_prefix('\\').filter(|after| after.starts_with(['*', '_'])) {
+            content.push_str(&escaped[..1]);
+            index += 2;
+            continue;
+        }
+
+        let marker = rest.chars().next().unwrap_or_default();
+        if marker != '*' && marker != '_' {
+            content.push(marker);
+            index += marker.len_utf8();
+            continue;
+        }
+
+        let length = rest.bytes().take_while(|&b| b == marker as u8).count();
+        let mut remaining = length;
+        while remaining > 0 {
+            let after = &rest[length..];
+            let Some((emphases, used)) = next_marker(marker, remaining, current, after) else {
+                break;
+            };
+            runs.push((run_start..content.len(), current));
+            run_start = content.len();
+            for emphasis in emphases {
+                current = current.with(emphasis, !current.has(emphasis));
+            }
+            remaining -= used;
+        }
+        content.push_str(&rest[..remaining]);
+        index += length;
+    }
+    runs.push((run_start..content.len(), current));
+
+    let mut spans = Vec::new();
+    for (range, style) in runs.into_iter().filter(|(_, style)| !style.is_plain()) {
+        style::restyle(&mut spans, range, |_| style);
+    }
+    (content, spans)
+}
+
+/// What the first markers of a run of `remaining` `marker` characters do
+/// given the emphasis already open: the emphases they toggle and how many
+/// characters that takes. Closing open emphasis wins over opening more.
+fn next_marker(marker: char, remaining: usize, current: Style, after: &str) -> Option<(Vec<Emphasis>, usize)> {
+    let candidates: &[(&[Emphasis], usize)] = match marker {
+        '*' => &[
+            (&[Emphasis::Bold, Emphasis::Italic], 3),
+            (&[Emphasis::Bold], 2),
+            (&[Emphasis::Italic], 1),
+        ],
+        _ => &[(&[Emphasis::Underline], 1)],
+    };
+    let fits = |(_, used): &&(&[Emphasis], usize)| *used <= remaining;
+
+    let closing = candidates
+        .iter()
+        .filter(fits)
+        .find(|(emphases, _)| emphases.iter().all(|&emphasis| current.has(emphasis)));
+    let opening = || {
+        let text_follows = after.starts_with(|c: char| !c.is_whitespace());
+        candidates.iter().filter(fits).find(|(emphases, used)| {
+            text_follows
+                && emphases.iter().all(|&emphasis| !current.has(emphasis))
+                && closes_later(marker, *used, after)
+        })
+    };
+    closing
+        .or_else(opening)
+        .map(|(emphases, used)| (emphases.to_vec(), *used))
+}
+
+/// Whether a run of at least `length` unescaped `marker` characters comes
+/// before the end of the line.
+fn closes_later(marker: char, length: usize, text: &str) -> bool {
+    let line = text.split('\n').next().unwrap_or_default();
+    let mut previous = None;
+    let mut run = 0;
+    for c in line.chars() {
+        if c == marker && (run > 0 || previous != Some('\\')) {
+            run += 1;
+            if run >= length {
+                return true;
+            }
+        } else {
+            run = 0;
+        }
+        previous = Some(c);
+    }
+    false
+}
+
+/// The element's text with its emphasis written as markers, literal
+/// markers escaped and each of `inserts` written verbatim at its offset.
+fn write_emphasis(element: &Element, inserts: &[(usize, String)]) -> String {
+    let mut inserts: Vec<&(usize, String)> = inserts.iter().collect();
+    inserts.sort_by_key(|(offset, _)| *offset);
+    let mut inserts = inserts.into_iter().peekable();
+
+    let mut pieces = Vec::new();
+    for (range, style) in style::runs(&element.styles, 0..element.content.len()) {
+        let mut start = range.start;
+        for &(offset, _) in inserts.clone().filter(|(offset, _)| range.start < *offset && *offset < range.end) {
+            pieces.push((start..offset, style));
+            start = offset;
+        }
+        pieces.push((start..range.end, style));
+    }
+
+    let mut output = String::with_capacity(element.content.len());
+    let mut previous = Style::PLAIN;
+    for (range, style) in pieces {
+        while let Some((_, insert)) = inserts.next_if(|(offset, _)| *offset <= range.start) {
+            output.push_str(insert);
+        }
+
+        let text = &element.content[range];
+        let body = text.trim_start();
+        let leading = &text[..text.len() - body.len()];
+        // Markers cannot open before whitespace: a run starting with some
+        // opens after it, and one of nothing but whitespace only keeps
+        // emphasis that is already open.
+        let style = if body.is_empty() {
+            Style {
+                bold: style.bold && previous.bold,
+                italic: style.italic && previous.italic,
+                underline: style.underline && previous.underline,
+            }
+        } else {
+            style
+        };
+
+        output.push_str(&markers(previous, style, true));
+        output.push_str(&escape(leading));
+        output.push_str(&markers(previous, style, false));
+        output.push_str(&escape(body));
+        previous = style;
+    }
+    output.push_str(&markers(previous, Style::PLAIN, true));
+    for (_, insert) in inserts {
+        output.push_str(insert);
+    }
+    output
+}
+
+/// The markers that close (`closing`) or open emphasis going from `from`
+/// to `to`. Underline wraps the asterisks so that markers nest.
+fn markers(from: Style, to: Style, closing: bool) -> String {
+    let changed = |emphasis: Emphasis| {
+        let (was, is) = (from.has(emphasis), to.has(emphasis));
+        if closing { was && !is } else { !was && is }
+    };
+    let stars = "*".repeat(2 * usize::from(changed(Emphasis::Bold)) + usize::from(changed(Emphasis::Italic)));
+    let underline = if changed(Emphasis::Underline) { "_" } else { "" };
+    if closing {
+        stars + underline
+    } else {
+        format!("{}{}", underline, stars)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('*', "\\*").replace('_', "\\_")
+}
+
+fn write_title_page(screenplay: &Screenplay) -> Option<String> {
+    let mut entries: Vec<(&str, &str)> = Vec::new();
+    for &key in TITLE_PAGE_ORDER {
+        let value = match key {
+            "Title" => Some(screenplay.title.as_str()).filter(|t| *t != Screenplay::UNTITLED),
+            "Author" => screenplay.author.as_deref(),
+            "Contact" => screenplay.contact.as_deref(),
+            _ => TitleField::from_key(key).and_then(|field| screenplay.title_field(field)),
+        };
+        if let Some(value) = value {
+            entries.push((key, value));
+        }
+    }
+
+    for (key, value) in &screenplay.metadata {
+        if TitleField::from_key(key).is_none() {
+            entries.push((key, value));
+        }
+    }
+
+    if entries.is_empty() {
+        return None;
+    }
+
+    let lines: Vec<String> = entries
+        .into_iter()
+        .map(|(key, value)| {
+            if value.contains('\n') {
+                let indented: Vec<String> = value.lines().map(|l| format!("    {}", l)).collect();
+                format!("{}:\n{}", key, indented.join("\n"))
+            } else {
+                format!("{}: {}", key, value)
+            }
+        })
+        .collect();
+    Some(lines.join("\n"))
+}
+
+fn write_character(cue: &Element, has_dialogue: bool) -> String {
+    let name = &cue.content;
+    let line = if has_dialogue && is_character(name) {
+        name.to_string()
+    } else {
+        format!("@{}", name)
+    };
+    if cue.dual { format!("{} ^", line) } else { line }
+}
+
+fn write_element(element: &Element, notes: &[&Note]) -> String {
+    let content = &element.content;
+    let inserts: Vec<(usize, String)> = notes
+        .iter()
+        .map(|note| (note.anchor.end.offset.min(content.len()), note_markup(&note.text)))
+        .collect();
+    let mut text = write_emphasis(element, &inserts);
+    if let Some(number) = scene_number::printed(element) {
+        text = format!("{} #{}#", text, number);
+    }
+    match element.element_type {
+        ElementType::SceneHeading if is_scene_heading(content) => text,
+        ElementType::SceneHeading => format!(".{}", text),
+        ElementType::Transition if is_transition(content) && inserts.is_empty() => text,
+        ElementType::Transition => format!("> {}", text),
+        ElementType::Character => write_character(element, false),
+        ElementType::Parenthetical => format!("({})", text),
+        ElementType::Dialogue => text,
+        ElementType::Shot => format!("!{}", text),
+        ElementType::Centered => text.lines().map(|line| format!("> {} <", line)).collect::<Vec<_>>().join("\n"),
+        ElementType::Lyrics => text.lines().map(|line| format!("~{}", line)).collect::<Vec<_>>().join("\n"),
+        ElementType::PageBreak => "===".to_string(),
+        ElementType::Act | ElementType::Sequence | ElementType::Section => {
+            format!("{} {}", section_marker(element.element_type), text)
+        }
+        ElementType::Action => {
+            // Verbatim constructs read back as they are; anything else
+            // goes through the markup.
+            let reads_back = |text: &str| {
+                let lines: Vec<&str> = text.lines().collect();
+                matches!(
+                    parse_body(&lines).elements.as_slice(),
+                    [only] if only.element_type == ElementType::Action && only == element
+                )
+            };
+            if inserts.is_empty() && reads_back(content) {
+                content.clone()
+            } else if reads_back(&text) {
+                text
+            } else {
+                format!("!{}", text)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scene_heading_detection() {
+        assert!(is_scene_heading("INT. HOUSE - DAY"));
+        assert!(is_scene_heading("ext. road - night"));
+        assert!(is_scene_heading("I/E CAR - MOVING"));
+        assert!(is_scene_heading("EXT./INT. CAR - MOVING"));
+        assert!(!is_scene_heading("INTERIOR MONOLOGUE"));
+    }
+
+    #[test]
+    fn test_character_detection() {
+        assert!(is_character("MARY (V.O.)"));
+        assert!(is_character("R2-D2"));
+        assert!(!is_character("Mary"));
+    }
+}
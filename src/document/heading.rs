@@ -0,0 +1,134 @@
+//! The parts of a scene heading.
+//!
+//! `INT. HOUSE - KITCHEN - NIGHT` breaks down into the prefix `INT.`, the
+//! location `HOUSE`, the sub-location `KITCHEN` and the time of day
+//! `NIGHT`. Parts are separated by ` - `; the last one is the time of day
+//! when it is a known time or when there are three or more parts, and
+//! everything between the location and the time is the sub-location.
+
+/// Prefixes in the order they are offered when typing a heading.
+pub const PREFIXES: [&str; 5] = ["INT.", "EXT.", "INT./EXT.", "EXT./INT.", "I/E."];
+
+/// Times of day in the order they are offered.
+pub const TIMES: [&str; 13] = [
+    "DAY",
+    "NIGHT",
+    "MORNING",
+    "AFTERNOON",
+    "EVENING",
+    "DAWN",
+    "DUSK",
+    "SUNRISE",
+    "SUNSET",
+    "CONTINUOUS",
+    "LATER",
+    "MOMENTS LATER",
+    "SAME TIME",
+];
+
+/// Prefixes as written, without their final period.
+const PREFIX_FORMS: [&str; 8] = ["INT", "EXT", "EST", "INT./EXT", "INT/EXT", "EXT./INT", "EXT/INT", "I/E"];
+
+/// Separator between the parts after the prefix.
+pub const SEPARATOR: &str = " - ";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Heading {
+    /// `INT.`, `EXT.` and the like, as written.
+    pub prefix: Option<String>,
+    pub location: String,
+    pub sub_location: Option<String>,
+    pub time: Option<String>,
+}
+
+impl Heading {
+    /// Breaks `text` down, uppercased as it prints. A heading without a
+    /// recognised prefix is all location and time, like `KITCHEN - LATER`.
+    pub fn parse(text: &str) -> Heading {
+        let text = text.trim().to_uppercase();
+        let (prefix, rest) = split_prefix(&text);
+
+        let mut parts: Vec<&str> = rest
+            .split(SEPARATOR)
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .collect();
+        let time = match parts.as_slice() {
+            [_, .., last] if parts.len() >= 3 || is_time(last) => parts.pop(),
+            _ => None,
+        };
+        let location = if parts.is_empty() { String::new() } else { parts.remove(0).to_string() };
+        let sub_location = (!parts.is_empty()).then(|| parts.join(SEPARATOR));
+
+        Heading {
+            prefix: prefix.map(str::to_string),
+            location,
+            sub_location,
+            time: time.map(str::to_string),
+        }
+    }
+
+    /// The location with its sub-location, as in `HOUSE - KITCHEN`.
+    pub fn place(&self) -> String {
+        match &self.sub_location {
+            Some(sub_location) => format!("{}{}{}", self.location, SEPARATOR, sub_location),
+            None => self.location.clone(),
+        }
+    }
+}
+
+/// Splits a recognised prefix off the front of `text`.
+pub fn split_prefix(text: &str) -> (Option<&str>, &str) {
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    let token = &text[..end];
+    let bare = token.strip_suffix('.').unwrap_or(token);
+    if PREFIX_FORMS.iter().any(|form| bare.eq_ignore_ascii_case(form)) {
+        (Some(token), text[end..].trim_start())
+    } else {
+        (None, text)
+    }
+}
+
+/// Whether `part` is one of the usual times of day, ignoring anything in
+/// parentheses after it, as in `NIGHT (1985)`.
+fn is_time(part: &str) -> bool {
+    let part = part.split('(').next().unwrap_or_default().trim();
+    TIMES.contains(&part) || part == "SAME"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(text: &str) -> (Option<String>, String, Option<String>, Option<String>) {
+        let heading = Heading::parse(text);
+        (heading.prefix, heading.location, heading.sub_location, heading.time)
+    }
+
+    fn some(text: &str) -> Option<String> {
+        Some(text.to_string())
+    }
+
+    #[test]
+    fn test_parse_headings() {
+        assert_eq!(
+            parts("int. house - kitchen - night"),
+            (some("INT."), "HOUSE".to_string(), some("KITCHEN"), some("NIGHT"))
+        );
+        assert_eq!(parts("EXT. PARK - DAY"), (some("EXT."), "PARK".to_string(), None, some("DAY")));
+        assert_eq!(
+            parts("INT./EXT. CAR - MOVING"),
+            (some("INT./EXT."), "CAR".to_string(), some("MOVING"), None)
+        );
+        assert_eq!(
+            parts("I/E TRUCK - CAB - DUSK"),
+            (some("I/E"), "TRUCK".to_string(), some("CAB"), some("DUSK"))
+        );
+        assert_eq!(
+            parts("INT. MALL - FOOD COURT - ARCADE - SAME"),
+            (some("INT."), "MALL".to_string(), some("FOOD COURT - ARCADE"), some("SAME"))
+        );
+        assert_eq!(parts("INTERIOR MONOLOGUE"), (None, "INTERIOR MONOLOGUE".to_string(), None, None));
+        assert_eq!(parts("INT."), (some("INT."), String::new(), None, None));
+    }
+}
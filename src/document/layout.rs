@@ -0,0 +1,421 @@
+//! Printed geometry of a screenplay: US Letter, 12pt Courier at ten
+//! characters per inch and six lines per inch, with a 1.5" left margin.
+//! Element positions are measured in character cells from the left margin.
+//! Where each element type sits within that block comes from the script's
+//! style sheet; see `template`.
+
+use super::{Element, ElementType, Screenplay, Style, StyleSpan, dual, style, text};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+pub const PAGE_WIDTH_INCHES: f32 = 8.5;
+pub const PAGE_HEIGHT_INCHES: f32 = 11.0;
+pub const LEFT_MARGIN_INCHES: f32 = 1.5;
+pub const TOP_MARGIN_INCHES: f32 = 1.0;
+pub const BOTTOM_MARGIN_INCHES: f32 = 1.0;
+
+pub const CHARS_PER_INCH: f32 = 10.0;
+pub const LINES_PER_INCH: f32 = 6.0;
+pub const FONT_SIZE: f32 = 12.0;
+
+/// Character cells between the left and right margins.
+pub const LINE_WIDTH: usize = 60;
+
+/// Character cells in each half of a dual dialogue pair.
+pub const DUAL_COLUMN_WIDTH: usize = 28;
+/// Where the right-hand half of a dual dialogue pair starts.
+pub const DUAL_RIGHT_INDENT: usize = 32;
+/// Character cells between a scene number and the text block.
+pub const SCENE_NUMBER_GAP: usize = 2;
+
+pub const LINES_PER_PAGE: usize =
+    ((PAGE_HEIGHT_INCHES - TOP_MARGIN_INCHES - BOTTOM_MARGIN_INCHES) * LINES_PER_INCH) as usize;
+
+/// Sheet the script is printed on. The margins and the text block stay the
+/// same; a taller sheet only fits more lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Paper {
+    #[default]
+    Letter,
+    A4,
+}
+
+impl Paper {
+    pub const ALL: [Paper; 2] = [Paper::Letter, Paper::A4];
+
+    pub fn width_inches(self) -> f32 {
+        match self {
+            Paper::Letter => PAGE_WIDTH_INCHES,
+            Paper::A4 => 210.0 / 25.4,
+        }
+    }
+
+    pub fn height_inches(self) -> f32 {
+        match self {
+            Paper::Letter => PAGE_HEIGHT_INCHES,
+            Paper::A4 => 297.0 / 25.4,
+        }
+    }
+
+    /// Body lines between the top and bottom margins.
+    pub fn lines_per_page(self) -> usize {
+        ((self.height_inches() - TOP_MARGIN_INCHES - BOTTOM_MARGIN_INCHES) * LINES_PER_INCH) as usize
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Paper::Letter => "US Letter",
+            Paper::A4 => "A4",
+        }
+    }
+}
+
+impl std::fmt::Display for Paper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The part of the line an element prints in. A speech of dual dialogue
+/// takes one half; everything else spans the whole line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Column {
+    #[default]
+    Full,
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+}
+
+impl Alignment {
+    pub const ALL: [Alignment; 3] = [Alignment::Left, Alignment::Center, Alignment::Right];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Alignment::Left => "Left",
+            Alignment::Center => "Center",
+            Alignment::Right => "Right",
+        }
+    }
+}
+
+impl std::fmt::Display for Alignment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How one type of element is laid out and printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ElementFormat {
+    pub indent: usize,
+    pub width: usize,
+    /// Blank lines above the element, unless it starts a page.
+    pub space_before: usize,
+    /// Blank lines below the element, added to the next one's
+    /// `space_before`.
+    pub space_after: usize,
+    /// Rows each wrapped line takes: 1 for single spacing, 2 for double.
+    pub line_spacing: usize,
+    pub alignment: Alignment,
+    pub uppercase: bool,
+    /// Emphasis added to the whole element on top of its own.
+    pub style: Style,
+}
+
+impl ElementFormat {
+    /// The format of the standard feature film template.
+    pub fn for_type(element_type: ElementType) -> Self {
+        let (indent, width, space_before, alignment) = match element_type {
+            ElementType::SceneHeading => (0, LINE_WIDTH, 2, Alignment::Left),
+            ElementType::Action => (0, LINE_WIDTH, 1, Alignment::Left),
+            ElementType::Character => (22, 38, 1, Alignment::Left),
+            ElementType::Parenthetical => (16, 24, 0, Alignment::Left),
+            ElementType::Dialogue => (10, 35, 0, Alignment::Left),
+            ElementType::Transition => (45, 15, 1, Alignment::Right),
+            ElementType::Shot => (0, LINE_WIDTH, 1, Alignment::Left),
+            ElementType::Centered => (0, LINE_WIDTH, 1, Alignment::Center),
+            ElementType::Lyrics => (10, 35, 0, Alignment::Left),
+            ElementType::PageBreak => (0, LINE_WIDTH, 0, Alignment::Left),
+            ElementType::Act | ElementType::Sequence => (0, LINE_WIDTH, 2, Alignment::Left),
+            ElementType::Section => (0, LINE_WIDTH, 1, Alignment::Left),
+        };
+        let style = Style {
+            italic: element_type == ElementType::Lyrics,
+            ..Style::PLAIN
+        };
+        Self {
+            indent,
+            width,
+            space_before,
+            space_after: 0,
+            line_spacing: 1,
+            alignment,
+            uppercase: element_type.is_uppercase(),
+            style,
+        }
+    }
+
+    /// This format for an element of `element_type` printed in `column`.
+    /// Within each half of dual dialogue the cue, parenthetical and
+    /// dialogue keep their usual order of indents on a narrower scale.
+    pub fn in_column(self, element_type: ElementType, column: Column) -> Self {
+        let base = match column {
+            Column::Full => return self,
+            Column::Left => 0,
+            Column::Right => DUAL_RIGHT_INDENT,
+        };
+        let (indent, width) = match element_type {
+            ElementType::Character => (8, 20),
+            ElementType::Parenthetical => (4, 22),
+            ElementType::Dialogue | ElementType::Lyrics => (0, DUAL_COLUMN_WIDTH),
+            _ => return self,
+        };
+        Self {
+            indent: base + indent,
+            width,
+            ..self
+        }
+    }
+
+    /// Rows `lines` wrapped lines take with this format's line spacing.
+    pub fn rows(&self, lines: usize) -> usize {
+        lines + lines.saturating_sub(1) * (self.line_spacing.max(1) - 1)
+    }
+
+    /// Column of the first character of `line` once alignment is applied.
+    pub fn column(&self, line: &str) -> usize {
+        let slack = self.width.saturating_sub(line.graphemes(true).count());
+        match self.alignment {
+            Alignment::Left => self.indent,
+            Alignment::Center => self.indent + slack / 2,
+            Alignment::Right => self.indent + slack,
+        }
+    }
+}
+
+/// What the editor shows before an element that never prints, so it
+/// stands apart from the script.
+pub fn marker(element_type: ElementType) -> &'static str {
+    match element_type {
+        ElementType::Act => "# ",
+        ElementType::Sequence => "## ",
+        ElementType::Section => "### ",
+        ElementType::PageBreak => "=== PAGE BREAK ===",
+        _ => "",
+    }
+}
+
+/// The columns a scene number starts at in the left and right margins,
+/// counted from the left margin. The left one ends just short of the text
+/// and so starts at a negative column.
+pub fn scene_number_columns(number: &str) -> (isize, isize) {
+    let left = -((SCENE_NUMBER_GAP + number.chars().count()) as isize);
+    (left, (LINE_WIDTH + SCENE_NUMBER_GAP) as isize)
+}
+
+/// The text of an element as it appears on the page, before wrapping.
+pub fn display_text(element: &Element, format: &ElementFormat) -> String {
+    styled_display_text(element, format).0
+}
+
+/// `display_text` with the emphasis it prints in, the element's own and
+/// its format's. Parentheses added around a parenthetical take the
+/// format's.
+pub fn styled_display_text(element: &Element, format: &ElementFormat) -> (String, Vec<StyleSpan>) {
+    let mut text = String::new();
+    let mut spans = Vec::new();
+    for (run, run_style) in element.formatted_runs(0..element.content.len(), format) {
+        if !run_style.is_plain() {
+            spans.push(StyleSpan::new(text.len()..text.len() + run.len(), run_style));
+        }
+        text.push_str(&run);
+    }
+    if element.element_type == ElementType::Parenthetical && !text.starts_with('(') {
+        let parenthesis = if format.style.is_plain() {
+            Vec::new()
+        } else {
+            vec![StyleSpan::new(0..1, format.style)]
+        };
+        style::insert(&mut spans, 0, 1, &parenthesis);
+        text.insert(0, '(');
+        style::insert(&mut spans, text.len(), 1, &parenthesis);
+        text.push(')');
+    }
+    (text, spans)
+}
+
+/// The printed lines of an element in `format`.
+pub fn element_lines(element: &Element, format: &ElementFormat) -> Vec<String> {
+    wrap(&display_text(element, format), format.width)
+}
+
+/// `element_lines` with the emphasis of each line, measured from its
+/// start.
+pub fn styled_element_lines(element: &Element, format: &ElementFormat) -> Vec<(String, Vec<StyleSpan>)> {
+    let (text, spans) = styled_display_text(element, format);
+    wrap_ranges(&text, format.width)
+        .into_iter()
+        .map(|line| (text[line.clone()].to_string(), style::slice(&spans, line)))
+        .collect()
+}
+
+/// The content behind each of `element_lines`, as byte ranges into
+/// `element.content`, each with the columns printed before the content
+/// starts on its line: one for the parenthesis opening a parenthetical.
+pub fn printed_lines(element: &Element, format: &ElementFormat) -> Vec<(Range<usize>, usize)> {
+    let text = display_text(element, format);
+    let lead = usize::from(text.len() > element.formatted_content_with(format).len());
+    // Where each character of the content starts in the printed text.
+    // Capitals are made character by character, as `formatted_runs` does.
+    let mut starts = Vec::with_capacity(element.content.len());
+    let mut printed = lead;
+    for (offset, c) in element.content.char_indices() {
+        starts.push((printed, offset));
+        printed += if format.uppercase {
+            c.to_uppercase().map(char::len_utf8).sum()
+        } else {
+            c.len_utf8()
+        };
+    }
+    let content_at = |printed: usize| {
+        starts
+            .iter()
+            .find(|&&(start, _)| start >= printed)
+            .map_or(element.content.len(), |&(_, offset)| offset)
+    };
+    wrap_ranges(&text, format.width)
+        .into_iter()
+        .map(|line| (content_at(line.start)..content_at(line.end), lead.saturating_sub(line.start)))
+        .collect()
+}
+
+/// The element's own text wrapped the way the editor shows it, as byte
+/// ranges into `element.content`. Parentheticals leave room for the
+/// parentheses drawn around them, and unprinted elements for their marker.
+pub fn content_lines(element: &Element, format: &ElementFormat) -> Vec<Range<usize>> {
+    let width = match element.element_type {
+        ElementType::Parenthetical => format.width.saturating_sub(2),
+        element_type => format.width.saturating_sub(marker(element_type).graphemes(true).count()),
+    };
+    wrap_ranges(&element.content, width)
+}
+
+/// `content_lines` for element `index` of the script, in the script's
+/// style and whichever column it prints in.
+pub fn content_lines_at(screenplay: &Screenplay, index: usize) -> Vec<Range<usize>> {
+    let elements = &screenplay.elements;
+    let format = screenplay.style_sheet.format_in(elements[index].element_type, dual::column_of(elements, index));
+    content_lines(&elements[index], &format)
+}
+
+/// Index of the wrapped line the caret at `offset` is drawn on. A caret on
+/// the boundary shared by two lines of a split word belongs to the later one.
+pub fn line_at(lines: &[Range<usize>], offset: usize) -> usize {
+    lines.iter().rposition(|line| line.start <= offset).unwrap_or(0)
+}
+
+/// Offset of the caret `column` clusters into wrapped line `index`, kept on
+/// that line even when it is shorter.
+pub fn offset_on_line(content: &str, lines: &[Range<usize>], index: usize, column: usize) -> usize {
+    let line = lines[index].clone();
+    let offset = line.start + text::offset_at_column(&content[line.clone()], column);
+    let shared_with_next = lines.get(index + 1).is_some_and(|next| next.start == offset);
+    if offset > line.start && shared_with_next {
+        text::previous_boundary(content, offset)
+    } else {
+        offset
+    }
+}
+
+/// Word-wraps `text` to `width` characters, breaking words that are longer
+/// than a whole line. Explicit newlines are kept and empty text still
+/// occupies one line.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    wrap_ranges(text, width)
+        .into_iter()
+        .map(|line| text[line].to_string())
+        .collect()
+}
+
+/// Byte ranges of the lines `wrap` produces. The space or newline a line
+/// breaks at belongs to neither line, so a range ends before it; lines split
+/// inside a long word share the boundary. Widths count grapheme clusters.
+pub fn wrap_ranges(text: &str, width: usize) -> Vec<Range<usize>> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut word_start = 0;
+
+    for paragraph in text.split('\n') {
+        let mut line = word_start..word_start;
+        let mut len = 0;
+
+        for word in paragraph.split(' ') {
+            let clusters: Vec<usize> = word.grapheme_indices(true).map(|(i, _)| word_start + i).collect();
+            if len > 0 && len + 1 + clusters.len() > width {
+                lines.push(line.clone());
+                len = 0;
+            } else if len > 0 {
+                len += 1;
+            }
+            if len == 0 {
+                line = word_start..word_start;
+            }
+
+            let mut rest = &clusters[..];
+            while len + rest.len() > width {
+                let take = width - len;
+                lines.push(line.start..rest[take]);
+                line = rest[take]..rest[take];
+                rest = &rest[take..];
+                len = 0;
+            }
+            line.end = word_start + word.len();
+            len += rest.len();
+            word_start += word.len() + 1;
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::template::StyleSheet;
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("one two three", 7), vec!["one two", "three"]);
+        assert_eq!(wrap("", 10), vec![""]);
+        assert_eq!(wrap("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+        assert_eq!(wrap("a\nb", 10), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_wrap_ranges_skip_break_characters() {
+        assert_eq!(wrap_ranges("one two three", 7), vec![0..7, 8..13]);
+        assert_eq!(wrap_ranges("abcdefghij", 4), vec![0..4, 4..8, 8..10]);
+        assert_eq!(wrap_ranges("a\n\nb", 10), vec![0..1, 2..2, 3..4]);
+        assert_eq!(wrap_ranges("na\u{ef}ve caf\u{e9}", 5), vec![0..6, 7..12]);
+    }
+
+    #[test]
+    fn test_page_geometry() {
+        assert_eq!(LINES_PER_PAGE, 54);
+        assert_eq!(Paper::Letter.lines_per_page(), LINES_PER_PAGE);
+        assert_eq!(Paper::A4.lines_per_page(), 58);
+        let transition = ElementFormat::for_type(ElementType::Transition);
+        assert_eq!(transition.column("CUT TO:") + "CUT TO:".len(), LINE_WIDTH);
+        let right = StyleSheet::default().format_in(ElementType::Dialogue, Column::Right);
+        assert_eq!(right.indent + right.width, LINE_WIDTH);
+    }
+}
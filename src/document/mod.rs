@@ -1,5 +1,29 @@
+pub mod completion;
+pub mod dual;
+mod edit;
 mod element;
+pub mod fdx;
+pub mod flow;
+pub mod format;
+pub mod heading;
+pub mod fountain;
+pub mod layout;
+pub mod note;
+pub mod pagination;
+pub mod pdf;
+mod range;
+pub mod scene_number;
 mod screenplay;
+pub mod search;
+pub mod style;
+pub mod template;
+pub mod text;
+pub mod title_page;
 
+pub use edit::Edit;
 pub use element::{Element, ElementType};
-pub use screenplay::Screenplay;
\ No newline at end of file
+pub use note::{Note, NoteColor};
+pub use range::{TextPosition, TextRange};
+pub use screenplay::{ElementChanges, Screenplay};
+pub use style::{Emphasis, Style, StyleSpan};
+pub(crate) use screenplay::read_file;
\ No newline at end of file
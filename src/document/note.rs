@@ -0,0 +1,224 @@
+//! Script notes: comments left on a stretch of the script, like notes in
+//! the margin of a printed draft.
+//!
+//! A note is anchored to a `TextRange`. Every `Edit` moves the anchors
+//! after it, so a note stays on the words it was left on while text is
+//! typed, deleted, undone and redone around it. When the text a note
+//! covers is deleted its anchor collapses to where the text was.
+
+use super::pagination::PageMap;
+use super::{Edit, Element, ElementType, Screenplay, TextPosition, TextRange, scene_number};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NoteColor {
+    #[default]
+    Yellow,
+    Green,
+    Blue,
+    Pink,
+    Orange,
+}
+
+impl NoteColor {
+    pub const ALL: [NoteColor; 5] = [
+        NoteColor::Yellow,
+        NoteColor::Green,
+        NoteColor::Blue,
+        NoteColor::Pink,
+        NoteColor::Orange,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NoteColor::Yellow => "Yellow",
+            NoteColor::Green => "Green",
+            NoteColor::Blue => "Blue",
+            NoteColor::Pink => "Pink",
+            NoteColor::Orange => "Orange",
+        }
+    }
+}
+
+impl fmt::Display for NoteColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Note {
+    /// Identifies the note while others are added and removed.
+    pub id: u64,
+    pub anchor: TextRange,
+    pub text: String,
+    pub author: String,
+    /// Seconds since the Unix epoch, or 0 when unknown, as for notes
+    /// imported from Fountain.
+    pub created: u64,
+    #[serde(default)]
+    pub color: NoteColor,
+    #[serde(default)]
+    pub resolved: bool,
+}
+
+impl Note {
+    pub fn new(id: u64, anchor: TextRange, author: String, created: u64) -> Self {
+        Self {
+            id,
+            anchor,
+            text: String::new(),
+            author,
+            created,
+            color: NoteColor::default(),
+            resolved: false,
+        }
+    }
+
+    /// Moves the anchor to follow `edit`, which has just been applied to
+    /// `elements`. Text inserted right at either end of the anchor lands
+    /// outside it, except that a collapsed anchor takes in text inserted
+    /// where it sits, so undoing a delete brings the note's text back.
+    pub fn follow(&mut self, edit: &Edit, elements: &[Element]) {
+        if let Edit::InsertText { index, offset, text, .. } = edit
+            && self.anchor.is_empty()
+            && self.anchor.start == TextPosition::new(*index, *offset)
+        {
+            self.anchor.end.offset += text.len();
+            return;
+        }
+        follow_position(&mut self.anchor.start, edit, elements, false);
+        follow_position(&mut self.anchor.end, edit, elements, true);
+        if self.anchor.end < self.anchor.start {
+            self.anchor.end = self.anchor.start;
+        }
+    }
+}
+
+fn follow_position(position: &mut TextPosition, edit: &Edit, elements: &[Element], is_end: bool) {
+    match edit {
+        Edit::InsertText {
+            index, offset, text, ..
+        } if position.element == *index
+            && (position.offset > *offset || (position.offset == *offset && !is_end)) =>
+        {
+            position.offset += text.len();
+        }
+        Edit::DeleteText {
+            index, offset, text, ..
+        } if position.element == *index => {
+            if position.offset >= offset + text.len() {
+                position.offset -= text.len();
+            } else if position.offset > *offset {
+                position.offset = *offset;
+            }
+        }
+        Edit::InsertElement { index, .. } if position.element >= *index => {
+            position.element += 1;
+        }
+        Edit::RemoveElement { index, .. } if position.element > *index => {
+            position.element -= 1;
+        }
+        // A position in a removed element moves to the end of the one
+        // before, where a backspace or a deleted selection joins them.
+        Edit::RemoveElement { index, .. } if position.element == *index => match index.checked_sub(1) {
+            Some(previous) => {
+                *position = TextPosition::new(previous, elements[previous].content.len());
+            }
+            None => position.offset = 0,
+        },
+        _ => {}
+    }
+}
+
+/// A plain-text list of the notes in script order, each with the page and
+/// scene it is on, the text it is anchored to and its status.
+pub fn report(screenplay: &Screenplay) -> String {
+    let pages = PageMap::for_screenplay(screenplay);
+    let mut notes: Vec<&Note> = screenplay.notes.iter().collect();
+    notes.sort_by_key(|note| note.anchor.start);
+
+    let mut output = format!("Notes: {}\n", screenplay.title);
+    if notes.is_empty() {
+        output.push_str("\nNo notes.\n");
+    }
+    for (number, note) in notes.into_iter().enumerate() {
+        let element = note.anchor.start.element;
+        let page = pages.page_of(element).unwrap_or(1);
+        let scene = screenplay.elements[..=element.min(screenplay.elements.len().saturating_sub(1))]
+            .iter()
+            .rev()
+            .find(|e| e.element_type == ElementType::SceneHeading)
+            .map(|heading| {
+                let text = heading.formatted_content_with(&screenplay.style_sheet.format(heading.element_type));
+                match scene_number::printed(heading) {
+                    Some(number) => format!("{} {}", number, text),
+                    None => text,
+                }
+            });
+
+        output.push_str(&format!("\n{}. Page {}", number + 1, page));
+        if let Some(scene) = scene {
+            output.push_str(&format!(" - {}", scene));
+        }
+        output.push('\n');
+
+        let mut byline = vec![note.color.as_str().to_string()];
+        if !note.author.is_empty() {
+            byline.push(note.author.clone());
+        }
+        if note.created > 0 {
+            byline.push(format_timestamp(note.created));
+        }
+        byline.push(if note.resolved { "Resolved" } else { "Open" }.to_string());
+        output.push_str(&format!("   {}\n", byline.join(" | ")));
+
+        let quoted: Vec<String> = screenplay.slice(note.anchor).iter().map(|e| e.content.clone()).collect();
+        let quoted = quoted.join(" / ");
+        if !quoted.trim().is_empty() {
+            output.push_str(&format!("   > {}\n", quoted.replace('\n', " ")));
+        }
+        for line in note.text.lines() {
+            output.push_str(&format!("   {}\n", line));
+        }
+    }
+    output
+}
+
+/// `YYYY-MM-DD HH:MM UTC` for a Unix timestamp.
+pub fn format_timestamp(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let minutes = seconds % 86_400 / 60;
+
+    // Howard Hinnant's days-to-civil conversion.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamps_format_as_utc() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00 UTC");
+        assert_eq!(format_timestamp(1_709_210_096), "2024-02-29 12:34 UTC");
+    }
+}
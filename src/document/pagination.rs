@@ -0,0 +1,660 @@
+//! Splits a screenplay into printed pages.
+//!
+//! Pagination follows the usual screenplay rules: a scene heading is never
+//! the last thing on a page, a character cue always stays with the start of
+//! its dialogue, and a speech that has to break is split after a line of
+//! dialogue with `(MORE)` at the foot of the page and `NAME (CONT'D)` at the
+//! top of the next one. The two speeches of dual dialogue print side by
+//! side on the same rows and are never split; a pair that would cross the
+//! foot of a page moves to the next one whole.
+//!
+//! Blank elements and outline headers print nothing and take no room, so a
+//! stray empty line in the editor never moves a page break. A page break
+//! element starts a new page unless the current one is still empty.
+//!
+//! Indents, spacing and line spacing come from the script's style sheet. A
+//! double-spaced element drops the blank row between its lines when they
+//! straddle a page break.
+//!
+//! After an edit, pagination restarts a page ahead of the change and stops
+//! at the first page that opens the way it did before, taking the old pages
+//! from there on.
+
+use super::layout::{self, Column, ElementFormat, Paper};
+use super::template::StyleSheet;
+use super::{Element, ElementChanges, ElementType, Screenplay, StyleSpan, dual};
+
+/// Lines an action paragraph must keep on each side of a page break.
+const MIN_SPLIT_LINES: usize = 2;
+
+pub const MORE: &str = "(MORE)";
+pub const CONTINUED: &str = "(CONT'D)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    /// Line `line` of the wrapped text of element `index`.
+    Element { index: usize, line: usize },
+    More,
+    /// Repeated character cue for the speech led by element `index`.
+    Continued { index: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub row: usize,
+    pub column: usize,
+    pub text: String,
+    /// Emphasis of `text`, measured from its start.
+    pub styles: Vec<StyleSpan>,
+    pub kind: LineKind,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Page {
+    pub number: usize,
+    pub lines: Vec<Line>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PageMap {
+    pages: Vec<Page>,
+    element_pages: Vec<usize>,
+    paper: Paper,
+    style_sheet: StyleSheet,
+}
+
+impl PageMap {
+    /// Paginates `elements` onto US Letter.
+    pub fn new(elements: &[Element]) -> Self {
+        Self::for_paper(elements, Paper::default())
+    }
+
+    /// Paginates `elements` in the feature film format.
+    pub fn for_paper(elements: &[Element], paper: Paper) -> Self {
+        Self::paginate(elements, paper, &StyleSheet::default())
+    }
+
+    /// Paginates a script on its own paper with its own style sheet.
+    pub fn for_screenplay(screenplay: &Screenplay) -> Self {
+        Self::paginate(&screenplay.elements, screenplay.paper, &screenplay.style_sheet)
+    }
+
+    fn paginate(elements: &[Element], paper: Paper, style_sheet: &StyleSheet) -> Self {
+        let mut paginator = Paginator::new(elements, style_sheet, paper.lines_per_page());
+        paginator.run(0);
+        paginator.finish(paper)
+    }
+
+    /// Paginates `screenplay` again after `changes` to its elements, keeping
+    /// the pages before the change and those after it that come out the same.
+    pub fn update(&mut self, screenplay: &Screenplay, changes: Option<ElementChanges>) {
+        let elements = &screenplay.elements;
+        let same_format =
+            !self.pages.is_empty() && self.paper == screenplay.paper && self.style_sheet == screenplay.style_sheet;
+        let changes = match changes {
+            None if same_format && self.element_pages.len() == elements.len() => return,
+            Some(changes) if same_format => changes,
+            _ => ElementChanges::ALL,
+        };
+        let old = std::mem::take(self);
+        let (start, tail) = changes.kept(old.element_pages.len(), elements.len());
+
+        // Headings and cues keep lines with what follows them, so a change
+        // can move the break at the top of its page: restart a page before,
+        // or further back at the first page that opens with a whole block.
+        let mut page = old.element_pages.get(start).or(old.element_pages.last()).map_or(0, |&page| page);
+        let restart = loop {
+            page = page.saturating_sub(1);
+            if page == 0 {
+                break 0;
+            }
+            if let Some(index) = old.opening(elements, page) {
+                break index;
+            }
+        };
+
+        let mut paginator = Paginator::new(elements, &screenplay.style_sheet, screenplay.paper.lines_per_page());
+        paginator.element_pages[..restart].copy_from_slice(&old.element_pages[..restart]);
+        let mut pages = old.pages;
+        paginator.reuse = Some(Reuse {
+            pages: pages.split_off(page),
+            first: page,
+            old_tail: old.element_pages.len() - tail,
+            new_tail: elements.len() - tail,
+            element_pages: old.element_pages,
+        });
+        paginator.pages = pages;
+        paginator.run(restart);
+        *self = paginator.finish(screenplay.paper);
+    }
+
+    /// The element page `page` opens with, when the page starts with its
+    /// first line and it leads a block pagination can start over at.
+    fn opening(&self, elements: &[Element], page: usize) -> Option<usize> {
+        match self.pages.get(page)?.lines.first()?.kind {
+            LineKind::Element { index, line: 0 } if starts_block(elements, index) => Some(index),
+            _ => None,
+        }
+    }
+
+    pub fn pages(&self) -> &[Page] {
+        &self.pages
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Number of the page the element starts on.
+    pub fn page_of(&self, element: usize) -> Option<usize> {
+        self.element_pages.get(element).map(|index| index + 1)
+    }
+}
+
+/// Whether pagination can start over at element `index` at the top of a
+/// page: it leads a block of its own rather than continuing a speech or
+/// pairing with the one before it.
+fn starts_block(elements: &[Element], index: usize) -> bool {
+    match elements[index].element_type {
+        ElementType::Dialogue | ElementType::Parenthetical | ElementType::Lyrics => false,
+        ElementType::Character => !elements[index].dual,
+        _ => true,
+    }
+}
+
+/// The pages from before an edit that pagination can take over once it
+/// reaches the unchanged elements at the end of the script.
+struct Reuse {
+    /// The old pages from page index `first` on.
+    pages: Vec<Page>,
+    first: usize,
+    element_pages: Vec<usize>,
+    /// Where the unchanged elements at the end start, in the old list and
+    /// in the new one.
+    old_tail: usize,
+    new_tail: usize,
+}
+
+/// One wrapped line of an element inside a dialogue block.
+struct SpeechLine {
+    index: usize,
+    line: usize,
+    text: String,
+    styles: Vec<StyleSpan>,
+    element_type: ElementType,
+    /// Rows the line takes, counting the blank one above it when the
+    /// element is double-spaced.
+    rows: usize,
+}
+
+fn speech_rows(speech: &[SpeechLine]) -> usize {
+    speech.iter().map(|line| line.rows).sum()
+}
+
+struct Paginator<'a> {
+    elements: &'a [Element],
+    style_sheet: &'a StyleSheet,
+    pages: Vec<Page>,
+    lines: Vec<Line>,
+    element_pages: Vec<usize>,
+    row: usize,
+    /// Blank lines the last element printed asks for below it.
+    space_after: usize,
+    lines_per_page: usize,
+    reuse: Option<Reuse>,
+}
+
+impl<'a> Paginator<'a> {
+    fn new(elements: &'a [Element], style_sheet: &'a StyleSheet, lines_per_page: usize) -> Self {
+        Self {
+            elements,
+            style_sheet,
+            pages: Vec::new(),
+            lines: Vec::new(),
+            element_pages: vec![0; elements.len()],
+            row: 0,
+            space_after: 0,
+            lines_per_page,
+            reuse: None,
+        }
+    }
+
+    fn run(&mut self, mut index: usize) {
+        while index < self.elements.len() {
+            if !self.prints(index) {
+                self.element_pages[index] = self.pages.len();
+                index += 1;
+                continue;
+            }
+            index = match self.elements[index].element_type {
+                ElementType::PageBreak => {
+                    if !self.lines.is_empty() {
+                        self.break_page();
+                    }
+                    self.element_pages[index] = self.pages.len();
+                    index + 1
+                }
+                ElementType::Character if dual::column_of(self.elements, index) == Column::Left => {
+                    self.place_dual(index)
+                }
+                ElementType::Character => self.place_speech(index),
+                ElementType::SceneHeading | ElementType::Shot => self.place_heading(index),
+                ElementType::Action => self.place_splittable(index),
+                _ => self.place_whole(index),
+            };
+            if self.take_over_rest() {
+                return;
+            }
+        }
+    }
+
+    /// Once the page being filled opens with an unchanged element that
+    /// opened an old page too, every page from there on comes out as it did
+    /// before: takes them over instead, renumbered, in place of this one.
+    fn take_over_rest(&mut self) -> bool {
+        let Some(reuse) = &self.reuse else {
+            return false;
+        };
+        let Some(&Line {
+            kind: LineKind::Element { index, line: 0 },
+            ..
+        }) = self.lines.first()
+        else {
+            return false;
+        };
+        if index < reuse.new_tail || !starts_block(self.elements, index) {
+            return false;
+        }
+        let old_index = index - reuse.new_tail + reuse.old_tail;
+        let opens = |page: usize| {
+            let kind = reuse.pages.get(page)?.lines.first()?.kind;
+            (kind == LineKind::Element { index: old_index, line: 0 }).then_some(page)
+        };
+        let Some(page) = reuse.element_pages[old_index].checked_sub(reuse.first).and_then(opens) else {
+            return false;
+        };
+
+        let Reuse {
+            mut pages,
+            first,
+            element_pages,
+            old_tail,
+            new_tail,
+        } = self.reuse.take().expect("checked above");
+        let shift = |index: usize| index - old_tail + new_tail;
+        let offset = self.pages.len();
+        for (number, mut taken) in (offset + 1..).zip(pages.drain(page..)) {
+            taken.number = number;
+            if old_tail != new_tail {
+                for line in &mut taken.lines {
+                    line.kind = match line.kind {
+                        LineKind::Element { index, line } => LineKind::Element {
+                            index: shift(index),
+                            line,
+                        },
+                        LineKind::Continued { index } => LineKind::Continued { index: shift(index) },
+                        LineKind::More => LineKind::More,
+                    };
+                }
+            }
+            self.pages.push(taken);
+        }
+        for (new, old) in self.element_pages[index..].iter_mut().zip(&element_pages[old_index..]) {
+            *new = old - first - page + offset;
+        }
+        self.lines.clear();
+        self.row = 0;
+        true
+    }
+
+    fn finish(mut self, paper: Paper) -> PageMap {
+        if !self.lines.is_empty() || self.pages.is_empty() {
+            self.break_page();
+        }
+        PageMap {
+            pages: self.pages,
+            element_pages: self.element_pages,
+            paper,
+            style_sheet: self.style_sheet.clone(),
+        }
+    }
+
+    /// Whether element `index` takes any room on the page.
+    fn prints(&self, index: usize) -> bool {
+        let element = &self.elements[index];
+        !element.is_empty() && !element.element_type.is_structural()
+    }
+
+    fn remaining(&self) -> usize {
+        self.lines_per_page.saturating_sub(self.row)
+    }
+
+    fn format(&self, element_type: ElementType) -> ElementFormat {
+        self.style_sheet.format(element_type)
+    }
+
+    fn element_lines(&self, index: usize) -> Vec<(String, Vec<StyleSpan>)> {
+        let element = &self.elements[index];
+        layout::styled_element_lines(element, &self.format(element.element_type))
+    }
+
+    fn spacing(&self, element_type: ElementType) -> usize {
+        if self.row == 0 {
+            0
+        } else {
+            self.space_after + self.format(element_type).space_before
+        }
+    }
+
+    fn break_page(&mut self) {
+        let number = self.pages.len() + 1;
+        self.pages.push(Page {
+            number,
+            lines: std::mem::take(&mut self.lines),
+        });
+        self.row = 0;
+    }
+
+    fn push(&mut self, column: usize, text: String, styles: Vec<StyleSpan>, kind: LineKind) {
+        if self.row >= self.lines_per_page {
+            self.break_page();
+        }
+        if let LineKind::Element { index, line } = kind {
+            if line == 0 {
+                self.element_pages[index] = self.pages.len();
+            }
+            self.space_after = self.format(self.elements[index].element_type).space_after;
+        }
+        self.lines.push(Line {
+            row: self.row,
+            column,
+            text,
+            styles,
+            kind,
+        });
+        self.row += 1;
+    }
+
+    /// Starts a new page unless `needed` lines, including spacing, still fit.
+    fn reserve(&mut self, element_type: ElementType, needed: usize) {
+        if self.row > 0 && self.spacing(element_type) + needed > self.remaining() {
+            self.break_page();
+        }
+        self.row += self.spacing(element_type);
+    }
+
+    fn push_element_lines(&mut self, index: usize, lines: Vec<(String, Vec<StyleSpan>)>, first_line: usize) {
+        let format = self.format(self.elements[index].element_type);
+        for (offset, (text, styles)) in lines.into_iter().enumerate() {
+            if offset > 0 {
+                self.row += format.line_spacing.max(1) - 1;
+            }
+            let kind = LineKind::Element {
+                index,
+                line: first_line + offset,
+            };
+            self.push(format.column(&text), text, styles, kind);
+        }
+    }
+
+    fn place_whole(&mut self, index: usize) -> usize {
+        let lines = self.element_lines(index);
+        let element_type = self.elements[index].element_type;
+        let rows = self.format(element_type).rows(lines.len());
+        self.reserve(element_type, rows.min(self.lines_per_page));
+        self.push_element_lines(index, lines, 0);
+        index + 1
+    }
+
+    fn place_splittable(&mut self, index: usize) -> usize {
+        let element_type = self.elements[index].element_type;
+        let format = self.format(element_type);
+        let mut lines = self.element_lines(index);
+        let total = lines.len();
+        let room = self.remaining().saturating_sub(self.spacing(element_type));
+        let fits_here = (1..=total).take_while(|&count| format.rows(count) <= room).count();
+
+        if self.row > 0 && format.rows(total) > room {
+            if fits_here >= MIN_SPLIT_LINES && total - fits_here >= MIN_SPLIT_LINES {
+                self.row += self.spacing(element_type);
+                let rest = lines.split_off(fits_here);
+                self.push_element_lines(index, lines, 0);
+                self.break_page();
+                self.push_element_lines(index, rest, fits_here);
+                return index + 1;
+            }
+            self.break_page();
+        }
+
+        self.row += self.spacing(element_type);
+        self.push_element_lines(index, lines, 0);
+        index + 1
+    }
+
+    fn place_heading(&mut self, index: usize) -> usize {
+        let lines = self.element_lines(index);
+        let element_type = self.elements[index].element_type;
+        let needed = self.format(element_type).rows(lines.len()) + self.lines_needed_after(index + 1);
+        self.reserve(element_type, needed.min(self.lines_per_page));
+        self.push_element_lines(index, lines, 0);
+        index + 1
+    }
+
+    /// The smallest run of lines that has to share a page with whatever
+    /// precedes the first printed element from `index` on.
+    fn lines_needed_after(&self, index: usize) -> usize {
+        let Some(index) = (index..self.elements.len()).find(|&index| self.prints(index)) else {
+            return 0;
+        };
+        let element = &self.elements[index];
+        let format = self.format(element.element_type);
+        let lines = self.element_lines(index).len();
+
+        match element.element_type {
+            ElementType::Character if dual::column_of(self.elements, index) == Column::Left => {
+                format.space_before + self.dual_columns(index).1
+            }
+            ElementType::Character => {
+                let speech = self.speech_lines(index, dual::speech_end(self.elements, index));
+                let speech = &speech[..speech.len().min(MIN_SPLIT_LINES)];
+                format.space_before + format.rows(lines) + speech_rows(speech)
+            }
+            ElementType::Action => format.space_before + format.rows(lines.min(MIN_SPLIT_LINES)),
+            _ => format.space_before + format.rows(lines),
+        }
+    }
+
+    fn speech_lines(&self, cue: usize, end: usize) -> Vec<SpeechLine> {
+        (cue + 1..end)
+            .filter(|&index| !self.elements[index].is_empty())
+            .flat_map(|index| {
+                let element_type = self.elements[index].element_type;
+                let spacing = self.format(element_type).line_spacing.max(1);
+                self.element_lines(index)
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(line, (text, styles))| SpeechLine {
+                        index,
+                        line,
+                        text,
+                        styles,
+                        element_type,
+                        rows: if line == 0 { 1 } else { spacing },
+                    })
+            })
+            .collect()
+    }
+
+    fn place_speech(&mut self, cue: usize) -> usize {
+        let end = dual::speech_end(self.elements, cue);
+        let cue_format = self.format(ElementType::Character);
+        let cue_lines = self.element_lines(cue);
+        let cue_rows = cue_format.rows(cue_lines.len());
+        let mut speech = self.speech_lines(cue, end);
+        let mut continued = false;
+
+        loop {
+            let spacing = self.spacing(ElementType::Character);
+            let available = self.remaining().saturating_sub(spacing);
+
+            if cue_rows + speech_rows(&speech) <= available {
+                self.row += spacing;
+                self.push_cue(cue, &cue_lines, continued);
+                self.push_speech(speech);
+                return end;
+            }
+
+            // Room for the cue, at least one line of speech and (MORE).
+            let room = available.saturating_sub(cue_rows + 1);
+            let fitting = (1..=speech.len()).take_while(|&k| speech_rows(&speech[..k]) <= room).count();
+            let split = if speech.len() < 2 {
+                None
+            } else {
+                (1..=fitting.min(speech.len() - 1))
+                    .rev()
+                    .find(|&k| speech[k - 1].element_type == ElementType::Dialogue)
+                    .or_else(|| (self.row == 0).then(|| fitting.clamp(1, speech.len() - 1)))
+            };
+
+            match split {
+                Some(split) => {
+                    let rest = speech.split_off(split);
+                    self.row += spacing;
+                    self.push_cue(cue, &cue_lines, continued);
+                    self.push_speech(speech);
+                    self.push(cue_format.indent, MORE.to_string(), Vec::new(), LineKind::More);
+                    self.break_page();
+                    speech = rest;
+                    continued = true;
+                }
+                None if self.row > 0 => self.break_page(),
+                None => {
+                    self.push_cue(cue, &cue_lines, continued);
+                    self.push_speech(speech);
+                    return end;
+                }
+            }
+        }
+    }
+
+    /// The printed lines of both halves of the dual dialogue pair led by
+    /// the cue at `left`, each with its row below the top of the pair, with
+    /// the rows the taller half needs and the index just past the pair.
+    fn dual_columns(&self, left: usize) -> ([Vec<Line>; 2], usize, usize) {
+        let right = dual::speech_end(self.elements, left);
+        let end = dual::speech_end(self.elements, right);
+        let lines_of = |range: std::ops::Range<usize>, column: Column| -> Vec<Line> {
+            let mut row = 0;
+            let mut lines = Vec::new();
+            for index in range.filter(|&index| !self.elements[index].is_empty()) {
+                let element = &self.elements[index];
+                let format = self.style_sheet.format_in(element.element_type, column);
+                for (line, (text, styles)) in layout::styled_element_lines(element, &format).into_iter().enumerate() {
+                    if line > 0 {
+                        row += format.line_spacing.max(1) - 1;
+                    }
+                    lines.push(Line {
+                        row,
+                        column: format.column(&text),
+                        text,
+                        styles,
+                        kind: LineKind::Element { index, line },
+                    });
+                    row += 1;
+                }
+            }
+            lines
+        };
+        let columns = [lines_of(left..right, Column::Left), lines_of(right..end, Column::Right)];
+        let height = columns.iter().filter_map(|lines| lines.last()).map(|line| line.row + 1).max().unwrap_or(0);
+        (columns, height, end)
+    }
+
+    /// Places a dual dialogue pair with both halves starting on the same
+    /// row. A pair taller than a page cannot be kept together and prints as
+    /// two ordinary speeches instead.
+    fn place_dual(&mut self, left: usize) -> usize {
+        let (columns, height, end) = self.dual_columns(left);
+        if height > self.lines_per_page {
+            let right = self.place_speech(left);
+            return self.place_speech(right);
+        }
+
+        self.reserve(ElementType::Character, height);
+        let top = self.row;
+        for lines in columns {
+            for line in lines {
+                self.row = top + line.row;
+                self.push(line.column, line.text, line.styles, line.kind);
+            }
+        }
+        self.row = top + height;
+        end
+    }
+
+    fn push_cue(&mut self, cue: usize, lines: &[(String, Vec<StyleSpan>)], continued: bool) {
+        if !continued {
+            self.push_element_lines(cue, lines.to_vec(), 0);
+            return;
+        }
+
+        let format = self.format(ElementType::Character);
+        let mut name = String::new();
+        let mut styles = Vec::new();
+        for (text, line_styles) in lines {
+            if !name.is_empty() {
+                name.push(' ');
+            }
+            let start = name.len();
+            styles.extend(
+                line_styles
+                    .iter()
+                    .map(|span| StyleSpan::new(span.start + start..span.end + start, span.style)),
+            );
+            name.push_str(text);
+        }
+        let text = if name.contains(CONTINUED) {
+            name
+        } else {
+            format!("{} {}", name, CONTINUED)
+        };
+        self.push(format.column(&text), text, styles, LineKind::Continued { index: cue });
+    }
+
+    fn push_speech(&mut self, speech: Vec<SpeechLine>) {
+        for line in speech {
+            if self.row > 0 {
+                self.row += line.rows - 1;
+            }
+            let format = self.format(line.element_type);
+            let kind = LineKind::Element {
+                index: line.index,
+                line: line.line,
+            };
+            self.push(format.column(&line.text), line.text, line.styles, kind);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_script_has_one_page() {
+        let map = PageMap::new(&[]);
+        assert_eq!(map.page_count(), 1);
+        assert_eq!(map.page_of(0), None);
+    }
+
+    #[test]
+    fn test_short_script_fits_one_page() {
+        let elements = vec![
+            Element::new(ElementType::SceneHeading, "INT. HALL - DAY".to_string()),
+            Element::new(ElementType::Action, "Quiet.".to_string()),
+        ];
+        let map = PageMap::new(&elements);
+        assert_eq!(map.page_count(), 1);
+        assert_eq!(map.pages()[0].lines[1].row, 2);
+    }
+}
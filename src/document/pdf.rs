@@ -0,0 +1,221 @@
+//! PDF export laid out to the standard screenplay template.
+//!
+//! Text is set in the built-in Courier fonts, so nothing needs embedding and
+//! characters outside Windows-1252 are replaced with `?`. Bold and italic
+//! text takes the matching Courier face; underlining is drawn as a rule
+//! below the text.
+
+use super::layout::{self, FONT_SIZE, LEFT_MARGIN_INCHES, LINE_WIDTH, LINES_PER_INCH, Paper, TOP_MARGIN_INCHES};
+use super::pagination::{LineKind, PageMap};
+use super::{Screenplay, Style, StyleSpan, scene_number, style, title_page};
+use pdf_writer::{Content, Name, Pdf, Rect, Ref, Str, TextStr};
+
+const POINTS_PER_INCH: f32 = 72.0;
+const LINE_HEIGHT: f32 = POINTS_PER_INCH / LINES_PER_INCH;
+const CHAR_WIDTH: f32 = POINTS_PER_INCH / layout::CHARS_PER_INCH;
+
+/// Rows above the first body line where the page number sits (half an inch).
+pub const PAGE_NUMBER_OFFSET: isize = 3;
+
+/// The Courier faces, by resource name, indexed by `face`.
+const FONTS: [(Name, Name); 4] = [
+    (Name(b"F1"), Name(b"Courier")),
+    (Name(b"F2"), Name(b"Courier-Bold")),
+    (Name(b"F3"), Name(b"Courier-Oblique")),
+    (Name(b"F4"), Name(b"Courier-BoldOblique")),
+];
+
+/// Points below the baseline the underline rule is drawn at, and its
+/// thickness.
+const UNDERLINE_OFFSET: f32 = 1.5;
+const UNDERLINE_WIDTH: f32 = 0.6;
+
+struct PageLine {
+    row: isize,
+    column: isize,
+    text: String,
+    styles: Vec<StyleSpan>,
+}
+
+pub fn write(screenplay: &Screenplay) -> Vec<u8> {
+    let mut pages = Vec::new();
+    if title_page::has_title_page(screenplay) {
+        pages.push(title_page_lines(screenplay));
+    }
+
+    for page in PageMap::for_screenplay(screenplay).pages() {
+        let mut lines = Vec::new();
+        for line in &page.lines {
+            lines.push(PageLine {
+                row: line.row as isize,
+                column: line.column as isize,
+                text: line.text.clone(),
+                styles: line.styles.clone(),
+            });
+            if let LineKind::Element { index, line: 0 } = line.kind
+                && let Some(number) = scene_number::printed(&screenplay.elements[index])
+            {
+                let (left, right) = layout::scene_number_columns(number);
+                for column in [left, right] {
+                    lines.push(PageLine {
+                        row: line.row as isize,
+                        column,
+                        text: number.to_string(),
+                        styles: Vec::new(),
+                    });
+                }
+            }
+        }
+
+        if let Some(label) = page_number(page.number) {
+            lines.push(PageLine {
+                row: -PAGE_NUMBER_OFFSET,
+                column: (LINE_WIDTH - label.len()) as isize,
+                text: label,
+                styles: Vec::new(),
+            });
+        }
+        pages.push(lines);
+    }
+
+    render(screenplay, &pages)
+}
+
+/// The number printed in the top-right corner. The first page of the
+/// script goes unnumbered.
+pub fn page_number(number: usize) -> Option<String> {
+    (number > 1).then(|| format!("{}.", number))
+}
+
+fn title_page_lines(screenplay: &Screenplay) -> Vec<PageLine> {
+    title_page::lines(screenplay, screenplay.paper.lines_per_page())
+        .into_iter()
+        .map(|line| PageLine {
+            row: line.row as isize,
+            column: line.column as isize,
+            text: line.text,
+            styles: Vec::new(),
+        })
+        .collect()
+}
+
+fn render(screenplay: &Screenplay, pages: &[Vec<PageLine>]) -> Vec<u8> {
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let info_id = Ref::new(3);
+    let font_ids: Vec<Ref> = (0..FONTS.len()).map(|index| Ref::new(4 + index as i32)).collect();
+    let first_page_id = 4 + FONTS.len() as i32;
+
+    let page_ids: Vec<Ref> = (0..pages.len())
+        .map(|index| Ref::new(first_page_id + 2 * index as i32))
+        .collect();
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id)
+        .kids(page_ids.iter().copied())
+        .count(pages.len() as i32);
+    for (&font_id, (_, base_font)) in font_ids.iter().zip(FONTS) {
+        pdf.type1_font(font_id)
+            .base_font(base_font)
+            .encoding_predefined(Name(b"WinAnsiEncoding"));
+    }
+
+    let mut info = pdf.document_info(info_id);
+    info.title(TextStr(&screenplay.title)).creator(TextStr("Quill"));
+    if let Some(author) = &screenplay.author {
+        info.author(TextStr(author));
+    }
+    drop(info);
+
+    let paper = screenplay.paper;
+    let media_box = Rect::new(
+        0.0,
+        0.0,
+        paper.width_inches() * POINTS_PER_INCH,
+        paper.height_inches() * POINTS_PER_INCH,
+    );
+
+    for (page_id, lines) in page_ids.iter().zip(pages) {
+        let content_id = Ref::new(page_id.get() + 1);
+
+        let mut page = pdf.page(*page_id);
+        page.parent(page_tree_id)
+            .media_box(media_box)
+            .contents(content_id);
+        page.resources()
+            .fonts()
+            .pairs(FONTS.iter().zip(&font_ids).map(|((name, _), &font_id)| (*name, font_id)));
+        drop(page);
+
+        pdf.stream(content_id, &page_content(paper, lines));
+    }
+
+    pdf.finish()
+}
+
+fn page_content(paper: Paper, lines: &[PageLine]) -> Vec<u8> {
+    let top = (paper.height_inches() - TOP_MARGIN_INCHES) * POINTS_PER_INCH;
+    let left = LEFT_MARGIN_INCHES * POINTS_PER_INCH;
+
+    let mut content = Content::new();
+    for line in lines {
+        let mut x = left + line.column as f32 * CHAR_WIDTH;
+        let y = top - (line.row + 1) as f32 * LINE_HEIGHT + LINE_HEIGHT * 0.25;
+
+        for (run, run_style) in style::runs(&line.styles, 0..line.text.len()) {
+            let text = encode(&line.text[run]);
+            let width = text.len() as f32 * CHAR_WIDTH;
+            content
+                .begin_text()
+                .set_font(FONTS[face(run_style)].0, FONT_SIZE)
+                .next_line(x, y)
+                .show(Str(&text))
+                .end_text();
+            if run_style.underline && !text.is_empty() {
+                content
+                    .set_line_width(UNDERLINE_WIDTH)
+                    .move_to(x, y - UNDERLINE_OFFSET)
+                    .line_to(x + width, y - UNDERLINE_OFFSET)
+                    .stroke();
+            }
+            x += width;
+        }
+    }
+    content.finish()
+}
+
+/// Index into `FONTS` of the face `style` prints in.
+fn face(style: Style) -> usize {
+    usize::from(style.bold) + 2 * usize::from(style.italic)
+}
+
+/// Encodes text as Windows-1252, the encoding of the built-in fonts.
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            '\u{20}'..='\u{7e}' | '\u{a0}'..='\u{ff}' => c as u8,
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            _ => b'?',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("Café — 5€"), b"Caf\xe9 \x97 5\x80");
+        assert_eq!(encode("日本"), b"??");
+    }
+}
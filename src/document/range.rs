@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+/// A caret position: an element index and an offset into its content.
+/// Positions order by element first, then by offset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct TextPosition {
+    pub element: usize,
+    pub offset: usize,
+}
+
+impl TextPosition {
+    pub fn new(element: usize, offset: usize) -> Self {
+        Self { element, offset }
+    }
+}
+
+/// A span of text between two positions, possibly crossing elements.
+/// `start` is never after `end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TextRange {
+    pub start: TextPosition,
+    pub end: TextPosition,
+}
+
+impl TextRange {
+    pub fn new(a: TextPosition, b: TextPosition) -> Self {
+        Self {
+            start: a.min(b),
+            end: a.max(b),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// The part of element `index` covered by this range, as offsets into
+    /// content of length `len`.
+    pub fn span_in(&self, index: usize, len: usize) -> Option<(usize, usize)> {
+        if index < self.start.element || index > self.end.element {
+            return None;
+        }
+        let from = if index == self.start.element { self.start.offset } else { 0 };
+        let to = if index == self.end.element { self.end.offset } else { len };
+        Some((from.min(len), to.min(len)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_orders_endpoints() {
+        let range = TextRange::new(TextPosition::new(3, 1), TextPosition::new(1, 4));
+        assert_eq!(range.start, TextPosition::new(1, 4));
+        assert_eq!(range.span_in(1, 10), Some((4, 10)));
+        assert_eq!(range.span_in(2, 6), Some((0, 6)));
+        assert_eq!(range.span_in(3, 6), Some((0, 1)));
+        assert_eq!(range.span_in(4, 6), None);
+    }
+}
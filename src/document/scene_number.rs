@@ -0,0 +1,241 @@
+//! Production scene numbers.
+//!
+//! A scene heading can carry a number, printed in both margins beside it.
+//! Renumbering gives every scene its place in the script, 1 upwards. Once
+//! a script is locked its numbers stay put: numbering then only fills in
+//! scenes that have none, a scene added between 12 and 13 becoming 12A,
+//! the next 12B, and one added before scene 1 becoming A1. A scene cut
+//! from a locked script is omitted rather than deleted, leaving a heading
+//! that reads `OMITTED` under the old number so no number is reused.
+
+use super::{Edit, Element, ElementType};
+use std::collections::HashSet;
+
+/// The heading left in place of an omitted scene.
+pub const OMITTED: &str = "OMITTED";
+
+/// Indexes of the scene headings in `elements`.
+fn headings(elements: &[Element]) -> Vec<usize> {
+    (0..elements.len())
+        .filter(|&index| elements[index].element_type == ElementType::SceneHeading)
+        .collect()
+}
+
+/// The number printed beside `element`, if it is a numbered scene
+/// heading.
+pub fn printed(element: &Element) -> Option<&str> {
+    match (&element.scene_number, element.element_type) {
+        (Some(number), ElementType::SceneHeading) => Some(number),
+        _ => None,
+    }
+}
+
+/// Edits numbering every scene by its place in the script.
+pub fn renumber(elements: &[Element]) -> Vec<Edit> {
+    headings(elements)
+        .into_iter()
+        .enumerate()
+        .filter_map(|(position, index)| set(elements, index, (position + 1).to_string()))
+        .collect()
+}
+
+/// Edits numbering the scenes that have no number, leaving every other
+/// number as it is.
+pub fn number(elements: &[Element]) -> Vec<Edit> {
+    let headings = headings(elements);
+    let mut taken: HashSet<String> = headings
+        .iter()
+        .filter_map(|&index| elements[index].scene_number.clone())
+        .collect();
+    let mut previous: Option<String> = None;
+    let mut edits = Vec::new();
+
+    for (position, &index) in headings.iter().enumerate() {
+        if let Some(number) = &elements[index].scene_number {
+            previous = Some(number.clone());
+            continue;
+        }
+        let later = headings[position + 1..]
+            .iter()
+            .find_map(|&later| elements[later].scene_number.as_deref());
+        let mut number = match (&previous, later) {
+            (Some(previous), Some(_)) => after(previous),
+            (Some(previous), None) => (whole(previous) + 1).to_string(),
+            (None, Some(first)) => format!("A{}", first),
+            (None, None) => "1".to_string(),
+        };
+        while taken.contains(&number) {
+            number = match (&previous, later) {
+                (Some(_), None) | (None, None) => (whole(&number) + 1).to_string(),
+                (None, Some(_)) => before(&number),
+                (Some(_), Some(_)) => after(&number),
+            };
+        }
+        taken.insert(number.clone());
+        edits.extend(set(elements, index, number.clone()));
+        previous = Some(number);
+    }
+    edits
+}
+
+/// Edits replacing the numbered scene around element `index` with an
+/// `OMITTED` heading that keeps its number, to be applied in order.
+/// `None` when the element is not in a numbered scene, or the scene is
+/// already omitted.
+pub fn omit(elements: &[Element], index: usize) -> Option<Vec<Edit>> {
+    let heading = elements
+        .get(..=index)?
+        .iter()
+        .rposition(|element| element.element_type == ElementType::SceneHeading)?;
+    let number = elements[heading].scene_number.clone()?;
+    let end = elements[heading + 1..]
+        .iter()
+        .position(|element| element.element_type == ElementType::SceneHeading)
+        .map_or(elements.len(), |offset| heading + 1 + offset);
+    if end == heading + 1 && elements[heading].content == OMITTED {
+        return None;
+    }
+
+    let mut edits: Vec<Edit> = (heading..end)
+        .rev()
+        .map(|index| Edit::RemoveElement {
+            index,
+            element: elements[index].clone(),
+        })
+        .collect();
+    edits.push(Edit::InsertElement {
+        index: heading,
+        element: omitted(number),
+    });
+    Some(edits)
+}
+
+/// Edits omitting, rather than losing, the numbered headings that `edits`
+/// removed from a locked script. Each comes back as an `OMITTED` heading
+/// at the end of the scene element `index` is in, the text that followed
+/// it having joined that scene.
+pub fn keep_omitted(elements: &[Element], index: usize, edits: &[Edit]) -> Vec<Edit> {
+    let mut numbers: Vec<&str> = Vec::new();
+    for edit in edits {
+        if let Edit::RemoveElement { element, .. } = edit
+            && let Some(number) = printed(element)
+            && !numbers.contains(&number)
+            && !elements.iter().any(|element| printed(element) == Some(number))
+        {
+            numbers.push(number);
+        }
+    }
+    let end = elements
+        .get(index + 1..)
+        .and_then(|rest| rest.iter().position(|element| element.element_type == ElementType::SceneHeading))
+        .map_or(elements.len(), |offset| index + 1 + offset);
+    numbers
+        .into_iter()
+        .enumerate()
+        .map(|(n, number)| Edit::InsertElement {
+            index: end + n,
+            element: omitted(number.to_string()),
+        })
+        .collect()
+}
+
+/// The heading left in place of the scene numbered `number`.
+fn omitted(number: String) -> Element {
+    let mut placeholder = Element::new(ElementType::SceneHeading, OMITTED.to_string());
+    placeholder.scene_number = Some(number);
+    placeholder
+}
+
+fn set(elements: &[Element], index: usize, number: String) -> Option<Edit> {
+    let from = elements[index].scene_number.clone();
+    (from.as_deref() != Some(number.as_str())).then_some(Edit::SetSceneNumber {
+        index,
+        from,
+        to: Some(number),
+    })
+}
+
+/// The whole number a scene number is based on: 12 for `12A` or `A12`.
+fn whole(number: &str) -> u64 {
+    number
+        .chars()
+        .filter(char::is_ascii_digit)
+        .collect::<String>()
+        .parse()
+        .unwrap_or(0)
+}
+
+/// The next number for a scene added after `number`: `12A` after `12`,
+/// `12B` after `12A`, and `12ZA` after `12Z`.
+fn after(number: &str) -> String {
+    match number.chars().last() {
+        Some(last) if last.is_ascii_uppercase() && last != 'Z' => {
+            format!("{}{}", &number[..number.len() - 1], (last as u8 + 1) as char)
+        }
+        _ => format!("{}A", number),
+    }
+}
+
+/// The next number in front of a scene numbered `number`: `B1` after
+/// `A1`, and `ZA1` after `Z1`.
+fn before(number: &str) -> String {
+    let letters = number.chars().take_while(char::is_ascii_uppercase).count();
+    match number[..letters].chars().last() {
+        Some(last) if last != 'Z' => format!(
+            "{}{}{}",
+            &number[..letters - 1],
+            (last as u8 + 1) as char,
+            &number[letters..]
+        ),
+        _ => format!("{}A{}", &number[..letters], &number[letters..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scene(number: Option<&str>) -> Element {
+        let mut element = Element::new(ElementType::SceneHeading, "INT. HALL - DAY".to_string());
+        element.scene_number = number.map(str::to_string);
+        element
+    }
+
+    fn numbers_after(elements: &mut [Element], edits: Vec<Edit>) -> Vec<Option<String>> {
+        for edit in edits {
+            if let Edit::SetSceneNumber { index, to, .. } = edit {
+                elements[index].scene_number = to;
+            }
+        }
+        elements.iter().map(|element| element.scene_number.clone()).collect()
+    }
+
+    #[test]
+    fn test_locked_numbers_get_inserts() {
+        let mut elements = vec![
+            scene(None),
+            scene(Some("1")),
+            scene(None),
+            scene(None),
+            scene(Some("2")),
+            scene(Some("3")),
+            scene(None),
+        ];
+        let edits = number(&elements);
+        let numbers: Vec<String> = numbers_after(&mut elements, edits).into_iter().flatten().collect();
+        assert_eq!(numbers, ["A1", "1", "1A", "1B", "2", "3", "4"]);
+
+        let edits = renumber(&elements);
+        let numbers: Vec<String> = numbers_after(&mut elements, edits).into_iter().flatten().collect();
+        assert_eq!(numbers, ["1", "2", "3", "4", "5", "6", "7"]);
+    }
+
+    #[test]
+    fn test_inserts_skip_numbers_in_use() {
+        assert_eq!(after("12Z"), "12ZA");
+        assert_eq!(before("Z1"), "ZA1");
+        let mut elements = vec![scene(Some("12")), scene(None), scene(Some("12A"))];
+        let edits = number(&elements);
+        assert_eq!(numbers_after(&mut elements, edits)[1].as_deref(), Some("12B"));
+    }
+}
@@ -1,36 +1,107 @@
-use super::Element;
-use crate::error::Result;
+use super::layout::Paper;
+use super::note::Note;
+use super::template::StyleSheet;
+use super::title_page::TitleField;
+use super::{Element, TextRange, format};
+use crate::error::{Result, ScreenplayError};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::fs;
+use std::io::ErrorKind;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Screenplay {
     pub title: String,
     pub author: Option<String>,
     pub contact: Option<String>,
+    /// Title page entries without a dedicated field, in file order.
+    #[serde(default)]
+    pub metadata: Vec<(String, String)>,
     pub elements: Vec<Element>,
+    /// Sheet size used for pagination, the page view and PDF export.
+    #[serde(default)]
+    pub paper: Paper,
+    /// Template and formats the script is laid out and printed with.
+    #[serde(default)]
+    pub style_sheet: StyleSheet,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<Note>,
+    /// Whether scene numbers are locked: numbering then keeps every
+    /// existing number and gives new scenes insert numbers such as 12A.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub scenes_locked: bool,
 
     #[serde(skip)]
     pub file_path: Option<PathBuf>,
 
     #[serde(skip)]
     pub modified: bool,
+
+    /// Bumped on every edit so derived data such as pagination knows when
+    /// to recompute.
+    #[serde(skip)]
+    pub revision: u64,
+
+    /// Elements changed since `take_changes` was last called.
+    #[serde(skip, default = "all_changed")]
+    changes: Option<ElementChanges>,
+}
+
+/// Which elements changed: the `start` first ones and the `tail` last ones
+/// are as they were, and those between may have been edited, added or
+/// removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ElementChanges {
+    pub start: usize,
+    pub tail: usize,
+}
+
+impl ElementChanges {
+    /// Every element, as in a script just created or opened.
+    pub const ALL: Self = Self { start: 0, tail: 0 };
+
+    /// How many elements lead and how many end both a list of `old` elements
+    /// and the list of `new` it became.
+    pub fn kept(self, old: usize, new: usize) -> (usize, usize) {
+        let start = self.start.min(old).min(new);
+        (start, self.tail.min(old - start).min(new - start))
+    }
+
+    /// The elements of a list of `len` that have changed.
+    pub fn range(self, len: usize) -> Range<usize> {
+        let start = self.start.min(len);
+        start..len - self.tail.min(len - start)
+    }
+}
+
+fn all_changed() -> Option<ElementChanges> {
+    Some(ElementChanges::ALL)
 }
 
 impl Screenplay {
+    pub const UNTITLED: &'static str = "Untitled";
+
     pub fn new(title: String) -> Self {
         Self {
             title,
             author: None,
             contact: None,
+            metadata: Vec::new(),
             elements: Vec::new(),
+            paper: Paper::default(),
+            style_sheet: StyleSheet::default(),
+            notes: Vec::new(),
+            scenes_locked: false,
             file_path: None,
             modified: false,
+            revision: 0,
+            changes: all_changed(),
         }
     }
 
     pub fn empty() -> Self {
-        Self::new("Untitled".to_string())
+        Self::new(Self::UNTITLED.to_string())
     }
 
     pub fn element_count(&self) -> usize {
@@ -42,21 +113,21 @@ impl Screenplay {
     }
 
     pub fn add_element(&mut self, element: Element) {
-        self.elements.push(element);
-        self.mark_modified();
+        self.insert_element(self.elements.len(), element);
     }
 
     pub fn insert_element(&mut self, index: usize, element: Element) {
         if index <= self.elements.len() {
             self.elements.insert(index, element);
-            self.mark_modified();
+            self.mark_changed(index, self.elements.len() - index - 1);
         }
     }
 
     pub fn remove_element(&mut self, index: usize) -> Option<Element> {
         if index < self.elements.len() {
-            self.mark_modified();
-            Some(self.elements.remove(index))
+            let element = self.elements.remove(index);
+            self.mark_changed(index, self.elements.len() - index);
+            Some(element)
         } else {
             None
         }
@@ -68,13 +139,101 @@ impl Screenplay {
 
     pub fn get_element_mut(&mut self, index: usize) -> Option<&mut Element> {
         if index < self.elements.len() {
-            self.mark_modified();
+            self.mark_changed(index, self.elements.len() - index - 1);
         }
         self.elements.get_mut(index)
     }
 
+    /// The elements changed since the last call, for data derived from them
+    /// that is brought up to date element by element.
+    pub fn take_changes(&mut self) -> Option<ElementChanges> {
+        self.changes.take()
+    }
+
+    /// Copies the text covered by `range`, one element per element it
+    /// touches, keeping each element's type and emphasis.
+    pub fn slice(&self, range: TextRange) -> Vec<Element> {
+        (range.start.element..=range.end.element)
+            .filter_map(|index| {
+                let element = self.elements.get(index)?;
+                let (from, to) = range.span_in(index, element.content.len())?;
+                Some(element.slice(from..to))
+            })
+            .collect()
+    }
+
+    /// The value of a title page entry, under its own key or an alias.
+    pub fn title_field(&self, field: TitleField) -> Option<&str> {
+        self.metadata
+            .iter()
+            .find(|(key, _)| field.matches(key))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets a title page entry in place, adding it when missing. A blank
+    /// value removes it.
+    pub fn set_title_field(&mut self, field: TitleField, value: &str) {
+        let existing = self.metadata.iter().position(|(key, _)| field.matches(key));
+        match (existing, value.trim().is_empty()) {
+            (Some(index), true) => {
+                self.metadata.remove(index);
+            }
+            (Some(index), false) => self.metadata[index].1 = value.to_string(),
+            (None, true) => return,
+            (None, false) => self.metadata.push((field.key().to_string(), value.to_string())),
+        }
+        self.mark_modified();
+    }
+
+    /// The id the next new note gets.
+    pub fn next_note_id(&self) -> u64 {
+        self.notes.iter().map(|note| note.id + 1).max().unwrap_or(1)
+    }
+
+    /// Adds `note`, keeping the notes in the order they were left in.
+    pub fn insert_note(&mut self, note: Note) {
+        let index = self.notes.partition_point(|existing| existing.id < note.id);
+        self.notes.insert(index, note);
+        self.mark_modified();
+    }
+
+    pub fn note(&self, id: u64) -> Option<&Note> {
+        self.notes.iter().find(|note| note.id == id)
+    }
+
+    /// Runs `change` on the note `id`, marking the script modified only if
+    /// the note changed. Returns whether it did.
+    pub fn update_note(&mut self, id: u64, change: impl FnOnce(&mut Note)) -> bool {
+        let Some(note) = self.notes.iter_mut().find(|note| note.id == id) else {
+            return false;
+        };
+        let before = note.clone();
+        change(note);
+        let changed = *note != before;
+        if changed {
+            self.mark_modified();
+        }
+        changed
+    }
+
+    pub fn remove_note(&mut self, id: u64) -> Option<Note> {
+        let index = self.notes.iter().position(|note| note.id == id)?;
+        self.mark_modified();
+        Some(self.notes.remove(index))
+    }
+
     pub fn mark_modified(&mut self) {
         self.modified = true;
+        self.revision = self.revision.wrapping_add(1);
+    }
+
+    /// Marks the script modified with the elements after the first `start`
+    /// and before the last `tail` changed.
+    fn mark_changed(&mut self, start: usize, tail: usize) {
+        self.mark_modified();
+        let changes = self.changes.get_or_insert(ElementChanges { start, tail });
+        changes.start = changes.start.min(start);
+        changes.tail = changes.tail.min(tail);
     }
 
     pub fn mark_saved(&mut self) {
@@ -91,12 +250,48 @@ impl Screenplay {
     }
 
     pub fn to_json(&self) -> Result<String> {
-        Ok(serde_json::to_string_pretty(self)?)
+        format::encode(self)
     }
 
     pub fn from_json(json: &str) -> Result<Self> {
-        Ok(serde_json::from_str(json)?)
+        format::decode(json)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = read_file(path)?;
+
+        let mut screenplay = match Self::from_json(&json) {
+            Err(ScreenplayError::JsonParse(e)) => {
+                return Err(ScreenplayError::InvalidFormat(e.to_string()));
+            }
+            result => result?,
+        };
+        screenplay.file_path = Some(path.to_path_buf());
+        screenplay.mark_saved();
+        Ok(screenplay)
     }
+
+    pub fn save(&mut self) -> Result<()> {
+        let path = self
+            .file_path
+            .clone()
+            .ok_or_else(|| ScreenplayError::FileNotFound("screenplay has no file path".to_string()))?;
+        self.save_as(&path)
+    }
+
+    pub fn save_as(&mut self, path: &Path) -> Result<()> {
+        fs::write(path, self.to_json()?)?;
+        self.file_path = Some(path.to_path_buf());
+        self.mark_saved();
+        Ok(())
+    }
+}
+
+pub(crate) fn read_file(path: &Path) -> Result<String> {
+    fs::read_to_string(path).map_err(|e| match e.kind() {
+        ErrorKind::NotFound => ScreenplayError::FileNotFound(path.display().to_string()),
+        _ => ScreenplayError::Io(e),
+    })
 }
 
 impl Default for Screenplay {
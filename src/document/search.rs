@@ -0,0 +1,243 @@
+//! Find and replace across a screenplay.
+//!
+//! Every query is compiled to a regular expression: plain text is escaped,
+//! whole-word matching adds word boundaries and case folding is a regex
+//! flag, so all modes share one matching path. Matches never span two
+//! elements, and empty matches are skipped.
+
+use super::style::{self, StyleSpan};
+use super::{Edit, Element, ElementType, Screenplay, TextPosition, TextRange};
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    pub text: String,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    /// Treat `text` as a regular expression, and expand `$1`, `${name}`
+    /// and `$$` in replacements.
+    pub regex: bool,
+    /// Only search elements of these types; all of them when empty.
+    pub element_types: Vec<ElementType>,
+}
+
+impl Query {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Self::default()
+        }
+    }
+
+    /// The matcher for this query, or `None` when there is nothing to
+    /// search for.
+    pub fn compile(&self) -> Result<Option<Matcher>, regex::Error> {
+        if self.text.is_empty() {
+            return Ok(None);
+        }
+        let mut pattern = if self.regex {
+            format!("(?:{})", self.text)
+        } else {
+            regex::escape(&self.text)
+        };
+        if self.whole_word {
+            // Plain text that starts or ends with punctuation, like
+            // "(V.O.)", has no word boundary to anchor to on that side.
+            let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+            if self.regex || is_word(self.text.chars().next()) {
+                pattern.insert_str(0, r"\b");
+            }
+            if self.regex || is_word(self.text.chars().next_back()) {
+                pattern.push_str(r"\b");
+            }
+        }
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .build()?;
+        Ok(Some(Matcher {
+            regex,
+            expand: self.regex,
+            element_types: self.element_types.clone(),
+        }))
+    }
+}
+
+/// A compiled `Query`.
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    regex: Regex,
+    expand: bool,
+    element_types: Vec<ElementType>,
+}
+
+impl Matcher {
+    fn searches(&self, element: &Element) -> bool {
+        self.element_types.is_empty() || self.element_types.contains(&element.element_type)
+    }
+
+    /// The byte ranges of the matches in one element's content.
+    fn find_in(&self, element: &Element) -> Vec<Range<usize>> {
+        if !self.searches(element) {
+            return Vec::new();
+        }
+        self.regex
+            .find_iter(&element.content)
+            .map(|found| found.range())
+            .filter(|range| !range.is_empty())
+            .collect()
+    }
+
+    /// Every match in script order.
+    pub fn find_all(&self, elements: &[Element]) -> Vec<TextRange> {
+        elements
+            .iter()
+            .enumerate()
+            .flat_map(|(index, element)| {
+                self.find_in(element).into_iter().map(move |range| {
+                    TextRange::new(TextPosition::new(index, range.start), TextPosition::new(index, range.end))
+                })
+            })
+            .collect()
+    }
+
+    /// What the match at `range` of `content` is replaced with.
+    fn replacement_for(&self, content: &str, range: Range<usize>, replacement: &str) -> String {
+        if !self.expand {
+            return replacement.to_string();
+        }
+        let mut expanded = String::new();
+        if let Some(captures) = self.regex.captures_at(content, range.start)
+            && captures.get(0).is_some_and(|found| found.range() == range)
+        {
+            captures.expand(replacement, &mut expanded);
+        }
+        expanded
+    }
+
+    /// Replaces the match at `range`, returning the edits made, or none
+    /// when `range` is no longer a match.
+    pub fn replace(&self, screenplay: &mut Screenplay, range: TextRange, replacement: &str) -> Vec<Edit> {
+        let index = range.start.element;
+        let Some(element) = screenplay.get_element(index) else {
+            return Vec::new();
+        };
+        let span = range.start.offset..range.end.offset;
+        if range.end.element != index || !self.find_in(element).contains(&span) {
+            return Vec::new();
+        }
+        let text = self.replacement_for(&element.content, span.clone(), replacement);
+        replace_text(screenplay, index, span, text)
+    }
+
+    /// Replaces every match, returning the edits made so they can be
+    /// undone together.
+    pub fn replace_all(&self, screenplay: &mut Screenplay, replacement: &str) -> Vec<Edit> {
+        let mut edits = Vec::new();
+        for index in 0..screenplay.elements.len() {
+            let element = &screenplay.elements[index];
+            let replacements: Vec<_> = self
+                .find_in(element)
+                .into_iter()
+                .map(|range| {
+                    let text = self.replacement_for(&element.content, range.clone(), replacement);
+                    (range, text)
+                })
+                .collect();
+            // Back to front, so the earlier ranges stay where they were.
+            for (range, text) in replacements.into_iter().rev() {
+                edits.extend(replace_text(screenplay, index, range, text));
+            }
+        }
+        edits
+    }
+}
+
+/// Swaps bytes `range` of element `index` for `text`, which takes the
+/// emphasis of the first character it replaces.
+fn replace_text(screenplay: &mut Screenplay, index: usize, range: Range<usize>, text: String) -> Vec<Edit> {
+    let element = &screenplay.elements[index];
+    let removed = element.slice(range.clone());
+    let style = style::style_at(&element.styles, range.start);
+    let styles = if style.is_plain() || text.is_empty() {
+        Vec::new()
+    } else {
+        vec![StyleSpan::new(0..text.len(), style)]
+    };
+
+    let mut edits = vec![Edit::DeleteText {
+        index,
+        offset: range.start,
+        text: removed.content,
+        styles: removed.styles,
+    }];
+    if !text.is_empty() {
+        edits.push(Edit::InsertText {
+            index,
+            offset: range.start,
+            text,
+            styles,
+        });
+    }
+    for edit in &edits {
+        edit.apply(screenplay);
+    }
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elements() -> Vec<Element> {
+        vec![
+            Element::new(ElementType::Action, "Bob sees Bobby. BOB waves.".to_string()),
+            Element::new(ElementType::Character, "BOB".to_string()),
+            Element::new(ElementType::Dialogue, "Hi, I'm Bob.".to_string()),
+        ]
+    }
+
+    fn found(query: &Query) -> Vec<(usize, usize, usize)> {
+        query
+            .compile()
+            .unwrap()
+            .map(|matcher| matcher.find_all(&elements()))
+            .unwrap_or_default()
+            .iter()
+            .map(|range| (range.start.element, range.start.offset, range.end.offset))
+            .collect()
+    }
+
+    #[test]
+    fn test_query_modes() {
+        let mut query = Query::new("bob");
+        assert_eq!(found(&query).len(), 5);
+
+        query.whole_word = true;
+        assert_eq!(found(&query), [(0, 0, 3), (0, 16, 19), (1, 0, 3), (2, 8, 11)]);
+
+        query.case_sensitive = true;
+        assert!(found(&query).is_empty());
+
+        query.element_types = vec![ElementType::Character, ElementType::Dialogue];
+        query.case_sensitive = false;
+        assert_eq!(found(&query), [(1, 0, 3), (2, 8, 11)]);
+
+        let query = Query {
+            regex: true,
+            ..Query::new(r"b\w+y")
+        };
+        assert_eq!(found(&query), [(0, 9, 14)]);
+        assert!(Query { regex: true, ..Query::new("(") }.compile().is_err());
+        assert!(Query::new("").compile().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_whole_word_around_punctuation() {
+        let query = Query {
+            whole_word: true,
+            ..Query::new("I'm")
+        };
+        assert_eq!(found(&query), [(2, 4, 7)]);
+    }
+}
@@ -0,0 +1,287 @@
+//! Inline emphasis inside an element's text.
+//!
+//! An element keeps its emphasis as spans over byte ranges of its content.
+//! Span lists are kept normalised: sorted, non-overlapping, with no empty
+//! or plain spans and no two touching spans of the same style. Equal text
+//! with equal emphasis therefore always compares, and hashes, equal.
+
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Style {
+    #[serde(default)]
+    pub bold: bool,
+    #[serde(default)]
+    pub italic: bool,
+    #[serde(default)]
+    pub underline: bool,
+}
+
+/// One of the independent attributes a `Style` combines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Emphasis {
+    Bold,
+    Italic,
+    Underline,
+}
+
+impl Style {
+    pub const PLAIN: Style = Style {
+        bold: false,
+        italic: false,
+        underline: false,
+    };
+
+    pub fn is_plain(self) -> bool {
+        self == Self::PLAIN
+    }
+
+    pub fn has(self, emphasis: Emphasis) -> bool {
+        match emphasis {
+            Emphasis::Bold => self.bold,
+            Emphasis::Italic => self.italic,
+            Emphasis::Underline => self.underline,
+        }
+    }
+
+    /// Every attribute set in either style.
+    pub fn union(self, other: Style) -> Style {
+        Style {
+            bold: self.bold || other.bold,
+            italic: self.italic || other.italic,
+            underline: self.underline || other.underline,
+        }
+    }
+
+    pub fn with(mut self, emphasis: Emphasis, on: bool) -> Style {
+        match emphasis {
+            Emphasis::Bold => self.bold = on,
+            Emphasis::Italic => self.italic = on,
+            Emphasis::Underline => self.underline = on,
+        }
+        self
+    }
+}
+
+/// `style` applied to bytes `start..end` of an element's content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StyleSpan {
+    pub start: usize,
+    pub end: usize,
+    pub style: Style,
+}
+
+impl StyleSpan {
+    pub fn new(range: Range<usize>, style: Style) -> Self {
+        Self {
+            start: range.start,
+            end: range.end,
+            style,
+        }
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+/// Style of the character starting at byte `offset`.
+pub fn style_at(spans: &[StyleSpan], offset: usize) -> Style {
+    spans
+        .iter()
+        .find(|span| span.start <= offset && offset < span.end)
+        .map_or(Style::PLAIN, |span| span.style)
+}
+
+/// The spans covering `range`, measured from its start.
+pub fn slice(spans: &[StyleSpan], range: Range<usize>) -> Vec<StyleSpan> {
+    spans
+        .iter()
+        .filter_map(|span| {
+            let start = span.start.max(range.start);
+            let end = span.end.min(range.end);
+            (start < end).then(|| StyleSpan::new(start - range.start..end - range.start, span.style))
+        })
+        .collect()
+}
+
+/// Adjusts `spans` for the removal of the text in `range`.
+pub fn remove(spans: &mut Vec<StyleSpan>, range: Range<usize>) {
+    let shift = |offset: usize| {
+        if offset <= range.start {
+            offset
+        } else if offset >= range.end {
+            offset - range.len()
+        } else {
+            range.start
+        }
+    };
+    for span in spans.iter_mut() {
+        span.start = shift(span.start);
+        span.end = shift(span.end);
+    }
+    normalize(spans);
+}
+
+/// Adjusts `spans` for `len` bytes inserted at `offset`, styled by
+/// `inserted` (measured from `offset`). A span the text lands inside is
+/// split around it, so inserted text only carries the styles it is given.
+pub fn insert(spans: &mut Vec<StyleSpan>, offset: usize, len: usize, inserted: &[StyleSpan]) {
+    let mut result = Vec::with_capacity(spans.len() + inserted.len() + 1);
+    for span in spans.iter() {
+        if span.end <= offset {
+            result.push(*span);
+        } else if span.start >= offset {
+            result.push(StyleSpan::new(span.start + len..span.end + len, span.style));
+        } else {
+            result.push(StyleSpan::new(span.start..offset, span.style));
+            result.push(StyleSpan::new(offset + len..span.end + len, span.style));
+        }
+    }
+    result.extend(
+        inserted
+            .iter()
+            .map(|span| StyleSpan::new(span.start + offset..span.end + offset, span.style)),
+    );
+    *spans = result;
+    normalize(spans);
+}
+
+/// Restyles every character in `range` with `change`, including ones
+/// that have no style yet.
+pub fn restyle(spans: &mut Vec<StyleSpan>, range: Range<usize>, change: impl Fn(Style) -> Style) {
+    let mut result = Vec::with_capacity(spans.len() + 2);
+    for span in spans.iter() {
+        if span.start < range.start {
+            result.push(StyleSpan::new(span.start..span.end.min(range.start), span.style));
+        }
+        if span.end > range.end {
+            result.push(StyleSpan::new(span.start.max(range.end)..span.end, span.style));
+        }
+    }
+    result.extend(
+        runs(spans, range.clone())
+            .into_iter()
+            .map(|(run, style)| StyleSpan::new(run, change(style))),
+    );
+    *spans = result;
+    normalize(spans);
+}
+
+/// Splits `range` into consecutive runs of one style each, plain gaps
+/// included.
+pub fn runs(spans: &[StyleSpan], range: Range<usize>) -> Vec<(Range<usize>, Style)> {
+    let mut runs = Vec::new();
+    let mut at = range.start;
+    for span in slice(spans, range.clone()) {
+        let (start, end) = (span.start + range.start, span.end + range.start);
+        if at < start {
+            runs.push((at..start, Style::PLAIN));
+        }
+        runs.push((start..end, span.style));
+        at = end;
+    }
+    if at < range.end || runs.is_empty() {
+        runs.push((at..range.end, Style::PLAIN));
+    }
+    runs
+}
+
+/// Repairs spans read from a file so they fit `content`: offsets past the
+/// end are clamped, offsets inside a character move back to its start,
+/// and a span overlapping an earlier one is cut to begin where it ends.
+pub fn sanitize(spans: &mut Vec<StyleSpan>, content: &str) {
+    let clamp = |offset: usize| {
+        let mut offset = offset.min(content.len());
+        while !content.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    };
+    for span in spans.iter_mut() {
+        span.start = clamp(span.start);
+        span.end = clamp(span.end);
+    }
+    spans.sort_by_key(|span| span.start);
+    let mut covered = 0;
+    for span in spans.iter_mut() {
+        span.start = span.start.max(covered);
+        covered = covered.max(span.end);
+    }
+    normalize(spans);
+}
+
+fn normalize(spans: &mut Vec<StyleSpan>) {
+    spans.retain(|span| span.start < span.end && !span.style.is_plain());
+    spans.sort_by_key(|span| span.start);
+    let mut merged: Vec<StyleSpan> = Vec::with_capacity(spans.len());
+    for span in spans.drain(..) {
+        match merged.last_mut() {
+            Some(last) if last.end == span.start && last.style == span.style => last.end = span.end,
+            _ => merged.push(span),
+        }
+    }
+    *spans = merged;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOLD: Style = Style {
+        bold: true,
+        italic: false,
+        underline: false,
+    };
+
+    #[test]
+    fn test_insert_splits_and_shifts_spans() {
+        let mut spans = vec![StyleSpan::new(2..6, BOLD)];
+        insert(&mut spans, 4, 3, &[]);
+        assert_eq!(spans, vec![StyleSpan::new(2..4, BOLD), StyleSpan::new(7..9, BOLD)]);
+
+        insert(&mut spans, 4, 0, &[]);
+        insert(&mut spans, 0, 1, &[]);
+        assert_eq!(spans, vec![StyleSpan::new(3..5, BOLD), StyleSpan::new(8..10, BOLD)]);
+    }
+
+    #[test]
+    fn test_remove_merges_touching_spans() {
+        let mut spans = vec![StyleSpan::new(2..4, BOLD), StyleSpan::new(7..9, BOLD)];
+        remove(&mut spans, 4..7);
+        assert_eq!(spans, vec![StyleSpan::new(2..6, BOLD)]);
+
+        remove(&mut spans, 0..10);
+        assert!(spans.is_empty());
+    }
+
+    #[test]
+    fn test_restyle_toggles_part_of_a_span() {
+        let mut spans = vec![StyleSpan::new(0..10, BOLD)];
+        restyle(&mut spans, 3..12, |style| style.with(Emphasis::Italic, true));
+        let both = BOLD.with(Emphasis::Italic, true);
+        let italic = Style::PLAIN.with(Emphasis::Italic, true);
+        assert_eq!(
+            spans,
+            vec![
+                StyleSpan::new(0..3, BOLD),
+                StyleSpan::new(3..10, both),
+                StyleSpan::new(10..12, italic),
+            ]
+        );
+
+        restyle(&mut spans, 0..12, |style| style.with(Emphasis::Italic, false));
+        assert_eq!(spans, vec![StyleSpan::new(0..10, BOLD)]);
+    }
+
+    #[test]
+    fn test_runs_cover_plain_gaps() {
+        let spans = vec![StyleSpan::new(2..4, BOLD)];
+        assert_eq!(
+            runs(&spans, 0..6),
+            vec![(0..2, Style::PLAIN), (2..4, BOLD), (4..6, Style::PLAIN)]
+        );
+        assert_eq!(runs(&spans, 3..3), vec![(3..3, Style::PLAIN)]);
+    }
+}
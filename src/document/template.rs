@@ -0,0 +1,227 @@
+//! Style sheets: how each type of element is laid out and printed.
+//!
+//! Every script starts from one of the bundled templates and may change
+//! the format of any element type on top of it. Only the changes are kept,
+//! so a script that follows its template exactly stores just the
+//! template's name. Each template also says which type follows which while
+//! typing; changes to that are the writer's, kept in `app::Preferences`.
+
+use super::flow::ElementFlow;
+use super::layout::{Alignment, Column, ElementFormat, LINE_WIDTH};
+use super::{ElementType, Style};
+use serde::{Deserialize, Serialize};
+
+const UNDERLINE: Style = Style {
+    underline: true,
+    ..Style::PLAIN
+};
+
+const BOLD: Style = Style {
+    bold: true,
+    ..Style::PLAIN
+};
+
+const ITALIC: Style = Style {
+    italic: true,
+    ..Style::PLAIN
+};
+
+/// A bundled set of formats for one kind of script.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Template {
+    #[default]
+    FeatureFilm,
+    /// Studio sitcom: action in capitals, dialogue double-spaced.
+    MultiCamera,
+    /// Centered names, full-width dialogue and indented stage directions.
+    StagePlay,
+    /// Radio style: names at the margin and sound cues in capitals.
+    AudioDrama,
+    /// Page and panel headings with balloons set in from the description.
+    ComicScript,
+}
+
+impl Template {
+    pub const ALL: [Template; 5] = [
+        Template::FeatureFilm,
+        Template::MultiCamera,
+        Template::StagePlay,
+        Template::AudioDrama,
+        Template::ComicScript,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Template::FeatureFilm => "Feature Film",
+            Template::MultiCamera => "Multi-Camera Sitcom",
+            Template::StagePlay => "Stage Play",
+            Template::AudioDrama => "Audio Drama",
+            Template::ComicScript => "Comic Script",
+        }
+    }
+
+    /// The format this template gives `element_type`.
+    pub fn format(self, element_type: ElementType) -> ElementFormat {
+        use ElementType::{Action, Character, Dialogue, Lyrics, Parenthetical, SceneHeading, Transition};
+
+        let standard = ElementFormat::for_type(element_type);
+        let at = |indent: usize, width: usize| ElementFormat {
+            indent,
+            width,
+            ..standard
+        };
+        match (self, element_type) {
+            (Template::FeatureFilm, _) => standard,
+
+            (Template::MultiCamera, SceneHeading | Transition) => ElementFormat {
+                style: UNDERLINE,
+                ..standard
+            },
+            (Template::MultiCamera, Action) => ElementFormat {
+                uppercase: true,
+                ..standard
+            },
+            (Template::MultiCamera, Dialogue | Lyrics) => ElementFormat {
+                line_spacing: 2,
+                ..standard
+            },
+
+            (Template::StagePlay, SceneHeading) => ElementFormat {
+                alignment: Alignment::Center,
+                space_after: 1,
+                ..standard
+            },
+            (Template::StagePlay, Action) => ElementFormat {
+                style: ITALIC,
+                ..at(20, 40)
+            },
+            (Template::StagePlay, Character) => ElementFormat {
+                alignment: Alignment::Center,
+                ..at(0, LINE_WIDTH)
+            },
+            (Template::StagePlay, Parenthetical) => ElementFormat {
+                style: ITALIC,
+                ..at(15, 30)
+            },
+            (Template::StagePlay, Dialogue) => at(0, LINE_WIDTH),
+            (Template::StagePlay, Lyrics) => at(10, 40),
+
+            (Template::AudioDrama, SceneHeading) => ElementFormat {
+                style: BOLD,
+                ..standard
+            },
+            (Template::AudioDrama, Action) => ElementFormat {
+                uppercase: true,
+                ..standard
+            },
+            (Template::AudioDrama, Character) => at(0, LINE_WIDTH),
+            (Template::AudioDrama, Parenthetical | Dialogue | Lyrics) => at(10, 50),
+
+            (Template::ComicScript, SceneHeading) => ElementFormat {
+                style: Style {
+                    bold: true,
+                    underline: true,
+                    ..Style::PLAIN
+                },
+                ..standard
+            },
+            (Template::ComicScript, Character) => at(10, 50),
+            (Template::ComicScript, Parenthetical) => at(15, 45),
+            (Template::ComicScript, Dialogue | Lyrics) => at(20, 40),
+
+            _ => standard,
+        }
+    }
+
+    /// The flow this template gives `element_type`. Plays, audio drama and
+    /// comics mostly go from one speech straight to the next.
+    pub fn flow(self, element_type: ElementType) -> ElementFlow {
+        let standard = ElementFlow::for_type(element_type);
+        match (self, element_type) {
+            (Template::StagePlay | Template::AudioDrama | Template::ComicScript, ElementType::Dialogue) => {
+                ElementFlow {
+                    enter: ElementType::Character,
+                    ..standard
+                }
+            }
+            _ => standard,
+        }
+    }
+}
+
+impl std::fmt::Display for Template {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The formats a script is laid out with: a template and the element
+/// types whose format differs from it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StyleSheet {
+    pub template: Template,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    overrides: Vec<(ElementType, ElementFormat)>,
+}
+
+impl StyleSheet {
+    pub fn new(template: Template) -> Self {
+        Self {
+            template,
+            overrides: Vec::new(),
+        }
+    }
+
+    pub fn format(&self, element_type: ElementType) -> ElementFormat {
+        self.overrides
+            .iter()
+            .find(|(overridden, _)| *overridden == element_type)
+            .map_or_else(|| self.template.format(element_type), |&(_, format)| format)
+    }
+
+    /// The format of an element of `element_type` printed in `column`.
+    pub fn format_in(&self, element_type: ElementType, column: Column) -> ElementFormat {
+        self.format(element_type).in_column(element_type, column)
+    }
+
+    /// Gives `element_type` its own format, or returns it to the
+    /// template's when `format` is the same.
+    pub fn set_format(&mut self, element_type: ElementType, format: ElementFormat) {
+        self.overrides.retain(|(overridden, _)| *overridden != element_type);
+        if format != self.template.format(element_type) {
+            self.overrides.push((element_type, format));
+        }
+    }
+
+    /// Whether any element type has a format of its own.
+    pub fn is_customized(&self) -> bool {
+        !self.overrides.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_film_is_the_standard_format() {
+        let sheet = StyleSheet::default();
+        for element_type in ElementType::ALL {
+            assert_eq!(sheet.format(element_type), ElementFormat::for_type(element_type));
+        }
+        assert_eq!(Template::MultiCamera.format(ElementType::Dialogue).line_spacing, 2);
+        assert!(Template::MultiCamera.format(ElementType::Action).uppercase);
+    }
+
+    #[test]
+    fn test_only_changes_are_kept() {
+        let mut sheet = StyleSheet::new(Template::StagePlay);
+        let dialogue = sheet.format(ElementType::Dialogue);
+        sheet.set_format(ElementType::Dialogue, ElementFormat { indent: 5, ..dialogue });
+        assert_eq!(sheet.format(ElementType::Dialogue).indent, 5);
+        assert!(sheet.is_customized());
+
+        sheet.set_format(ElementType::Dialogue, dialogue);
+        assert_eq!(sheet, StyleSheet::new(Template::StagePlay));
+    }
+}
@@ -0,0 +1,71 @@
+//! Grapheme-cluster helpers for caret positions.
+//!
+//! Offsets into element content are byte indices so they can be used to
+//! slice and edit the string directly, but the caret only ever rests on a
+//! grapheme cluster boundary: an accented letter, an emoji with modifiers or
+//! a flag is one step for movement, backspace and delete. Columns count
+//! grapheme clusters and are what the caret's on-screen position and
+//! vertical movement are based on.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The boundary before the cluster that ends at or contains `offset`.
+pub fn previous_boundary(text: &str, offset: usize) -> usize {
+    let offset = floor_boundary(text, offset);
+    text[..offset]
+        .grapheme_indices(true)
+        .next_back()
+        .map_or(0, |(index, _)| index)
+}
+
+/// The boundary after the cluster that starts at or contains `offset`.
+pub fn next_boundary(text: &str, offset: usize) -> usize {
+    let offset = floor_boundary(text, offset);
+    text[offset..]
+        .graphemes(true)
+        .next()
+        .map_or(text.len(), |grapheme| offset + grapheme.len())
+}
+
+/// The nearest boundary at or before `offset`, clamped to the text.
+pub fn floor_boundary(text: &str, offset: usize) -> usize {
+    if offset >= text.len() {
+        return text.len();
+    }
+    text.grapheme_indices(true)
+        .map(|(index, _)| index)
+        .take_while(|&index| index <= offset)
+        .last()
+        .unwrap_or(0)
+}
+
+/// Number of grapheme clusters before `offset`.
+pub fn column(text: &str, offset: usize) -> usize {
+    text[..floor_boundary(text, offset)].graphemes(true).count()
+}
+
+/// Byte offset of the boundary `column` clusters in, or the end of the text.
+pub fn offset_at_column(text: &str, column: usize) -> usize {
+    text.grapheme_indices(true)
+        .nth(column)
+        .map_or(text.len(), |(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_boundaries_step_over_clusters() {
+        // "e" + combining acute, a thumbs up with skin tone, then "x".
+        let text = "e\u{301}\u{1F44D}\u{1F3FD}x";
+        assert_eq!(next_boundary(text, 0), 3);
+        assert_eq!(next_boundary(text, 3), 11);
+        assert_eq!(previous_boundary(text, text.len()), 11);
+        assert_eq!(previous_boundary(text, 11), 3);
+        assert_eq!(floor_boundary(text, 5), 3);
+        assert_eq!(column(text, text.len()), 3);
+        assert_eq!(offset_at_column(text, 2), 11);
+        assert_eq!(offset_at_column(text, 9), text.len());
+    }
+}
@@ -0,0 +1,218 @@
+//! The title page: the entries a script's cover carries and where they
+//! print.
+//!
+//! Title, author and contact have their own fields on `Screenplay`. Every
+//! other entry lives in `Screenplay::metadata` under its Fountain key, so
+//! entries Quill has no field for survive next to the ones it knows;
+//! `TitleField` gives the known ones a typed handle.
+//!
+//! The layout follows the usual convention: title, credit, author and
+//! source centered a third of the way down, contact details in the bottom
+//! left corner and draft, copyright and registration notices in the bottom
+//! right.
+
+use super::layout::{Alignment, ElementFormat, LINE_WIDTH};
+use super::{ElementType, Screenplay};
+
+pub const DEFAULT_CREDIT: &str = "Written by";
+
+/// Row the title is printed on.
+const TITLE_ROW: usize = 18;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TitleField {
+    /// The line between title and author, "Written by" when absent.
+    Credit,
+    /// Source material, e.g. "Based on the novel by ...".
+    Source,
+    DraftDate,
+    /// Draft or revision label, e.g. "Second Draft" or "Blue Revisions".
+    Revision,
+    Copyright,
+    /// WGA registration number.
+    Wga,
+}
+
+impl TitleField {
+    pub const ALL: [TitleField; 6] = [
+        TitleField::Credit,
+        TitleField::Source,
+        TitleField::DraftDate,
+        TitleField::Revision,
+        TitleField::Copyright,
+        TitleField::Wga,
+    ];
+
+    /// Key the field is stored and exported under.
+    pub fn key(self) -> &'static str {
+        match self {
+            TitleField::Credit => "Credit",
+            TitleField::Source => "Source",
+            TitleField::DraftDate => "Draft date",
+            TitleField::Revision => "Revision",
+            TitleField::Copyright => "Copyright",
+            TitleField::Wga => "WGA",
+        }
+    }
+
+    /// Name shown next to the field when editing.
+    pub fn label(self) -> &'static str {
+        match self {
+            TitleField::Credit => "Credit line",
+            TitleField::Source => "Source material",
+            TitleField::DraftDate => "Draft date",
+            TitleField::Revision => "Draft / revision",
+            TitleField::Copyright => "Copyright",
+            TitleField::Wga => "WGA registration",
+        }
+    }
+
+    /// Whether `key` names this field, accepting the spellings other tools
+    /// write.
+    pub fn matches(self, key: &str) -> bool {
+        let aliases: &[&str] = match self {
+            TitleField::DraftDate => &["date"],
+            TitleField::Wga => &["wga registration", "registration"],
+            _ => &[],
+        };
+        key.eq_ignore_ascii_case(self.key()) || aliases.iter().any(|alias| key.eq_ignore_ascii_case(alias))
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|field| field.matches(key))
+    }
+}
+
+/// One line of the title page, positioned like a body line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TitleLine {
+    pub row: usize,
+    pub column: usize,
+    pub text: String,
+}
+
+pub fn has_title_page(screenplay: &Screenplay) -> bool {
+    screenplay.title != Screenplay::UNTITLED
+        || screenplay.author.is_some()
+        || screenplay.contact.is_some()
+        || !screenplay.metadata.is_empty()
+}
+
+/// Lays the title page out on a page with `lines_per_page` body lines.
+pub fn lines(screenplay: &Screenplay, lines_per_page: usize) -> Vec<TitleLine> {
+    let mut row = TITLE_ROW;
+    let mut lines = Vec::new();
+    let mut centered = |row: usize, text: &str| lines.push(aligned(row, text, Alignment::Center));
+
+    for (offset, text) in screenplay.title.to_uppercase().lines().enumerate() {
+        centered(row + offset, text);
+    }
+    row += screenplay.title.lines().count().max(1) - 1;
+
+    if let Some(author) = &screenplay.author {
+        let credit = screenplay.title_field(TitleField::Credit).unwrap_or(DEFAULT_CREDIT);
+        row += 3;
+        centered(row, credit);
+        row += 1;
+        for text in author.lines() {
+            row += 1;
+            centered(row, text);
+        }
+    }
+
+    if let Some(source) = screenplay.title_field(TitleField::Source) {
+        row += 1;
+        for text in source.lines() {
+            row += 1;
+            centered(row, text);
+        }
+    }
+
+    row += 1;
+    for (key, value) in &screenplay.metadata {
+        if TitleField::from_key(key).is_some() {
+            continue;
+        }
+        for text in value.lines() {
+            row += 1;
+            centered(row, text);
+        }
+    }
+
+    if let Some(contact) = &screenplay.contact {
+        lines.extend(bottom_block(contact.lines(), lines_per_page, Alignment::Left));
+    }
+
+    let notices = [TitleField::DraftDate, TitleField::Revision, TitleField::Copyright, TitleField::Wga]
+        .into_iter()
+        .filter_map(|field| screenplay.title_field(field).map(|value| (field, value)))
+        .flat_map(|(field, value)| {
+            value.lines().map(move |text| match field {
+                TitleField::Wga if !text.to_uppercase().contains("WGA") => format!("WGA Registration {}", text),
+                _ => text.to_string(),
+            })
+        })
+        .collect::<Vec<String>>();
+    lines.extend(bottom_block(
+        notices.iter().map(String::as_str),
+        lines_per_page,
+        Alignment::Right,
+    ));
+
+    lines
+}
+
+/// Lines set against the bottom margin.
+fn bottom_block<'a>(
+    texts: impl Iterator<Item = &'a str>,
+    lines_per_page: usize,
+    alignment: Alignment,
+) -> Vec<TitleLine> {
+    let texts: Vec<&str> = texts.collect();
+    let start = lines_per_page.saturating_sub(texts.len());
+    texts
+        .into_iter()
+        .enumerate()
+        .map(|(offset, text)| aligned(start + offset, text, alignment))
+        .collect()
+}
+
+fn aligned(row: usize, text: &str, alignment: Alignment) -> TitleLine {
+    let format = ElementFormat {
+        indent: 0,
+        width: LINE_WIDTH,
+        alignment,
+        ..ElementFormat::for_type(ElementType::Action)
+    };
+    TitleLine {
+        row,
+        column: format.column(text),
+        text: text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unicode_segmentation::UnicodeSegmentation;
+
+    #[test]
+    fn test_notices_sit_bottom_right() {
+        let mut screenplay = Screenplay::new("Big Fish".to_string());
+        screenplay.author = Some("John August".to_string());
+        screenplay.set_title_field(TitleField::Source, "Based on the novel by Daniel Wallace");
+        screenplay.set_title_field(TitleField::Revision, "Blue Revisions");
+        screenplay.set_title_field(TitleField::Wga, "1234567");
+
+        let lines = lines(&screenplay, 54);
+        let wga = lines.iter().find(|line| line.text == "WGA Registration 1234567").unwrap();
+        assert_eq!(wga.row, 53);
+        assert_eq!(wga.column + wga.text.graphemes(true).count(), LINE_WIDTH);
+        let revision = lines.iter().find(|line| line.text == "Blue Revisions").unwrap();
+        assert_eq!(revision.row, 52);
+
+        let author = lines.iter().position(|line| line.text == "John August").unwrap();
+        assert!(lines[author + 1].text.starts_with("Based on"));
+        assert!(lines[author + 1].row > lines[author].row);
+    }
+}
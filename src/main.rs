@@ -2,7 +2,8 @@ use quill::{App};
 use quill::ui::view;
 
 fn main() -> iced::Result {
-    iced::application("Quill", App::update, view)
+    iced::application(App::title, App::update, view)
         .subscription(App::subscription)
-        .run()
+        .exit_on_close_request(false)
+        .run_with(App::new)
 }
\ No newline at end of file
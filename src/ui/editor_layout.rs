@@ -0,0 +1,620 @@
+//! Screen layout of the editor: every element wrapped to its column
+//! width and stacked with the spacing it gets on the page. Drawing, hit
+//! testing and caret placement all read positions from here so they agree.
+//!
+//! Wrapping is the expensive part, so the wrapped lines of each element are
+//! kept between updates and only redone for the elements an edit changed
+//! and those whose format or column changed. Elements are stacked top to
+//! bottom, which lets lookups by height binary-search instead of walking
+//! the whole script. The two speeches of dual dialogue sit side by side, so
+//! the two halves of a pair are one block that starts at its first cue.
+//!
+//! Elements flow down one continuous draft column, or onto paper pages at
+//! print scale, one canvas pixel to the point. On pages each printed line
+//! is the paginator's own, drawn at the row and column it prints at.
+
+use crate::document::layout::{self, Column, ElementFormat, LEFT_MARGIN_INCHES, LINE_WIDTH, Paper, TOP_MARGIN_INCHES};
+use crate::document::pagination::{Line, LineKind, PageMap};
+use crate::document::template::StyleSheet;
+use crate::document::title_page::TitleLine;
+use crate::document::{Element, ElementChanges, ElementType, Style, TextPosition, dual, pdf, scene_number, style, text};
+use iced::{Point, Rectangle, Size};
+use std::ops::Range;
+use std::sync::Arc;
+use unicode_segmentation::UnicodeSegmentation;
+
+pub const TOP: f32 = 20.0;
+pub const LINE_HEIGHT: f32 = 18.0;
+pub const FONT_SIZE: f32 = 12.0;
+pub const CHAR_WIDTH: f32 = 7.2;
+pub const PIXELS_PER_INCH: f32 = CHAR_WIDTH * layout::CHARS_PER_INCH;
+/// Left edge of the text block, leaving room for a page's left margin.
+pub const LEFT: f32 = TOP + LEFT_MARGIN_INCHES * PIXELS_PER_INCH;
+/// Line pitch on paper pages: six lines to the inch.
+pub const PAGE_LINE_HEIGHT: f32 = PIXELS_PER_INCH / layout::LINES_PER_INCH;
+/// Space between consecutive pages.
+pub const PAGE_GAP: f32 = 24.0;
+
+/// How elements are placed on the canvas.
+#[derive(Debug, Clone, Copy)]
+pub enum Flow<'a> {
+    /// One continuous column with the page spacing between elements.
+    Draft,
+    /// Line for line where `pages` prints them on `paper`, after the title
+    /// page when there is one.
+    Pages {
+        pages: &'a PageMap,
+        paper: Paper,
+        title_page: Option<&'a [TitleLine]>,
+    },
+}
+
+/// A sheet drawn in page view with the text printed on it besides the
+/// script's own lines.
+#[derive(Debug, Clone)]
+pub struct PageFrame {
+    pub bounds: Rectangle,
+    pub labels: Vec<Label>,
+}
+
+/// Printed text that is not part of any element: page numbers, `(MORE)`
+/// and `(CONT'D)` cues.
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub text: String,
+    pub position: Point,
+}
+
+/// One wrapped line of an element, independent of where the element sits.
+#[derive(Debug, Clone)]
+pub struct LineShape {
+    /// Bytes of the element's content shown on this line.
+    pub range: Range<usize>,
+    /// The text drawn, including case changes and parentheses, split
+    /// wherever its emphasis changes.
+    pub runs: Vec<TextRun>,
+    /// Where the content starts; differs from where the first run is drawn
+    /// when a parenthesis precedes it.
+    pub x: f32,
+    /// Scene numbers drawn in the margins beside the line, which take no
+    /// part in hit-testing.
+    pub margin: Vec<TextRun>,
+}
+
+impl LineShape {
+    /// X coordinate just past the last character drawn.
+    fn end_x(&self) -> f32 {
+        self.runs
+            .last()
+            .map_or(self.x, |run| run.x + run.text.graphemes(true).count() as f32 * CHAR_WIDTH)
+    }
+}
+
+/// Part of a line drawn in one style.
+#[derive(Debug, Clone)]
+pub struct TextRun {
+    pub text: String,
+    pub x: f32,
+    pub style: Style,
+}
+
+/// A wrapped line placed on the canvas.
+#[derive(Debug, Clone, Copy)]
+pub struct VisualLine<'a> {
+    pub element: usize,
+    /// Index of this line among the element's wrapped lines.
+    pub line: usize,
+    pub shape: &'a LineShape,
+    pub y: f32,
+}
+
+#[derive(Debug)]
+struct ElementShape {
+    lines: Vec<LineShape>,
+    space_before: f32,
+    space_after: f32,
+    /// Distance from the top of one line to the next in draft view.
+    line_pitch: f32,
+}
+
+impl ElementShape {
+    fn new(element: &Element, format: &ElementFormat) -> Self {
+        let ranges = layout::content_lines(element, format);
+        let last = ranges.len() - 1;
+        let lines = ranges
+            .into_iter()
+            .enumerate()
+            .map(|(line, range)| line_shape(element, format, line, line == last, range))
+            .collect();
+        Self::with_lines(lines, format)
+    }
+
+    fn with_lines(lines: Vec<LineShape>, format: &ElementFormat) -> Self {
+        Self {
+            lines,
+            space_before: format.space_before as f32 * LINE_HEIGHT,
+            space_after: format.space_after as f32 * LINE_HEIGHT,
+            line_pitch: format.line_spacing.max(1) as f32 * LINE_HEIGHT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EditorLayout {
+    /// Shapes of the elements as placed in the current flow.
+    shapes: Vec<Arc<ElementShape>>,
+    /// Every element wrapped for draft view.
+    draft: Vec<Arc<ElementShape>>,
+    /// Elements shaped from the lines the paginator printed, once the page
+    /// view has shown them.
+    printed: Vec<Option<Arc<ElementShape>>>,
+    /// The style sheet the shapes were made with.
+    style_sheet: StyleSheet,
+    columns: Vec<Column>,
+    /// The first element of the block each element is placed in: itself,
+    /// or the left-hand cue of a dual dialogue pair.
+    blocks: Vec<usize>,
+    /// Y of every line, element by element.
+    ys: Vec<f32>,
+    /// Index into `ys` of the first line of each element.
+    starts: Vec<usize>,
+    pages: Vec<PageFrame>,
+    line_height: f32,
+    height: f32,
+    revision: u64,
+    generation: u64,
+}
+
+impl EditorLayout {
+    /// Lays `elements` out in the feature film format.
+    pub fn new(elements: &[Element]) -> Self {
+        let mut layout = Self::default();
+        layout.update(elements, &StyleSheet::default(), Flow::Draft, 0, Some(ElementChanges::ALL));
+        layout
+    }
+
+    /// Lays `elements` out again in `style_sheet` for screenplay revision
+    /// `revision` after `changes` to them, reusing the lines of every other
+    /// element whose format stayed the same.
+    pub fn update(
+        &mut self,
+        elements: &[Element],
+        style_sheet: &StyleSheet,
+        flow: Flow,
+        revision: u64,
+        changes: Option<ElementChanges>,
+    ) {
+        let changes = match changes {
+            _ if self.style_sheet != *style_sheet => ElementChanges::ALL,
+            Some(changes) => changes,
+            None if self.draft.len() == elements.len() => ElementChanges {
+                start: elements.len(),
+                tail: 0,
+            },
+            None => ElementChanges::ALL,
+        };
+        let (start, tail) = changes.kept(self.draft.len(), elements.len());
+        let (old_end, end) = (self.draft.len() - tail, elements.len() - tail);
+        let columns = dual::columns(elements);
+        let shape = |index: usize| {
+            let element = &elements[index];
+            Arc::new(ElementShape::new(element, &style_sheet.format_in(element.element_type, columns[index])))
+        };
+        self.draft.splice(start..old_end, (start..end).map(shape));
+        self.printed.splice(start..old_end, (start..end).map(|_| None));
+        self.columns.splice(start..old_end, columns[start..end].iter().copied());
+        // Joining or splitting a dual pair moves speeches the edit did not
+        // touch into another column.
+        for index in (0..elements.len()).filter(|&index| self.columns[index] != columns[index]) {
+            self.draft[index] = shape(index);
+            self.printed[index] = None;
+        }
+        self.columns = columns;
+        if self.style_sheet != *style_sheet {
+            self.style_sheet = style_sheet.clone();
+        }
+        self.shapes.clone_from(&self.draft);
+
+        self.blocks.clear();
+        for (index, &column) in self.columns.iter().enumerate() {
+            let previous = index.checked_sub(1).map_or(Column::Full, |previous| self.columns[previous]);
+            let block = match (previous, column) {
+                (Column::Left, Column::Left | Column::Right) | (Column::Right, Column::Right) => self.blocks[index - 1],
+                _ => index,
+            };
+            self.blocks.push(block);
+        }
+        self.revision = revision;
+        self.generation = self.generation.wrapping_add(1);
+
+        self.ys.clear();
+        self.starts.clear();
+        self.pages.clear();
+        match flow {
+            Flow::Draft => self.place_draft(),
+            Flow::Pages {
+                pages,
+                paper,
+                title_page,
+            } => self.place_pages(elements, style_sheet, pages, paper, title_page),
+        }
+    }
+
+    fn place_draft(&mut self) {
+        self.line_height = LINE_HEIGHT;
+        let mut y = TOP;
+        // Bottom of the left-hand speech of a dual pair while the
+        // right-hand one is placed beside it.
+        let mut left_bottom = None;
+        for (index, shape) in self.shapes.iter().enumerate() {
+            let block = self.blocks[index];
+            if self.columns[index] == Column::Right && self.columns[index - 1] == Column::Left {
+                left_bottom = Some(y);
+                y = self.ys[self.starts[block]];
+            } else {
+                if let Some(bottom) = left_bottom.take() {
+                    y = y.max(bottom);
+                }
+                if index > 0 {
+                    y += self.shapes[index - 1].space_after + shape.space_before;
+                }
+            }
+            self.starts.push(self.ys.len());
+            for line in 0..shape.lines.len() {
+                if line > 0 {
+                    y += shape.line_pitch - LINE_HEIGHT;
+                }
+                self.ys.push(y);
+                y += LINE_HEIGHT;
+            }
+        }
+        if let Some(bottom) = left_bottom {
+            y = y.max(bottom);
+        }
+        self.height = y + TOP;
+    }
+
+    fn place_pages(
+        &mut self,
+        elements: &[Element],
+        style_sheet: &StyleSheet,
+        pages: &PageMap,
+        paper: Paper,
+        title_page: Option<&[TitleLine]>,
+    ) {
+        self.line_height = PAGE_LINE_HEIGHT;
+        let size = Size::new(
+            paper.width_inches() * PIXELS_PER_INCH,
+            paper.height_inches() * PIXELS_PER_INCH,
+        );
+        let sheet = |index: usize| Rectangle::new(Point::new(TOP, TOP + index as f32 * (size.height + PAGE_GAP)), size);
+        let text_x = |column: usize| LEFT + column as f32 * CHAR_WIDTH;
+        let body_y = |bounds: Rectangle, row: isize| {
+            bounds.y + TOP_MARGIN_INCHES * PIXELS_PER_INCH + row as f32 * PAGE_LINE_HEIGHT
+        };
+
+        if let Some(lines) = title_page {
+            let bounds = sheet(0);
+            let labels = lines
+                .iter()
+                .map(|line| Label {
+                    text: line.text.clone(),
+                    position: Point::new(text_x(line.column), body_y(bounds, line.row as isize)),
+                })
+                .collect();
+            self.pages.push(PageFrame { bounds, labels });
+        }
+
+        let mut printed: Vec<Vec<(f32, &Line)>> = vec![Vec::new(); self.shapes.len()];
+        for page in pages.pages() {
+            let bounds = sheet(self.pages.len());
+            let row_y = |row: isize| body_y(bounds, row);
+
+            let mut labels = Vec::new();
+            if let Some(number) = pdf::page_number(page.number) {
+                labels.push(Label {
+                    position: Point::new(text_x(LINE_WIDTH - number.len()), row_y(-pdf::PAGE_NUMBER_OFFSET)),
+                    text: number,
+                });
+            }
+            for line in &page.lines {
+                let y = row_y(line.row as isize);
+                match line.kind {
+                    LineKind::Element { index, .. } => {
+                        if let Some(lines) = printed.get_mut(index) {
+                            lines.push((y, line));
+                        }
+                    }
+                    LineKind::More | LineKind::Continued { .. } => labels.push(Label {
+                        text: line.text.clone(),
+                        position: Point::new(text_x(line.column), y),
+                    }),
+                }
+            }
+            self.pages.push(PageFrame { bounds, labels });
+        }
+
+        // Printed elements show their lines as printed, wrapped the way
+        // the paginator wrapped them. Blank and outline elements print
+        // nothing and keep the editor's lines, following the previous one.
+        let first_page = self.pages.len() - pages.page_count();
+        let mut y = self.pages.get(first_page).map_or(TOP, |page| page.bounds.y) + TOP_MARGIN_INCHES * PIXELS_PER_INCH
+            - PAGE_LINE_HEIGHT;
+        for (index, printed) in printed.into_iter().enumerate() {
+            self.starts.push(self.ys.len());
+            if printed.is_empty() {
+                for _ in 0..self.shapes[index].lines.len() {
+                    y += PAGE_LINE_HEIGHT;
+                    self.ys.push(y);
+                }
+                continue;
+            }
+            y = printed.last().map_or(y, |&(line_y, _)| line_y);
+            self.ys.extend(printed.iter().map(|&(line_y, _)| line_y));
+            let element = &elements[index];
+            let format = style_sheet.format_in(element.element_type, self.columns[index]);
+            let shape = self.printed[index].get_or_insert_with(|| {
+                let ranges = layout::printed_lines(element, &format);
+                let lines = printed
+                    .into_iter()
+                    .map(|(_, line)| {
+                        let LineKind::Element { line: number, .. } = line.kind else {
+                            unreachable!("only element lines are collected");
+                        };
+                        let (range, lead) = ranges.get(number).cloned().unwrap_or_default();
+                        printed_line_shape(element, line, number, range, lead)
+                    })
+                    .collect();
+                Arc::new(ElementShape::with_lines(lines, &format))
+            });
+            self.shapes[index] = Arc::clone(shape);
+        }
+        self.height = self.pages.last().map_or(0.0, |page| page.bounds.y + page.bounds.height) + TOP;
+    }
+
+    /// The screenplay revision this layout was computed for.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Changes whenever the layout is recomputed, including when only the
+    /// flow changed.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Height of the laid out script including the margins.
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    /// Distance between consecutive lines of an element.
+    pub fn line_height(&self) -> f32 {
+        self.line_height
+    }
+
+    /// The paper pages, empty in draft view.
+    pub fn pages(&self) -> &[PageFrame] {
+        &self.pages
+    }
+
+    fn top_of(&self, element: usize) -> f32 {
+        self.ys[self.starts[element]]
+    }
+
+    fn lines_of(&self, element: usize) -> impl Iterator<Item = VisualLine<'_>> {
+        let ys = self.starts.get(element).map_or(&[][..], |&start| &self.ys[start..]);
+        self.shapes
+            .get(element)
+            .into_iter()
+            .flat_map(|shape| shape.lines.iter().enumerate())
+            .zip(ys)
+            .map(move |((line, shape), &y)| VisualLine { element, line, shape, y })
+    }
+
+    /// Index of the first element of the last block that starts at or
+    /// above `y`.
+    fn element_at(&self, y: f32) -> usize {
+        let index = self.blocks.partition_point(|&block| self.top_of(block) <= y).saturating_sub(1);
+        self.blocks.get(index).copied().unwrap_or(0)
+    }
+
+    /// Index just past the block element `element` is placed in.
+    fn block_end(&self, element: usize) -> usize {
+        let block = self.blocks[element];
+        element + self.blocks[element..].iter().take_while(|&&other| other == block).count()
+    }
+
+    /// The lines that intersect the band from `top` to `bottom`.
+    pub fn lines_between(&self, top: f32, bottom: f32) -> impl Iterator<Item = VisualLine<'_>> {
+        (self.element_at(top)..self.shapes.len())
+            .take_while(move |&element| self.top_of(self.blocks[element]) <= bottom)
+            .flat_map(|element| self.lines_of(element))
+            .filter(move |line| line.y + self.line_height >= top && line.y <= bottom)
+    }
+
+    /// Top-left of the caret at `offset` in element `element`.
+    pub fn caret(&self, elements: &[Element], element: usize, offset: usize) -> Option<Point> {
+        let content = &elements.get(element)?.content;
+        let lines: Vec<VisualLine> = self.lines_of(element).collect();
+        let ranges: Vec<Range<usize>> = lines.iter().map(|line| line.shape.range.clone()).collect();
+        let line = lines.get(layout::line_at(&ranges, offset))?;
+        Some(Point::new(x_of(content, line.shape, offset), line.y))
+    }
+
+    /// The text position under `point`: the nearest line, then the nearest
+    /// character boundary on it. Of two lines side by side, the nearer one
+    /// across wins.
+    pub fn hit_test(&self, elements: &[Element], point: Point) -> Option<TextPosition> {
+        if self.shapes.is_empty() {
+            return None;
+        }
+        let element = self.element_at(point.y);
+        // A point in the gap below an element may be nearer the next one.
+        let end = self.shapes.len().min(self.block_end(element) + 1);
+        let line = (element..end).flat_map(|element| self.lines_of(element)).min_by(|a, b| {
+            let distance = |line: &VisualLine| {
+                let across = (line.shape.x - point.x).max(point.x - line.shape.end_x()).max(0.0);
+                ((line.y + FONT_SIZE / 2.0 - point.y).abs(), across)
+            };
+            let (a, b) = (distance(a), distance(b));
+            a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1))
+        })?;
+        let element = elements.get(line.element)?;
+
+        let column = ((point.x - line.shape.x) / CHAR_WIDTH).round().max(0.0) as usize;
+        let ranges: Vec<Range<usize>> = self.shapes[line.element].lines.iter().map(|line| line.range.clone()).collect();
+        let offset = layout::offset_on_line(&element.content, &ranges, line.line, column);
+        Some(TextPosition::new(line.element, offset))
+    }
+}
+
+/// X coordinate of `offset` when drawn on `line` of an element with
+/// `content`.
+pub fn x_of(content: &str, line: &LineShape, offset: usize) -> f32 {
+    let offset = offset.clamp(line.range.start, line.range.end);
+    line.x + text::column(&content[line.range.start..], offset - line.range.start) as f32 * CHAR_WIDTH
+}
+
+fn line_shape(element: &Element, format: &ElementFormat, line: usize, last: bool, range: Range<usize>) -> LineShape {
+    let mut parts = element.formatted_runs(range.clone(), format);
+
+    let (column, text_column) = match element.element_type {
+        ElementType::Parenthetical => {
+            if line == 0 {
+                parts.insert(0, ("(".to_string(), Style::PLAIN));
+            }
+            if last {
+                parts.push((")".to_string(), Style::PLAIN));
+            }
+            let text_column = if line == 0 { format.indent } else { format.indent + 1 };
+            (format.indent + 1, text_column)
+        }
+        element_type if !layout::marker(element_type).is_empty() => {
+            let marker = layout::marker(element_type);
+            let width = marker.graphemes(true).count();
+            if line == 0 {
+                parts.insert(0, (marker.to_string(), Style::PLAIN));
+            }
+            let text_column = if line == 0 { format.indent } else { format.indent + width };
+            (format.indent + width, text_column)
+        }
+        _ => {
+            let text: String = parts.iter().map(|(text, _)| text.as_str()).collect();
+            let column = format.column(&text);
+            (column, column)
+        }
+    };
+
+    let mut x = LEFT + text_column as f32 * CHAR_WIDTH;
+    let runs = parts
+        .into_iter()
+        .filter(|(text, _)| !text.is_empty())
+        .map(|(text, style)| {
+            let run = TextRun { x, style, text };
+            x += run.text.graphemes(true).count() as f32 * CHAR_WIDTH;
+            run
+        })
+        .collect();
+
+    LineShape {
+        range,
+        runs,
+        x: LEFT + column as f32 * CHAR_WIDTH,
+        margin: margin(element, line),
+    }
+}
+
+/// Printed line `number` of `element` as the paginator placed it, showing
+/// `range` of the content after `lead` columns of added text.
+fn printed_line_shape(element: &Element, printed: &Line, number: usize, range: Range<usize>, lead: usize) -> LineShape {
+    let mut x = LEFT + printed.column as f32 * CHAR_WIDTH;
+    let runs = style::runs(&printed.styles, 0..printed.text.len())
+        .into_iter()
+        .filter(|(run, _)| !run.is_empty())
+        .map(|(run, style)| {
+            let run = TextRun {
+                text: printed.text[run].to_string(),
+                x,
+                style,
+            };
+            x += run.text.graphemes(true).count() as f32 * CHAR_WIDTH;
+            run
+        })
+        .collect();
+
+    LineShape {
+        range,
+        runs,
+        x: LEFT + (printed.column + lead) as f32 * CHAR_WIDTH,
+        margin: margin(element, number),
+    }
+}
+
+/// The scene number drawn in both margins beside the first line of a
+/// numbered heading.
+fn margin(element: &Element, line: usize) -> Vec<TextRun> {
+    match scene_number::printed(element) {
+        Some(number) if line == 0 => {
+            let (left, right) = layout::scene_number_columns(number);
+            [left, right]
+                .into_iter()
+                .map(|column| TextRun {
+                    text: number.to_string(),
+                    x: LEFT + column as f32 * CHAR_WIDTH,
+                    style: Style::PLAIN,
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elements(count: usize) -> Vec<Element> {
+        (0..count)
+            .map(|n| Element::new(ElementType::Action, format!("Beat {}.", n)))
+            .collect()
+    }
+
+    #[test]
+    fn test_update_reuses_unchanged_elements() {
+        let mut elements = elements(3);
+        let mut layout = EditorLayout::new(&elements);
+        let (first, second, last) = (
+            Arc::clone(&layout.shapes[0]),
+            Arc::clone(&layout.shapes[1]),
+            Arc::clone(&layout.shapes[2]),
+        );
+
+        elements[1].content.push_str(" And more.");
+        let changes = ElementChanges { start: 1, tail: 1 };
+        layout.update(&elements, &StyleSheet::default(), Flow::Draft, 1, Some(changes));
+        assert!(Arc::ptr_eq(&first, &layout.shapes[0]));
+        assert!(!Arc::ptr_eq(&second, &layout.shapes[1]));
+        assert!(Arc::ptr_eq(&last, &layout.shapes[2]));
+
+        elements.insert(1, Element::new(ElementType::Action, "Inserted.".to_string()));
+        let changes = ElementChanges { start: 1, tail: 2 };
+        layout.update(&elements, &StyleSheet::default(), Flow::Draft, 2, Some(changes));
+        assert_eq!(layout.shapes.len(), 4);
+        assert!(Arc::ptr_eq(&first, &layout.shapes[0]));
+        assert!(Arc::ptr_eq(&last, &layout.shapes[3]));
+    }
+
+    #[test]
+    fn test_lines_between_only_yields_visible_lines() {
+        let elements = elements(1000);
+        let layout = EditorLayout::new(&elements);
+        let top = layout.top_of(500);
+        let lines: Vec<usize> = layout
+            .lines_between(top, top + 10.0 * LINE_HEIGHT)
+            .map(|line| line.element)
+            .collect();
+        assert_eq!(lines.first(), Some(&500));
+        assert!(lines.len() <= 11);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod editor_layout;
 mod view;
 mod screenplay_editor;
 
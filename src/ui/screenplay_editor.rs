@@ -1,44 +1,191 @@
-use crate::document::{Element, ElementType};
-use iced::widget::canvas;
-use iced::{mouse, Font, Point, Rectangle, Renderer, Theme};
+use super::editor_layout::{self, EditorLayout, FONT_SIZE, LINE_HEIGHT};
+use crate::app::{Message, Viewport};
+use crate::document::{Element, Note, NoteColor, TextPosition, TextRange};
+use iced::widget::canvas::{self, event, Event};
+use iced::{mouse, Color, Font, Point, Rectangle, Renderer, Size, Theme, Vector};
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+use unicode_segmentation::UnicodeSegmentation;
 
-pub struct ScreenplayEditor {
-    elements: Vec<Element>,
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+const DOUBLE_CLICK_DISTANCE: f32 = 4.0;
+const SCROLLBAR_WIDTH: f32 = 10.0;
+const MIN_THUMB_HEIGHT: f32 = 24.0;
+/// Distance from the bottom of the text to its underline.
+const UNDERLINE_GAP: f32 = 1.0;
+const NOTE_MARKER_WIDTH: f32 = 6.0;
+/// Space between a note marker and the text block.
+const NOTE_MARKER_GAP: f32 = 3.0 * editor_layout::CHAR_WIDTH;
+/// Space on either side of the names in the completion popup.
+const COMPLETION_PADDING: f32 = 4.0;
+/// Pixels scrolled per line reported by a mouse wheel.
+const WHEEL_LINE: f32 = 3.0 * LINE_HEIGHT;
+
+pub struct ScreenplayEditor<'a> {
+    elements: &'a [Element],
+    layout: &'a EditorLayout,
+    viewport: Viewport,
     cursor_position: usize,
     cursor_offset: usize,
     cursor_visible: bool,
+    selection: Option<TextRange>,
+    notes: &'a [Note],
+    current_note: Option<u64>,
+    matches: &'a [TextRange],
+    matches_generation: u64,
+    completions: &'a [String],
+    selected_completion: usize,
+}
+
+/// Mouse state and drawing caches kept by the canvas between frames.
+#[derive(Default)]
+pub struct EditorState {
+    dragging: bool,
+    /// Where the scrollbar thumb was grabbed, measured from its top.
+    thumb_grab: Option<f32>,
+    last_press: Option<(Instant, Point)>,
+    /// Text, selection and scrollbar; everything but the blinking caret.
+    page: canvas::Cache,
+    /// What `page` was last drawn for.
+    page_key: Cell<Option<PageKey>>,
+}
+
+/// Everything the cached layer depends on. The layer is redrawn only when
+/// one of these changes, so a blinking caret or an idle tick costs nothing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PageKey {
+    generation: u64,
+    offset: f32,
+    size: Size,
+    selection: Option<TextRange>,
+    current_note: Option<u64>,
+    matches_generation: u64,
+}
+
+impl EditorState {
+    /// Records a press and reports whether it completes a double click.
+    fn press(&mut self, point: Point) -> bool {
+        let now = Instant::now();
+        let double = self.last_press.is_some_and(|(time, previous)| {
+            now.duration_since(time) <= DOUBLE_CLICK_INTERVAL
+                && previous.distance(point) <= DOUBLE_CLICK_DISTANCE
+        });
+        // A third click starts over rather than chaining another double.
+        self.last_press = if double { None } else { Some((now, point)) };
+        double
+    }
+}
+
+struct Scrollbar {
+    track: Rectangle,
+    thumb: Rectangle,
+    min: f32,
+    max: f32,
+}
+
+impl Scrollbar {
+    /// The scroll offset that puts the top of the thumb at `thumb_top`.
+    fn offset_at(&self, thumb_top: f32) -> f32 {
+        let travel = (self.track.height - self.thumb.height).max(1.0);
+        let fraction = ((thumb_top - self.track.y) / travel).clamp(0.0, 1.0);
+        self.min + fraction * (self.max - self.min)
+    }
 }
 
-impl ScreenplayEditor {
-    pub fn new(elements: Vec<Element>, cursor_position: usize, cursor_offset: usize, cursor_visible: bool) -> Self {
+impl<'a> ScreenplayEditor<'a> {
+    pub fn new(
+        elements: &'a [Element],
+        layout: &'a EditorLayout,
+        viewport: Viewport,
+        cursor_position: usize,
+        cursor_offset: usize,
+        cursor_visible: bool,
+        selection: Option<TextRange>,
+    ) -> Self {
         Self {
             elements,
+            layout,
+            viewport,
             cursor_position,
             cursor_offset,
             cursor_visible,
+            selection,
+            notes: &[],
+            current_note: None,
+            matches: &[],
+            matches_generation: 0,
+            completions: &[],
+            selected_completion: 0,
         }
     }
-}
 
-impl<Message> canvas::Program<Message> for ScreenplayEditor {
-    type State = ();
+    /// Shows a margin marker for each of `notes` and highlights the text
+    /// of the one open in the note panel.
+    pub fn notes(mut self, notes: &'a [Note], current_note: Option<u64>) -> Self {
+        self.notes = notes;
+        self.current_note = current_note;
+        self
+    }
 
-    fn draw(
-        &self,
-        _state: &Self::State,
-        renderer: &Renderer,
-        theme: &Theme,
-        bounds: Rectangle,
-        _cursor: mouse::Cursor,
-    ) -> Vec<canvas::Geometry> {
-        let mut frame = canvas::Frame::new(renderer, bounds.size());
+    /// Highlights the find bar's `matches`, which must be in script order.
+    /// `generation` changes whenever they do.
+    pub fn matches(mut self, matches: &'a [TextRange], generation: u64) -> Self {
+        self.matches = matches;
+        self.matches_generation = generation;
+        self
+    }
 
-        let mut y_position = 20.0;
-        let line_height = 24.0;
-        let font_size = 12.0;
+    /// Lists `completions` under the element being typed, with the one at
+    /// `selected` highlighted.
+    pub fn completions(mut self, completions: &'a [String], selected: usize) -> Self {
+        self.completions = completions;
+        self.selected_completion = selected;
+        self
+    }
 
-        let char_width = 7.2;
+    /// The text position under `point`, given in canvas coordinates.
+    fn hit_test(&self, point: Point) -> Option<TextPosition> {
+        let point = point + Vector::new(0.0, self.viewport.offset);
+        self.layout.hit_test(self.elements, point)
+    }
 
+    fn scrollbar(&self, size: Size) -> Option<Scrollbar> {
+        let (min, max) = self.viewport.range(self.layout.height());
+        if max <= min {
+            return None;
+        }
+
+        let track = Rectangle::new(
+            Point::new(size.width - SCROLLBAR_WIDTH, 0.0),
+            Size::new(SCROLLBAR_WIDTH, size.height),
+        );
+        let total = max - min + size.height;
+        let height = (size.height * size.height / total).clamp(MIN_THUMB_HEIGHT.min(size.height), size.height);
+        let fraction = (self.viewport.offset - min) / (max - min);
+        let thumb = Rectangle::new(
+            Point::new(track.x, track.y + fraction * (track.height - height)),
+            Size::new(SCROLLBAR_WIDTH, height),
+        );
+        Some(Scrollbar { track, thumb, min, max })
+    }
+
+    /// Grabs the thumb, or pages towards the click when the track is hit.
+    fn press_scrollbar(&self, state: &mut EditorState, scrollbar: &Scrollbar, point: Point) -> Message {
+        if scrollbar.thumb.contains(point) {
+            state.thumb_grab = Some(point.y - scrollbar.thumb.y);
+            return Message::ScrolledTo(self.viewport.offset);
+        }
+        let page = self.viewport.page();
+        if point.y < scrollbar.thumb.y {
+            Message::Scrolled(-page)
+        } else {
+            Message::Scrolled(page)
+        }
+    }
+
+    /// Draws the lines in view with their selection and note markers, and
+    /// the scrollbar.
+    fn draw_page(&self, frame: &mut canvas::Frame, theme: &Theme) {
         let courier_font = Font {
             family: iced::font::Family::Name("Courier New"),
             weight: iced::font::Weight::Normal,
@@ -46,52 +193,340 @@ impl<Message> canvas::Program<Message> for ScreenplayEditor {
             style: iced::font::Style::Normal,
         };
 
+        let palette = theme.extended_palette();
         let text_color = theme.palette().text;
+        let selection_color = Color {
+            a: 0.3,
+            ..theme.palette().primary
+        };
+        let match_color = Color::from_rgba(0.95, 0.8, 0.25, 0.4);
 
-        for (index, element) in self.elements.iter().enumerate() {
-            let (display_text, x_position) = match element.element_type {
-                ElementType::SceneHeading => {
-                    (element.content.to_uppercase(), 100.0)
-                }
-                ElementType::Action => {
-                    (element.content.clone(), 100.0)
+        let line_height = self.layout.line_height();
+        let top = self.viewport.offset;
+        let bottom = top + frame.height();
+
+        if !self.layout.pages().is_empty() {
+            frame.fill_rectangle(Point::ORIGIN, frame.size(), palette.background.weak.color);
+        }
+
+        frame.with_save(|frame| {
+            frame.translate(Vector::new(0.0, -top));
+
+            let pages = self
+                .layout
+                .pages()
+                .iter()
+                .filter(|page| page.bounds.y <= bottom && page.bounds.y + page.bounds.height >= top);
+            for page in pages {
+                frame.fill_rectangle(page.bounds.position(), page.bounds.size(), palette.background.base.color);
+                frame.stroke(
+                    &canvas::Path::rectangle(page.bounds.position(), page.bounds.size()),
+                    canvas::Stroke::default().with_color(palette.background.strong.color),
+                );
+                for label in &page.labels {
+                    frame.fill_text(canvas::Text {
+                        content: label.text.clone(),
+                        position: label.position,
+                        color: text_color,
+                        size: FONT_SIZE.into(),
+                        font: courier_font,
+                        ..canvas::Text::default()
+                    });
                 }
-                ElementType::Character => {
-                    (element.content.to_uppercase(), 250.0)
+            }
+
+            for note in self.notes {
+                let start = note.anchor.start;
+                let Some(position) = self.layout.caret(self.elements, start.element, start.offset) else {
+                    continue;
+                };
+                if position.y + line_height < top || position.y > bottom {
+                    continue;
                 }
-                ElementType::Dialogue => {
-                    (element.content.clone(), 180.0)
+                let alpha = if note.resolved { 0.35 } else { 1.0 };
+                frame.fill_rectangle(
+                    Point::new(editor_layout::LEFT - NOTE_MARKER_GAP - NOTE_MARKER_WIDTH, position.y - 2.0),
+                    Size::new(NOTE_MARKER_WIDTH, line_height),
+                    Color {
+                        a: alpha,
+                        ..note_color(note.color)
+                    },
+                );
+            }
+
+            let current_note = self
+                .current_note
+                .and_then(|id| self.notes.iter().find(|note| note.id == id));
+            let highlights = [
+                current_note.map(|note| {
+                    let color = Color {
+                        a: 0.35,
+                        ..note_color(note.color)
+                    };
+                    (note.anchor, color)
+                }),
+                self.selection.map(|selection| (selection, selection_color)),
+            ];
+
+            for line in self.layout.lines_between(top - line_height, bottom + line_height) {
+                let content = &self.elements[line.element].content;
+                let shape = line.shape;
+                // Matches never span elements, so only this element's can
+                // reach the line.
+                let first = self.matches.partition_point(|range| range.start.element < line.element);
+                let matches = self.matches[first..]
+                    .iter()
+                    .take_while(|range| range.start.element == line.element)
+                    .map(|&range| (range, match_color));
+                for (range, color) in matches.chain(highlights.iter().flatten().copied()) {
+                    let Some((from, to)) = range.span_in(line.element, content.len()) else {
+                        continue;
+                    };
+                    let from = from.max(shape.range.start);
+                    let to = to.min(shape.range.end);
+                    if from < to {
+                        let start = editor_layout::x_of(content, shape, from);
+                        frame.fill_rectangle(
+                            Point::new(start, line.y - 2.0),
+                            Size::new(editor_layout::x_of(content, shape, to) - start, line_height),
+                            color,
+                        );
+                    }
                 }
-                ElementType::Parenthetical => {
-                    (format!("({})", element.content), 220.0)
+
+                for run in shape.runs.iter().chain(&shape.margin) {
+                    let font = Font {
+                        weight: if run.style.bold {
+                            iced::font::Weight::Bold
+                        } else {
+                            iced::font::Weight::Normal
+                        },
+                        style: if run.style.italic {
+                            iced::font::Style::Italic
+                        } else {
+                            iced::font::Style::Normal
+                        },
+                        ..courier_font
+                    };
+                    frame.fill_text(canvas::Text {
+                        content: run.text.clone(),
+                        position: Point::new(run.x, line.y),
+                        color: text_color,
+                        size: FONT_SIZE.into(),
+                        font,
+                        ..canvas::Text::default()
+                    });
+                    if run.style.underline {
+                        let width = run.text.graphemes(true).count() as f32 * editor_layout::CHAR_WIDTH;
+                        frame.fill_rectangle(
+                            Point::new(run.x, line.y + FONT_SIZE + UNDERLINE_GAP),
+                            Size::new(width, 1.0),
+                            text_color,
+                        );
+                    }
                 }
-                ElementType::Transition => {
-                    (element.content.to_uppercase(), 400.0)
+            }
+        });
+
+        if let Some(scrollbar) = self.scrollbar(frame.size()) {
+            frame.fill_rectangle(
+                scrollbar.track.position(),
+                scrollbar.track.size(),
+                palette.background.weak.color,
+            );
+            frame.fill_rectangle(
+                scrollbar.thumb.position(),
+                scrollbar.thumb.size(),
+                palette.background.strong.color,
+            );
+        }
+    }
+}
+
+impl canvas::Program<Message> for ScreenplayEditor<'_> {
+    type State = EditorState;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (event::Status, Option<Message>) {
+        if let Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) = event {
+            let dragged = std::mem::take(&mut state.dragging);
+            state.thumb_grab = None;
+            return (event::Status::Ignored, dragged.then_some(Message::DragEnded));
+        }
+
+        if let Some(grab) = state.thumb_grab
+            && let Event::Mouse(mouse::Event::CursorMoved { .. }) = event
+            && let Some(scrollbar) = self.scrollbar(bounds.size())
+            && let Some(point) = cursor.position_from(bounds.position())
+        {
+            let offset = scrollbar.offset_at(point.y - grab);
+            return (event::Status::Captured, Some(Message::ScrolledTo(offset)));
+        }
+
+        let Some(point) = cursor.position_in(bounds) else {
+            return (event::Status::Ignored, None);
+        };
+
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                if let Some(scrollbar) = self.scrollbar(bounds.size())
+                    && scrollbar.track.contains(point)
+                {
+                    let message = self.press_scrollbar(state, &scrollbar, point);
+                    return (event::Status::Captured, Some(message));
                 }
-            };
 
-            frame.fill_text(canvas::Text {
-                content: display_text.clone(),
-                position: Point::new(x_position, y_position),
-                color: text_color,
-                size: font_size.into(),
-                font: courier_font,
-                ..canvas::Text::default()
-            });
+                let double = state.press(point);
+                let Some(position) = self.hit_test(point) else {
+                    return (event::Status::Captured, None);
+                };
 
-            if index == self.cursor_position && self.cursor_visible {
-                let cursor_x = x_position + (self.cursor_offset as f32 * char_width);
+                let message = if double {
+                    Message::DoubleClickedAt(position)
+                } else {
+                    state.dragging = true;
+                    Message::ClickedAt(position)
+                };
+                (event::Status::Captured, Some(message))
+            }
+            Event::Mouse(mouse::Event::CursorMoved { .. }) if state.dragging => (
+                event::Status::Captured,
+                self.hit_test(point).map(Message::DraggedTo),
+            ),
+            Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                let pixels = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } => -y * WHEEL_LINE,
+                    mouse::ScrollDelta::Pixels { y, .. } => -y,
+                };
+                (event::Status::Captured, Some(Message::Scrolled(pixels)))
+            }
+            _ => (event::Status::Ignored, None),
+        }
+    }
 
+    fn draw(
+        &self,
+        state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let key = PageKey {
+            generation: self.layout.generation(),
+            offset: self.viewport.offset,
+            size: bounds.size(),
+            selection: self.selection,
+            current_note: self.current_note,
+            matches_generation: self.matches_generation,
+        };
+        if state.page_key.replace(Some(key)) != Some(key) {
+            state.page.clear();
+        }
+        let page = state
+            .page
+            .draw(renderer, bounds.size(), |frame| self.draw_page(frame, theme));
+
+        let mut caret = canvas::Frame::new(renderer, bounds.size());
+        if self.cursor_visible
+            && let Some(position) = self.layout.caret(self.elements, self.cursor_position, self.cursor_offset)
+        {
+            caret.fill_rectangle(
+                Point::new(position.x, position.y - 2.0 - self.viewport.offset),
+                Size::new(2.0, self.layout.line_height()),
+                theme.palette().text,
+            );
+        }
+        self.draw_completions(&mut caret, theme);
+
+        vec![page, caret.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &Self::State,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        if state.thumb_grab.is_some() {
+            return mouse::Interaction::Grabbing;
+        }
+        let Some(point) = cursor.position_in(bounds) else {
+            return mouse::Interaction::default();
+        };
+        match self.scrollbar(bounds.size()) {
+            Some(scrollbar) if scrollbar.thumb.contains(point) => mouse::Interaction::Grab,
+            Some(scrollbar) if scrollbar.track.contains(point) => mouse::Interaction::default(),
+            _ => mouse::Interaction::Text,
+        }
+    }
+}
+
+impl ScreenplayEditor<'_> {
+    /// The completion popup, drawn over everything below the caret's line.
+    fn draw_completions(&self, frame: &mut canvas::Frame, theme: &Theme) {
+        if self.completions.is_empty() {
+            return;
+        }
+        let Some(start) = self.layout.caret(self.elements, self.cursor_position, 0) else {
+            return;
+        };
+        let line_height = self.layout.line_height();
+        let columns = self
+            .completions
+            .iter()
+            .map(|completion| completion.graphemes(true).count())
+            .max()
+            .unwrap_or(0);
+        let bounds = Rectangle::new(
+            Point::new(
+                start.x - COMPLETION_PADDING,
+                start.y - 2.0 + line_height - self.viewport.offset,
+            ),
+            Size::new(
+                columns as f32 * editor_layout::CHAR_WIDTH + 2.0 * COMPLETION_PADDING,
+                self.completions.len() as f32 * line_height,
+            ),
+        );
+
+        let palette = theme.extended_palette();
+        frame.fill_rectangle(bounds.position(), bounds.size(), palette.background.base.color);
+        frame.stroke(
+            &canvas::Path::rectangle(bounds.position(), bounds.size()),
+            canvas::Stroke::default().with_color(palette.background.strong.color),
+        );
+        for (i, completion) in self.completions.iter().enumerate() {
+            let y = bounds.y + i as f32 * line_height;
+            if i == self.selected_completion {
                 frame.fill_rectangle(
-                    Point::new(cursor_x, y_position - 2.0),
-                    iced::Size::new(2.0, line_height - 2.0),
-                    text_color,
+                    Point::new(bounds.x, y),
+                    Size::new(bounds.width, line_height),
+                    palette.primary.weak.color,
                 );
             }
-
-            y_position += line_height;
+            frame.fill_text(canvas::Text {
+                content: completion.clone(),
+                position: Point::new(start.x, y + 2.0),
+                color: theme.palette().text,
+                size: FONT_SIZE.into(),
+                font: Font::with_name("Courier New"),
+                ..canvas::Text::default()
+            });
         }
+    }
+}
 
-        vec![frame.into_geometry()]
+fn note_color(color: NoteColor) -> Color {
+    match color {
+        NoteColor::Yellow => Color::from_rgb(0.95, 0.78, 0.2),
+        NoteColor::Green => Color::from_rgb(0.3, 0.72, 0.35),
+        NoteColor::Blue => Color::from_rgb(0.3, 0.55, 0.95),
+        NoteColor::Pink => Color::from_rgb(0.94, 0.45, 0.7),
+        NoteColor::Orange => Color::from_rgb(0.97, 0.58, 0.2),
     }
-}
\ No newline at end of file
+}
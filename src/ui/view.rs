@@ -1,18 +1,21 @@
-use crate::app::{App, Message};
+use crate::app::{App, EDITOR_AREA, ExportFormat, FIND_INPUT, Message, NOTE_INPUT, Screen, ViewMode};
+use crate::document::note::{self, NoteColor};
+use crate::document::{ElementType, Emphasis, Screenplay};
+use crate::document::flow::ElementFlow;
+use crate::document::layout::{Alignment, ElementFormat, Paper};
+use crate::document::template::Template;
+use crate::document::title_page::{DEFAULT_CREDIT, TitleField};
 use crate::ui::ScreenplayEditor;
-use iced::widget::{canvas, column, container, text};
+use iced::widget::{
+    button, canvas, checkbox, column, container, pick_list, row, scrollable, text, text_editor, text_input, toggler,
+};
 use iced::Element;
 
-pub fn view(app: &App) -> Element<'_, Message> {
-    let screenplay_display = canvas(ScreenplayEditor::new(
-        app.screenplay.elements.clone(),
-        app.cursor_position,
-        app.cursor_offset,
-        app.cursor_visible,
-    ))
-    .width(iced::Length::Fill)
-    .height(iced::Length::Fill);
+const FIELD_LABEL_WIDTH: f32 = 140.0;
+const STYLE_CELL_WIDTH: f32 = 72.0;
+const FLOW_CELL_WIDTH: f32 = 160.0;
 
+pub fn view(app: &App) -> Element<'_, Message> {
     let element_type_display = text(format!(
         "Current: {} (Press Tab to change)",
         app.current_element_type.as_str()
@@ -20,19 +23,60 @@ pub fn view(app: &App) -> Element<'_, Message> {
     .size(14);
 
     let info = text(format!(
-        "{} - {} elements | Cursor at element {} offset {}",
+        "{} - {} elements | Page {} of {} | Cursor at element {} offset {}",
         app.screenplay.title,
         app.screenplay.element_count(),
+        app.current_page(),
+        app.page_map.page_count(),
         app.cursor_position,
         app.cursor_offset
     ))
     .size(12);
 
+    let screen_buttons = match app.screen {
+        Screen::Script => row![
+            button("Title Page").on_press(Message::ScreenSelected(Screen::TitlePage)),
+            button("Styles").on_press(Message::ScreenSelected(Screen::Styles)),
+        ],
+        Screen::TitlePage | Screen::Styles => {
+            row![button("Back to Script").on_press(Message::ScreenSelected(Screen::Script))]
+        }
+    }
+    .spacing(8);
+
+    let toolbar = row![
+        button("New").on_press(Message::NewFile),
+        button("Open").on_press(Message::OpenFile),
+        button("Save").on_press(Message::SaveFile),
+        button("Save As").on_press(Message::SaveFileAs),
+        button("Export Fountain").on_press(Message::Export(ExportFormat::Fountain)),
+        button("Export FDX").on_press(Message::Export(ExportFormat::FinalDraft)),
+        button("Export PDF").on_press(Message::Export(ExportFormat::Pdf)),
+        button("Export Notes").on_press(Message::Export(ExportFormat::NotesReport)),
+        screen_buttons,
+        toggler(app.viewport.typewriter)
+            .label("Typewriter")
+            .on_toggle(|_| Message::ToggleTypewriter),
+        pick_list(ViewMode::ALL, Some(app.view_mode), Message::ViewModeSelected),
+        pick_list(Paper::ALL, Some(app.screenplay.paper), Message::PaperSelected),
+    ]
+    .spacing(8);
+
+    let status = text(app.status.clone().unwrap_or_default()).size(12);
+
+    let main = match app.screen {
+        Screen::Script => script_editor(app),
+        Screen::TitlePage => title_page_form(app),
+        Screen::Styles => styles_form(app),
+    };
+
     let content = column![
         text("Quill").size(32),
+        toolbar,
         info,
         element_type_display,
-        screenplay_display,
+        status,
+        main,
     ]
     .spacing(10)
     .padding(20);
@@ -41,4 +85,288 @@ pub fn view(app: &App) -> Element<'_, Message> {
         .width(iced::Length::Fill)
         .height(iced::Length::Fill)
         .into()
-}
\ No newline at end of file
+}
+
+fn script_editor(app: &App) -> Element<'_, Message> {
+    let editor = canvas(
+        ScreenplayEditor::new(
+            &app.screenplay.elements,
+            app.editor_layout(),
+            app.viewport,
+            app.cursor_position,
+            app.cursor_offset,
+            app.cursor_visible,
+            app.selection(),
+        )
+        .notes(&app.screenplay.notes, app.current_note)
+        .matches(&app.find.matches, app.find.generation)
+        .completions(&app.completions, app.selected_completion),
+    )
+    .width(iced::Length::Fill)
+    .height(iced::Length::Fill);
+    let editor = container(editor).id(container::Id::new(EDITOR_AREA));
+
+    column![]
+        .push(scene_bar(app))
+        .push_maybe(find_bar(app))
+        .push_maybe(note_panel(app))
+        .push(editor)
+        .spacing(8)
+        .into()
+}
+
+/// Scene numbering commands. Renumbering is off while numbers are locked.
+fn scene_bar(app: &App) -> Element<'_, Message> {
+    let locked = app.screenplay.scenes_locked;
+    row![
+        button("Number Scenes").on_press(Message::NumberScenes),
+        button("Renumber Scenes").on_press_maybe((!locked).then_some(Message::RenumberScenes)),
+        checkbox("Lock Scene Numbers", locked).on_toggle(Message::LockScenes),
+        button("Omit Scene").on_press(Message::OmitScene),
+    ]
+    .spacing(8)
+    .align_y(iced::Alignment::Center)
+    .into()
+}
+
+/// Search and replace fields with their options, above the script.
+fn find_bar(app: &App) -> Option<Element<'_, Message>> {
+    let find = &app.find;
+    if !find.open {
+        return None;
+    }
+
+    let count = match (&find.error, find.position_of(app.selection())) {
+        (Some(error), _) => error.lines().last().unwrap_or_default().to_string(),
+        _ if find.query.text.is_empty() => String::new(),
+        (None, Some(position)) => format!("{} of {}", position, find.matches.len()),
+        (None, None) => format!("{} matches", find.matches.len()),
+    };
+    let search = row![
+        text_input("Find", &find.query.text)
+            .id(FIND_INPUT)
+            .on_input(Message::FindTextChanged)
+            .on_submit(Message::FindNext),
+        button("Previous").on_press(Message::FindPrevious),
+        button("Next").on_press(Message::FindNext),
+        text(count).size(12).width(160),
+        button("Close").on_press(Message::CloseFind),
+    ]
+    .spacing(8)
+    .align_y(iced::Alignment::Center);
+
+    let replace = row![
+        text_input("Replace with", &find.replacement)
+            .on_input(Message::ReplacementChanged)
+            .on_submit(Message::Replace),
+        button("Replace").on_press(Message::Replace),
+        button("Replace All").on_press(Message::ReplaceAll),
+    ]
+    .spacing(8)
+    .align_y(iced::Alignment::Center);
+
+    let mut options = row![
+        checkbox("Match case", find.query.case_sensitive).on_toggle(Message::FindCaseSensitiveToggled),
+        checkbox("Whole word", find.query.whole_word).on_toggle(Message::FindWholeWordToggled),
+        checkbox("Regex", find.query.regex).on_toggle(Message::FindRegexToggled),
+        text("Only in:").size(12),
+    ]
+    .spacing(12)
+    .align_y(iced::Alignment::Center);
+    for element_type in ElementType::ALL {
+        let checked = find.query.element_types.contains(&element_type);
+        options = options.push(
+            checkbox(element_type.as_str(), checked)
+                .on_toggle(move |on| Message::FindElementTypeToggled(element_type, on))
+                .size(14)
+                .text_size(12),
+        );
+    }
+
+    Some(column![search, replace, options.wrap()].spacing(4).into())
+}
+
+/// The note open for editing, above the script.
+fn note_panel(app: &App) -> Option<Element<'_, Message>> {
+    let note = app.current_note.and_then(|id| app.screenplay.note(id))?;
+
+    let created = if note.created > 0 {
+        note::format_timestamp(note.created)
+    } else {
+        String::new()
+    };
+    let header = row![
+        text_input("Author", &note.author)
+            .on_input(Message::NoteAuthorChanged)
+            .width(160),
+        text(created).size(12),
+        pick_list(NoteColor::ALL, Some(note.color), Message::NoteColorSelected),
+        toggler(note.resolved)
+            .label("Resolved")
+            .on_toggle(Message::NoteResolved),
+        button("Delete").on_press(Message::DeleteNote),
+        button("Close").on_press(Message::CloseNote),
+    ]
+    .spacing(8)
+    .align_y(iced::Alignment::Center);
+
+    let body = text_input("Note", &note.text)
+        .id(NOTE_INPUT)
+        .on_input(Message::NoteTextChanged);
+
+    Some(column![header, body].spacing(4).into())
+}
+
+/// Every title page entry, each saved to the screenplay as it is typed.
+fn title_page_form(app: &App) -> Element<'_, Message> {
+    let screenplay = &app.screenplay;
+    let title = if screenplay.title == Screenplay::UNTITLED {
+        ""
+    } else {
+        screenplay.title.as_str()
+    };
+
+    let mut form = column![
+        labeled(
+            "Title",
+            text_input(Screenplay::UNTITLED, title).on_input(Message::TitleChanged),
+        ),
+        labeled(
+            "Author",
+            text_input("", screenplay.author.as_deref().unwrap_or_default()).on_input(Message::AuthorChanged),
+        ),
+    ]
+    .spacing(8)
+    .max_width(640);
+
+    for field in TitleField::ALL {
+        let placeholder = if field == TitleField::Credit { DEFAULT_CREDIT } else { "" };
+        let value = screenplay.title_field(field).unwrap_or_default();
+        form = form.push(labeled(
+            field.label(),
+            text_input(placeholder, value).on_input(move |value| Message::TitleFieldChanged(field, value)),
+        ));
+    }
+
+    form = form.push(labeled(
+        "Contact",
+        text_editor(&app.contact).on_action(Message::ContactAction).height(100),
+    ));
+
+    scrollable(form).height(iced::Length::Fill).into()
+}
+
+/// The template, the format it or the script gives each element type, and
+/// the flow the writer uses with it, each change applied straight away.
+fn styles_form(app: &App) -> Element<'_, Message> {
+    let style_sheet = &app.screenplay.style_sheet;
+    let template = pick_list(Template::ALL, Some(style_sheet.template), Message::TemplateSelected);
+    let note = if style_sheet.is_customized() { "Changed from the template" } else { "" };
+
+    let mut header = row![text("").width(FIELD_LABEL_WIDTH)].spacing(8);
+    for label in ["Indent", "Width", "Before", "After", "Spacing", "Align", "Caps", "Bold", "Italic", "Underline"] {
+        header = header.push(text(label).size(12).width(STYLE_CELL_WIDTH));
+    }
+
+    let mut form = column![labeled("Template", row![template, text(note).size(12)].spacing(8)), header]
+        .spacing(8)
+        .max_width(1000);
+    for element_type in ElementType::ALL {
+        form = form.push(format_row(element_type, style_sheet.format(element_type)));
+    }
+
+    let flow_note = if app.preferences.is_customized(style_sheet.template) {
+        "Changed from the template for every script using it"
+    } else {
+        ""
+    };
+    form = form.push(row![text("Element flow").size(18), text(flow_note).size(12)].spacing(8)).push(
+        row![
+            text("").width(FIELD_LABEL_WIDTH),
+            text("Tab changes it to").size(12).width(FLOW_CELL_WIDTH),
+            text("Enter starts").size(12).width(FLOW_CELL_WIDTH),
+        ]
+        .spacing(8),
+    );
+    for element_type in ElementType::ALL {
+        form = form.push(flow_row(element_type, app.flow(element_type)));
+    }
+
+    scrollable(form).height(iced::Length::Fill).into()
+}
+
+fn format_row<'a>(element_type: ElementType, format: ElementFormat) -> Element<'a, Message> {
+    let changed = move |format| Message::ElementFormatChanged(element_type, format);
+    let number = move |value: usize, set: fn(ElementFormat, usize) -> ElementFormat| {
+        text_input("", &value.to_string())
+            .on_input(move |input| {
+                let value = if input.is_empty() { 0 } else { input.parse().unwrap_or(value) };
+                changed(set(format, value))
+            })
+            .width(STYLE_CELL_WIDTH)
+    };
+    let flag = move |checked: bool, set: fn(ElementFormat, bool) -> ElementFormat| {
+        checkbox("", checked)
+            .on_toggle(move |on| changed(set(format, on)))
+            .width(STYLE_CELL_WIDTH)
+    };
+    let emphasis = move |emphasis: Emphasis| {
+        checkbox("", format.style.has(emphasis))
+            .on_toggle(move |on| {
+                changed(ElementFormat {
+                    style: format.style.with(emphasis, on),
+                    ..format
+                })
+            })
+            .width(STYLE_CELL_WIDTH)
+    };
+
+    row![
+        text(element_type.as_str()).width(FIELD_LABEL_WIDTH),
+        number(format.indent, |format, indent| ElementFormat { indent, ..format }),
+        number(format.width, |format, width| ElementFormat {
+            width: width.max(1),
+            ..format
+        }),
+        number(format.space_before, |format, space_before| ElementFormat { space_before, ..format }),
+        number(format.space_after, |format, space_after| ElementFormat { space_after, ..format }),
+        number(format.line_spacing, |format, line_spacing| ElementFormat {
+            line_spacing: line_spacing.max(1),
+            ..format
+        }),
+        pick_list(Alignment::ALL, Some(format.alignment), move |alignment| {
+            changed(ElementFormat { alignment, ..format })
+        })
+        .width(STYLE_CELL_WIDTH),
+        flag(format.uppercase, |format, uppercase| ElementFormat { uppercase, ..format }),
+        emphasis(Emphasis::Bold),
+        emphasis(Emphasis::Italic),
+        emphasis(Emphasis::Underline),
+    ]
+    .spacing(8)
+    .align_y(iced::Alignment::Center)
+    .into()
+}
+
+fn flow_row<'a>(element_type: ElementType, flow: ElementFlow) -> Element<'a, Message> {
+    row![
+        text(element_type.as_str()).width(FIELD_LABEL_WIDTH),
+        pick_list(ElementType::ALL, Some(flow.tab), move |tab| {
+            Message::ElementFlowChanged(element_type, ElementFlow { tab, ..flow })
+        })
+        .width(FLOW_CELL_WIDTH),
+        pick_list(ElementType::ALL, Some(flow.enter), move |enter| {
+            Message::ElementFlowChanged(element_type, ElementFlow { enter, ..flow })
+        })
+        .width(FLOW_CELL_WIDTH),
+    ]
+    .spacing(8)
+    .align_y(iced::Alignment::Center)
+    .into()
+}
+
+fn labeled<'a>(label: &'a str, input: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+    row![text(label).width(FIELD_LABEL_WIDTH), input.into()]
+        .spacing(8)
+        .into()
+}
@@ -0,0 +1,857 @@
+use iced::keyboard::Modifiers;
+use quill::document::{Emphasis, NoteColor, Style, StyleSpan, TextPosition};
+use quill::app::{PendingAction, Screen, UnsavedChoice, ViewMode};
+use quill::document::title_page::TitleField;
+use quill::document::layout::{ElementFormat, Paper};
+use quill::document::flow::ElementFlow;
+use quill::document::template::Template;
+use quill::ui::editor_layout::{CHAR_WIDTH, LEFT};
+use quill::{App, ElementType, Message};
+
+fn type_text(app: &mut App, text: &str) {
+    for c in text.chars() {
+        let _ = app.update(Message::CharacterTyped(c));
+    }
+}
+
+#[test]
+fn test_undo_typing_by_word() {
+    let mut app = App::default();
+    type_text(&mut app, "She runs");
+
+    let _ = app.update(Message::Undo);
+    assert_eq!(app.screenplay.elements[0].content, "She");
+    assert_eq!(app.cursor_offset, 3);
+
+    let _ = app.update(Message::Undo);
+    assert_eq!(app.screenplay.elements[0].content, "");
+    assert_eq!(app.cursor_offset, 0);
+
+    let _ = app.update(Message::Redo);
+    let _ = app.update(Message::Redo);
+    assert_eq!(app.screenplay.elements[0].content, "She runs");
+    assert_eq!(app.cursor_offset, 8);
+}
+
+#[test]
+fn test_undo_back_to_saved_script_clears_modified() {
+    let path = std::env::temp_dir().join(format!("quill-save-point-{}.quill", std::process::id()));
+    let mut app = App::default();
+    type_text(&mut app, "She runs");
+    let _ = app.update(Message::SavePathSelected(Some(path.clone())));
+    std::fs::remove_file(&path).unwrap();
+    assert!(!app.screenplay.modified);
+
+    type_text(&mut app, " home");
+    assert!(app.screenplay.modified);
+    let _ = app.update(Message::Undo);
+    assert_eq!(app.screenplay.elements[0].content, "She runs");
+    assert!(!app.screenplay.modified);
+    let _ = app.update(Message::Undo);
+    assert!(app.screenplay.modified);
+    let _ = app.update(Message::Redo);
+    assert!(!app.screenplay.modified);
+
+    let _ = app.update(Message::TitleChanged("Runner".to_string()));
+    type_text(&mut app, "!");
+    let _ = app.update(Message::Undo);
+    assert!(app.screenplay.modified);
+}
+
+#[test]
+fn test_drag_ends_typing_run() {
+    let mut app = App::default();
+    type_text(&mut app, "She");
+    let _ = app.update(Message::DraggedTo(TextPosition::new(0, 3)));
+    let _ = app.update(Message::DragEnded);
+    type_text(&mut app, "e");
+
+    let _ = app.update(Message::Undo);
+    assert_eq!(app.screenplay.elements[0].content, "She");
+}
+
+#[test]
+fn test_undo_enter_tab_and_backspace() {
+    let mut app = App::default();
+    type_text(&mut app, "BOB");
+    let _ = app.update(Message::EnterPressed);
+    let _ = app.update(Message::TabPressed);
+    assert_eq!(app.screenplay.elements.len(), 2);
+
+    let _ = app.update(Message::BackspacePressed);
+    assert_eq!(app.screenplay.elements.len(), 1);
+
+    let _ = app.update(Message::Undo);
+    assert_eq!(app.screenplay.elements.len(), 2);
+    assert_eq!(app.cursor_position, 1);
+
+    let tabbed = app.screenplay.elements[1].element_type;
+    let _ = app.update(Message::Undo);
+    assert_ne!(app.screenplay.elements[1].element_type, tabbed);
+
+    let _ = app.update(Message::Undo);
+    assert_eq!(app.screenplay.elements.len(), 1);
+    assert_eq!(app.cursor_position, 0);
+    assert_eq!(app.current_element_type, ElementType::Action);
+}
+
+#[test]
+fn test_shift_selection_across_elements_is_replaced_by_typing() {
+    let mut app = App::default();
+    type_text(&mut app, "She runs");
+    let _ = app.update(Message::EnterPressed);
+    type_text(&mut app, "fast now");
+
+    let _ = app.update(Message::Home);
+    let _ = app.update(Message::ArrowRight);
+    let _ = app.update(Message::ArrowRight);
+    let _ = app.update(Message::ArrowRight);
+    let _ = app.update(Message::ModifiersChanged(Modifiers::SHIFT));
+    let _ = app.update(Message::ArrowUp);
+    let _ = app.update(Message::ModifiersChanged(Modifiers::empty()));
+    assert!(app.selection().is_some());
+
+    type_text(&mut app, "x");
+    assert_eq!(app.screenplay.elements.len(), 1);
+    assert_eq!(app.screenplay.elements[0].content, "Shext now");
+    assert_eq!(app.selection(), None);
+
+    let _ = app.update(Message::Undo);
+    assert_eq!(app.screenplay.elements.len(), 2);
+    assert_eq!(app.screenplay.elements[1].content, "fast now");
+}
+
+#[test]
+fn test_double_click_selects_word() {
+    let mut app = App::default();
+    type_text(&mut app, "She runs home");
+
+    let _ = app.update(Message::DoubleClickedAt(TextPosition::new(0, 5)));
+    let selection = app.selection().unwrap();
+    assert_eq!(selection.start, TextPosition::new(0, 4));
+    assert_eq!(selection.end, TextPosition::new(0, 8));
+
+    let _ = app.update(Message::BackspacePressed);
+    assert_eq!(app.screenplay.elements[0].content, "She  home");
+}
+
+#[test]
+fn test_copy_paste_keeps_element_types() {
+    let mut app = App::default();
+    type_text(&mut app, "NORA");
+    let _ = app.update(Message::TabPressed);
+    let _ = app.update(Message::TabPressed);
+    let _ = app.update(Message::EnterPressed);
+    type_text(&mut app, "Hello?");
+
+    let _ = app.update(Message::ModifiersChanged(Modifiers::SHIFT));
+    let _ = app.update(Message::ArrowUp);
+    let _ = app.update(Message::Home);
+    let _ = app.update(Message::ModifiersChanged(Modifiers::empty()));
+    let _ = app.update(Message::Cut);
+    assert_eq!(app.screenplay.elements.len(), 1);
+    assert_eq!(app.screenplay.elements[0].content, "");
+
+    let text = app.clipboard.as_ref().unwrap().text.clone();
+    assert!(text.contains("NORA\nHello?"));
+    let _ = app.update(Message::ClipboardRead(Some(text)));
+    let types: Vec<ElementType> = app.screenplay.elements.iter().map(|e| e.element_type).collect();
+    assert_eq!(types, vec![ElementType::Character, ElementType::Dialogue]);
+    assert_eq!(app.cursor_position, 1);
+    assert_eq!(app.cursor_offset, 6);
+
+    let _ = app.update(Message::Undo);
+    assert_eq!(app.screenplay.elements.len(), 1);
+}
+
+#[test]
+fn test_paste_plain_text_splits_into_elements() {
+    let mut app = App::default();
+    type_text(&mut app, "Before ");
+    let text = "the storm.\n\nJO\nGet inside!".to_string();
+    let _ = app.update(Message::ClipboardRead(Some(text)));
+
+    let elements = &app.screenplay.elements;
+    assert_eq!(elements.len(), 3);
+    assert_eq!(elements[0].content, "Before the storm.");
+    assert_eq!(elements[1].element_type, ElementType::Character);
+    assert_eq!(elements[2].element_type, ElementType::Dialogue);
+    assert_eq!(elements[2].content, "Get inside!");
+}
+
+#[test]
+fn test_arrows_move_by_wrapped_line() {
+    let mut app = App::default();
+    let words = "The rain keeps falling on the tin roof while the town sleeps and nobody hears the knock.";
+    type_text(&mut app, words);
+    let _ = app.update(Message::EnterPressed);
+    type_text(&mut app, "Next.");
+
+    // The action wraps before "and" at sixty columns.
+    let second_line = words.find("and nobody").unwrap();
+    let _ = app.update(Message::ArrowUp);
+    assert_eq!(app.cursor_position, 0);
+    assert_eq!(app.cursor_offset, second_line + 5);
+
+    let _ = app.update(Message::ArrowUp);
+    assert_eq!(app.cursor_position, 0);
+    assert_eq!(app.cursor_offset, 5);
+
+    let _ = app.update(Message::End);
+    assert_eq!(app.cursor_offset, second_line - 1);
+    let _ = app.update(Message::Home);
+    assert_eq!(app.cursor_offset, 0);
+
+    let _ = app.update(Message::ArrowDown);
+    let _ = app.update(Message::ArrowDown);
+    assert_eq!(app.cursor_position, 1);
+}
+
+#[test]
+fn test_viewport_follows_caret_and_pages() {
+    let mut app = App::default();
+    let _ = app.update(Message::ViewportResized(200.0));
+    for n in 0..40 {
+        type_text(&mut app, &format!("Beat {}.", n));
+        let _ = app.update(Message::EnterPressed);
+    }
+    let bottom = app.viewport.offset;
+    assert!(bottom > 0.0);
+
+    let _ = app.update(Message::PageUp);
+    assert!(app.viewport.offset < bottom);
+    assert!(app.cursor_position < 40);
+
+    let _ = app.update(Message::Scrolled(-10_000.0));
+    assert_eq!(app.viewport.offset, 0.0);
+
+    let _ = app.update(Message::ToggleTypewriter);
+    let centered = app.viewport.offset;
+    let _ = app.update(Message::ArrowUp);
+    assert!(app.viewport.offset < centered);
+}
+
+#[test]
+fn test_click_places_caret_where_it_is_drawn() {
+    let mut app = App::default();
+    type_text(&mut app, "Zoe\u{301} waits.");
+    let _ = app.update(Message::EnterPressed);
+    type_text(&mut app, "Nothing happens.");
+
+    let layout = app.editor_layout();
+    let elements = &app.screenplay.elements;
+    let offset = "Zoe\u{301}".len();
+    let caret = layout.caret(elements, 0, offset).unwrap();
+    let position = layout
+        .hit_test(elements, iced::Point::new(caret.x + 1.0, caret.y + 4.0))
+        .unwrap();
+    assert_eq!(position, TextPosition::new(0, offset));
+
+    let _ = app.update(Message::ClickedAt(position));
+    assert_eq!((app.cursor_position, app.cursor_offset), (0, offset));
+
+    // Past the end of a line, and below the last one, clamp to the text.
+    let beyond = iced::Point::new(caret.x + 2000.0, app.editor_layout().height() + 100.0);
+    let position = app.editor_layout().hit_test(&app.screenplay.elements, beyond).unwrap();
+    assert_eq!(position, TextPosition::new(1, "Nothing happens.".len()));
+}
+
+#[test]
+fn test_page_view_places_lines_where_they_print() {
+    let mut app = App::default();
+    let _ = app.update(Message::ViewportResized(600.0));
+    for n in 0..28 {
+        type_text(&mut app, &format!("Beat {}.", n));
+        let _ = app.update(Message::EnterPressed);
+    }
+    let _ = app.update(Message::ViewModeSelected(ViewMode::Page));
+    assert_eq!(app.page_map.page_count(), 2);
+
+    let layout = app.editor_layout();
+    let pages = layout.pages();
+    assert_eq!(pages.len(), 2);
+    assert!(pages[0].labels.is_empty());
+    assert_eq!(pages[1].labels[0].text, "2.");
+
+    // The first element on page two sits at the top of its body, and the
+    // caret on the trailing blank element stays in view.
+    let first = app.page_map.pages()[1].lines[0].kind;
+    let quill::document::pagination::LineKind::Element { index, .. } = first else {
+        panic!("expected an element line");
+    };
+    let caret = layout.caret(&app.screenplay.elements, index, 0).unwrap();
+    assert!(caret.y > pages[1].bounds.y && caret.y < pages[1].bounds.y + 100.0);
+    let caret = layout
+        .caret(&app.screenplay.elements, app.cursor_position, app.cursor_offset)
+        .unwrap();
+    assert!(caret.y >= app.viewport.offset && caret.y < app.viewport.offset + app.viewport.height);
+
+    let _ = app.update(Message::PaperSelected(Paper::A4));
+    assert_eq!(app.page_map.page_count(), 1);
+    assert_eq!(app.editor_layout().pages().len(), 1);
+
+    let _ = app.update(Message::ViewModeSelected(ViewMode::Draft));
+    assert!(app.editor_layout().pages().is_empty());
+}
+
+#[test]
+fn test_page_view_draws_lines_as_printed() {
+    let mut app = App::default();
+    let _ = app.update(Message::ViewportResized(600.0));
+    let _ = app.update(Message::TemplateSelected(Template::MultiCamera));
+    // Fits the editor's line but not the printed one once in capitals.
+    let action = "Der Straßenbahnfahrer grüßt die Straße, die große Stadt ruft";
+    type_text(&mut app, action);
+    let _ = app.update(Message::EnterPressed);
+    let _ = app.update(Message::TabPressed);
+    let _ = app.update(Message::TabPressed);
+    type_text(&mut app, "MAX");
+    let _ = app.update(Message::EnterPressed);
+    let _ = app.update(Message::TabPressed);
+    type_text(&mut app, "softly");
+    let _ = app.update(Message::ViewModeSelected(ViewMode::Page));
+
+    let layout = app.editor_layout();
+    let lines: Vec<_> = layout.lines_between(0.0, layout.height()).collect();
+    let printed = &app.page_map.pages()[0].lines;
+    assert_eq!(lines.len(), printed.len());
+    for (line, printed) in lines.iter().zip(printed) {
+        let text: String = line.shape.runs.iter().map(|run| run.text.as_str()).collect();
+        assert_eq!(text, printed.text);
+        assert_eq!(line.shape.runs[0].x, LEFT + printed.column as f32 * CHAR_WIDTH);
+    }
+    assert_eq!(lines[0].shape.range.end, lines[1].shape.range.start - 1);
+
+    // The caret in a parenthetical sits after the printed parenthesis.
+    let paren = lines.iter().find(|line| line.element == 2).unwrap();
+    let caret = layout.caret(&app.screenplay.elements, 2, 0).unwrap();
+    assert!(caret.x > paren.shape.runs[0].x);
+    assert_eq!(caret.y, paren.y);
+}
+
+#[test]
+fn test_title_page_edits_show_as_page_one() {
+    let mut app = App::default();
+    type_text(&mut app, "It begins.");
+    let _ = app.update(Message::ScreenSelected(Screen::TitlePage));
+    let _ = app.update(Message::TitleChanged("Night Shift".to_string()));
+    let _ = app.update(Message::AuthorChanged("Ada Byron".to_string()));
+    let _ = app.update(Message::TitleFieldChanged(TitleField::DraftDate, "May 2026".to_string()));
+    assert_eq!(app.screenplay.title, "Night Shift");
+    assert_eq!(app.screenplay.title_field(TitleField::DraftDate), Some("May 2026"));
+    assert!(app.screenplay.modified);
+
+    let _ = app.update(Message::TitleFieldChanged(TitleField::DraftDate, String::new()));
+    assert_eq!(app.screenplay.title_field(TitleField::DraftDate), None);
+
+    let _ = app.update(Message::ScreenSelected(Screen::Script));
+    let _ = app.update(Message::ViewModeSelected(ViewMode::Page));
+    let pages = app.editor_layout().pages();
+    assert_eq!(pages.len(), app.page_map.page_count() + 1);
+    let title: Vec<&str> = pages[0].labels.iter().map(|label| label.text.as_str()).collect();
+    assert!(title.contains(&"NIGHT SHIFT"));
+    assert!(title.contains(&"Ada Byron"));
+
+    // The script itself starts on the second sheet.
+    let caret = app.editor_layout().caret(&app.screenplay.elements, 0, 0).unwrap();
+    assert!(caret.y > pages[1].bounds.y);
+}
+
+#[test]
+fn test_emphasis_toggle_and_typing() {
+    let bold = Style {
+        bold: true,
+        ..Style::PLAIN
+    };
+    let mut app = App::default();
+    type_text(&mut app, "Run now");
+    let _ = app.update(Message::DoubleClickedAt(TextPosition::new(0, 1)));
+    let _ = app.update(Message::ToggleEmphasis(Emphasis::Bold));
+    assert_eq!(app.screenplay.elements[0].styles, vec![StyleSpan::new(0..3, bold)]);
+    assert!(app.selection().is_some());
+
+    // Text typed inside bold text is bold; a toggle with no selection
+    // applies to what is typed next.
+    let _ = app.update(Message::ClickedAt(TextPosition::new(0, 3)));
+    type_text(&mut app, "!");
+    let _ = app.update(Message::ToggleEmphasis(Emphasis::Bold));
+    type_text(&mut app, "x");
+    assert_eq!(app.screenplay.elements[0].content, "Run!x now");
+    assert_eq!(app.screenplay.elements[0].styles, vec![StyleSpan::new(0..4, bold)]);
+
+    let _ = app.update(Message::Undo);
+    assert_eq!(app.screenplay.elements[0].content, "Run now");
+    assert_eq!(app.screenplay.elements[0].styles, vec![StyleSpan::new(0..3, bold)]);
+
+    let _ = app.update(Message::Undo);
+    assert!(app.screenplay.elements[0].styles.is_empty());
+}
+
+#[test]
+fn test_cut_and_paste_keep_emphasis() {
+    let mut app = App::default();
+    type_text(&mut app, "Run now");
+    let _ = app.update(Message::DoubleClickedAt(TextPosition::new(0, 5)));
+    let _ = app.update(Message::ToggleEmphasis(Emphasis::Underline));
+    let _ = app.update(Message::Cut);
+    let _ = app.update(Message::ClickedAt(TextPosition::new(0, 0)));
+    let _ = app.update(Message::ClipboardRead(None));
+
+    let element = &app.screenplay.elements[0];
+    assert_eq!(element.content, "nowRun ");
+    assert_eq!(element.styles.len(), 1);
+    assert_eq!(element.styles[0].range(), 0..3);
+    assert!(element.styles[0].style.underline);
+}
+
+#[test]
+fn test_notes_follow_edits_and_navigate() {
+    let mut app = App::default();
+    type_text(&mut app, "She runs fast");
+    let _ = app.update(Message::EnterPressed);
+    type_text(&mut app, "He waits");
+
+    let _ = app.update(Message::DoubleClickedAt(TextPosition::new(0, 5)));
+    let _ = app.update(Message::AddNote);
+    let _ = app.update(Message::NoteTextChanged("Too slow?".to_string()));
+    let first = app.current_note.unwrap();
+    let _ = app.update(Message::DoubleClickedAt(TextPosition::new(1, 4)));
+    let _ = app.update(Message::AddNote);
+    let second = app.current_note.unwrap();
+    let _ = app.update(Message::NoteTextChanged("Why?".to_string()));
+    let _ = app.update(Message::CloseNote);
+
+    let _ = app.update(Message::ClickedAt(TextPosition::new(0, 0)));
+    type_text(&mut app, "Then ");
+    let anchor = app.screenplay.note(first).unwrap().anchor;
+    assert_eq!((anchor.start, anchor.end), (TextPosition::new(0, 9), TextPosition::new(0, 13)));
+
+    let _ = app.update(Message::NextNote);
+    assert_eq!(app.current_note, Some(first));
+    let _ = app.update(Message::NextNote);
+    assert_eq!(app.current_note, Some(second));
+    assert_eq!(app.selection().map(|range| range.end), Some(TextPosition::new(1, 8)));
+    let _ = app.update(Message::NextNote);
+    assert_eq!(app.current_note, Some(first));
+    let _ = app.update(Message::PreviousNote);
+    assert_eq!(app.current_note, Some(second));
+
+    let _ = app.update(Message::DoubleClickedAt(TextPosition::new(0, 10)));
+    let _ = app.update(Message::BackspacePressed);
+    let anchor = app.screenplay.note(first).unwrap().anchor;
+    assert!(anchor.is_empty());
+    assert_eq!(anchor.start, TextPosition::new(0, 9));
+
+    let _ = app.update(Message::Undo);
+    let anchor = app.screenplay.note(first).unwrap().anchor;
+    assert_eq!((anchor.start, anchor.end), (TextPosition::new(0, 9), TextPosition::new(0, 13)));
+
+    let _ = app.update(Message::NextNote);
+    let _ = app.update(Message::DeleteNote);
+    assert_eq!(app.screenplay.notes.len(), 1);
+}
+
+#[test]
+fn test_note_changes_undo_and_empty_notes_close_away() {
+    let mut app = App::default();
+    type_text(&mut app, "She runs.");
+    let _ = app.update(Message::AddNote);
+    let id = app.current_note.unwrap();
+
+    let revision = app.screenplay.revision;
+    let _ = app.update(Message::NoteTextChanged(String::new()));
+    assert_eq!(app.screenplay.revision, revision);
+
+    for text in ["T", "To", "Too"] {
+        let _ = app.update(Message::NoteTextChanged(text.to_string()));
+    }
+    let _ = app.update(Message::NoteColorSelected(NoteColor::Blue));
+    let _ = app.update(Message::Undo);
+    let note = app.screenplay.note(id).unwrap();
+    assert_eq!((note.text.as_str(), note.color), ("", NoteColor::Yellow));
+
+    let _ = app.update(Message::Undo);
+    assert!(app.screenplay.notes.is_empty());
+    let _ = app.update(Message::Redo);
+    assert_eq!(app.screenplay.notes.len(), 1);
+
+    let _ = app.update(Message::CloseNote);
+    assert!(app.screenplay.notes.is_empty());
+    let _ = app.update(Message::Undo);
+    assert_eq!(app.screenplay.notes.len(), 1);
+}
+
+#[test]
+fn test_find_and_replace_by_element_type() {
+    let mut app = App::default();
+    let text = "Bob waves at Bobby.\n\nBOB\nHi, I'm Bob.".to_string();
+    let _ = app.update(Message::ClipboardRead(Some(text)));
+
+    let _ = app.update(Message::OpenFind);
+    let _ = app.update(Message::FindTextChanged("bob".to_string()));
+    assert_eq!(app.find.matches.len(), 4);
+    let _ = app.update(Message::FindWholeWordToggled(true));
+    assert_eq!(app.find.matches.len(), 3);
+    let _ = app.update(Message::FindElementTypeToggled(ElementType::Character, true));
+    let _ = app.update(Message::FindElementTypeToggled(ElementType::Dialogue, true));
+    assert_eq!(app.find.matches.len(), 2);
+
+    let _ = app.update(Message::ClickedAt(TextPosition::new(0, 0)));
+    let _ = app.update(Message::FindNext);
+    assert_eq!(app.find.position_of(app.selection()), Some(1));
+    let _ = app.update(Message::FindPrevious);
+    assert_eq!(app.find.position_of(app.selection()), Some(2));
+
+    let _ = app.update(Message::ReplacementChanged("Robert".to_string()));
+    let _ = app.update(Message::Replace);
+    assert_eq!(app.screenplay.elements[2].content, "Hi, I'm Robert.");
+    assert_eq!(app.find.position_of(app.selection()), Some(1));
+    let _ = app.update(Message::Undo);
+    assert_eq!(app.screenplay.elements[2].content, "Hi, I'm Bob.");
+
+    let _ = app.update(Message::ReplaceAll);
+    let contents: Vec<_> = app.screenplay.elements.iter().map(|e| e.content.as_str()).collect();
+    assert_eq!(contents, ["Bob waves at Bobby.", "Robert", "Hi, I'm Robert."]);
+    assert!(app.find.matches.is_empty());
+
+    let _ = app.update(Message::Undo);
+    let contents: Vec<_> = app.screenplay.elements.iter().map(|e| e.content.as_str()).collect();
+    assert_eq!(contents, ["Bob waves at Bobby.", "BOB", "Hi, I'm Bob."]);
+
+    let _ = app.update(Message::FindElementTypeToggled(ElementType::Character, false));
+    let _ = app.update(Message::FindElementTypeToggled(ElementType::Dialogue, false));
+    let _ = app.update(Message::FindRegexToggled(true));
+    let _ = app.update(Message::FindTextChanged(r"(\w+) waves".to_string()));
+    let _ = app.update(Message::ReplacementChanged("$1 nods".to_string()));
+    let _ = app.update(Message::ReplaceAll);
+    assert_eq!(app.screenplay.elements[0].content, "Bob nods at Bobby.");
+
+    let _ = app.update(Message::FindTextChanged("(".to_string()));
+    assert!(app.find.error.is_some());
+    let _ = app.update(Message::CloseFind);
+    assert!(!app.find.open);
+}
+
+#[test]
+fn test_character_cue_completion() {
+    let mut app = App::default();
+    let text = "MARY\nHello.\n\nMARK\nHi.\n\nMARY\nBye.".to_string();
+    let _ = app.update(Message::ClipboardRead(Some(text)));
+    let _ = app.update(Message::EnterPressed);
+    type_text(&mut app, "ma");
+    assert!(app.completions.is_empty());
+
+    let _ = app.update(Message::TabPressed);
+    let _ = app.update(Message::TabPressed);
+    assert_eq!(app.current_element_type, ElementType::Character);
+    let _ = app.update(Message::BackspacePressed);
+    type_text(&mut app, "a");
+    assert_eq!(app.completions, ["MARY", "MARK"]);
+    let _ = app.update(Message::ArrowDown);
+    let _ = app.update(Message::EnterPressed);
+    assert_eq!(app.screenplay.elements[6].content, "MARK");
+    assert!(app.completions.is_empty());
+    assert_eq!(app.screenplay.elements.len(), 7);
+
+    type_text(&mut app, " (v");
+    assert_eq!(app.completions, ["MARK (V.O.)"]);
+    let _ = app.update(Message::EscapePressed);
+    assert!(app.completions.is_empty());
+    type_text(&mut app, ".");
+    let _ = app.update(Message::TabPressed);
+    assert_eq!(app.screenplay.elements[6].content, "MARK (V.O.)");
+    assert_eq!(app.cursor_offset, "MARK (V.O.)".len());
+
+    let _ = app.update(Message::Undo);
+    assert_eq!(app.screenplay.elements[6].content, "MARK (v.");
+    let _ = app.update(Message::ArrowLeft);
+    assert!(app.completions.is_empty());
+}
+
+#[test]
+fn test_scene_heading_completion_in_stages() {
+    let mut app = App::default();
+    let _ = app.update(Message::TabPressed);
+    let text = "INT. HOUSE - KITCHEN - NIGHT\n\nShe waits.".to_string();
+    let _ = app.update(Message::ClipboardRead(Some(text)));
+    let _ = app.update(Message::EnterPressed);
+    let _ = app.update(Message::TabPressed);
+    assert_eq!(app.current_element_type, ElementType::SceneHeading);
+
+    type_text(&mut app, "i");
+    assert_eq!(app.completions, ["INT. ", "INT./EXT. ", "I/E. "]);
+    let _ = app.update(Message::TabPressed);
+    assert_eq!(app.screenplay.elements[2].content, "INT. ");
+    assert!(app.completions.is_empty());
+
+    type_text(&mut app, "h");
+    assert_eq!(app.completions, ["INT. HOUSE - KITCHEN - "]);
+    let _ = app.update(Message::EnterPressed);
+    assert_eq!(app.completions.first().map(String::as_str), Some("INT. HOUSE - KITCHEN - DAY"));
+    let _ = app.update(Message::ArrowDown);
+    let _ = app.update(Message::EnterPressed);
+    assert_eq!(app.screenplay.elements[2].content, "INT. HOUSE - KITCHEN - NIGHT");
+    assert!(app.completions.is_empty());
+
+    let _ = app.update(Message::EnterPressed);
+    assert_eq!(app.screenplay.elements.len(), 4);
+}
+
+#[test]
+fn test_toggle_dual_dialogue() {
+    let mut app = App::default();
+    let text = "They both talk.\n\nANN\nStop.\n\nBOB\nNo, you stop.".to_string();
+    let _ = app.update(Message::ClipboardRead(Some(text)));
+    assert_eq!(app.screenplay.elements.len(), 5);
+    assert_eq!(app.cursor_position, 4);
+
+    let _ = app.update(Message::ToggleDualDialogue);
+    assert!(app.screenplay.elements[3].dual);
+
+    // The two speeches start on the same line, side by side, and a click
+    // on either one lands in it.
+    let layout = app.editor_layout();
+    let elements = &app.screenplay.elements;
+    let left = layout.caret(elements, 1, 0).unwrap();
+    let right = layout.caret(elements, 3, 0).unwrap();
+    assert_eq!(left.y, right.y);
+    assert!(right.x > left.x);
+    let position = layout
+        .hit_test(elements, iced::Point::new(right.x + 1.0, right.y + 4.0))
+        .unwrap();
+    assert_eq!(position, TextPosition::new(3, 0));
+    let position = layout
+        .hit_test(elements, iced::Point::new(left.x + 1.0, left.y + 4.0))
+        .unwrap();
+    assert_eq!(position, TextPosition::new(1, 0));
+
+    let _ = app.update(Message::Undo);
+    assert!(!app.screenplay.elements[3].dual);
+    let _ = app.update(Message::Redo);
+    assert!(app.screenplay.elements[3].dual);
+
+    let _ = app.update(Message::ClickedAt(TextPosition::new(0, 0)));
+    let _ = app.update(Message::ToggleDualDialogue);
+    assert!(app.status.is_some());
+    assert!(app.screenplay.elements[3].dual);
+}
+
+#[test]
+fn test_tab_cycles_through_every_element_type() {
+    let mut app = App::default();
+    let mut seen = vec![app.current_element_type];
+    for _ in 1..ElementType::ALL.len() {
+        let _ = app.update(Message::TabPressed);
+        seen.push(app.current_element_type);
+    }
+    let _ = app.update(Message::TabPressed);
+    assert_eq!(app.current_element_type, ElementType::Action);
+
+    seen.sort_by_key(|element_type| ElementType::ALL.iter().position(|t| t == element_type));
+    assert_eq!(seen, ElementType::ALL);
+}
+
+#[test]
+fn test_page_break_holds_no_text() {
+    let mut app = App::default();
+    type_text(&mut app, "La la");
+    while app.current_element_type != ElementType::PageBreak {
+        let _ = app.update(Message::TabPressed);
+    }
+    assert_eq!(app.screenplay.elements[0].content, "");
+
+    type_text(&mut app, "Oops");
+    let _ = app.update(Message::ClipboardRead(Some("Pasted".to_string())));
+    assert_eq!(app.screenplay.elements[0].content, "");
+
+    let _ = app.update(Message::Undo);
+    assert_eq!(app.screenplay.elements[0].element_type, ElementType::Lyrics);
+    assert_eq!(app.screenplay.elements[0].content, "La la");
+}
+
+#[test]
+fn test_template_and_formats_apply_to_the_script() {
+    let mut app = App::default();
+    type_text(&mut app, "She waits.");
+    let x = app.editor_layout().caret(&app.screenplay.elements, 0, 0).unwrap().x;
+
+    let _ = app.update(Message::ScreenSelected(Screen::Styles));
+    let _ = app.update(Message::TemplateSelected(Template::AudioDrama));
+    assert!(app.screenplay.modified);
+    assert_eq!(app.page_map.pages()[0].lines[0].text, "SHE WAITS.");
+
+    let action = app.screenplay.style_sheet.format(ElementType::Action);
+    let _ = app.update(Message::ElementFormatChanged(ElementType::Action, ElementFormat { indent: 10, ..action }));
+    assert_eq!(app.page_map.pages()[0].lines[0].column, 10);
+    assert!(app.editor_layout().caret(&app.screenplay.elements, 0, 0).unwrap().x > x);
+    assert!(app.screenplay.style_sheet.is_customized());
+
+    // Choosing a template again starts over from its formats.
+    let _ = app.update(Message::TemplateSelected(Template::AudioDrama));
+    assert!(!app.screenplay.style_sheet.is_customized());
+}
+
+#[test]
+fn test_flow_changes_are_kept_per_template_not_in_the_script() {
+    let mut app = App::default();
+    let flow = app.flow(ElementType::Action);
+    let revision = app.screenplay.revision;
+    let _ = app.update(Message::ElementFlowChanged(
+        ElementType::Action,
+        ElementFlow {
+            tab: ElementType::Character,
+            ..flow
+        },
+    ));
+    assert_eq!(app.screenplay.revision, revision);
+    let _ = app.update(Message::TabPressed);
+    assert_eq!(app.current_element_type, ElementType::Character);
+
+    // Another template has its own flow; coming back finds the change.
+    let _ = app.update(Message::TemplateSelected(Template::StagePlay));
+    assert_eq!(app.flow(ElementType::Action), flow);
+    let _ = app.update(Message::TemplateSelected(Template::FeatureFilm));
+    assert_eq!(app.flow(ElementType::Action).tab, ElementType::Character);
+    assert!(!app.screenplay.style_sheet.is_customized());
+}
+
+#[test]
+fn test_enter_retypes_action_lines_and_breaks_out_when_blank() {
+    let mut app = App::default();
+    let _ = app.update(Message::ClipboardRead(Some("NORA\nHello?".to_string())));
+    assert_eq!(app.screenplay.elements[0].element_type, ElementType::Character);
+    let _ = app.update(Message::EnterPressed);
+    type_text(&mut app, "BOOM!");
+    let _ = app.update(Message::EnterPressed);
+    assert_eq!(app.screenplay.elements[2].element_type, ElementType::Action);
+
+    type_text(&mut app, "NORA");
+    let _ = app.update(Message::EnterPressed);
+    assert_eq!(app.screenplay.elements[3].element_type, ElementType::Character);
+    assert_eq!(app.current_element_type, ElementType::Dialogue);
+
+    // A blank line of dialogue turns into action rather than adding another.
+    let _ = app.update(Message::EnterPressed);
+    assert_eq!(app.screenplay.elements.len(), 5);
+    assert_eq!(app.current_element_type, ElementType::Action);
+
+    type_text(&mut app, "int. cellar - night");
+    let _ = app.update(Message::EnterPressed);
+    assert_eq!(app.screenplay.elements[4].element_type, ElementType::SceneHeading);
+    assert_eq!(app.current_element_type, ElementType::Action);
+    let _ = app.update(Message::Undo);
+    assert_eq!(app.screenplay.elements.len(), 5);
+    assert_eq!(app.screenplay.elements[4].element_type, ElementType::Action);
+
+    // Plays go from one speech straight to the next cue.
+    let _ = app.update(Message::TemplateSelected(Template::StagePlay));
+    let _ = app.update(Message::ClickedAt(TextPosition::new(1, "Hello?".len())));
+    let _ = app.update(Message::EnterPressed);
+    assert_eq!(app.screenplay.elements[2].element_type, ElementType::Character);
+}
+
+fn scene_numbers(app: &App) -> Vec<(String, Option<String>)> {
+    app.screenplay
+        .elements
+        .iter()
+        .filter(|e| e.element_type == ElementType::SceneHeading)
+        .map(|e| (e.content.clone(), e.scene_number.clone()))
+        .collect()
+}
+
+#[test]
+fn test_locked_scene_numbers_insert_and_omit() {
+    let mut app = App::default();
+    let script = "INT. HALL - DAY\nNora waits.\nEXT. YARD - NIGHT\nRain.";
+    let _ = app.update(Message::ClipboardRead(Some(script.to_string())));
+    let _ = app.update(Message::LockScenes(true));
+    assert!(app.screenplay.scenes_locked);
+    let numbers: Vec<_> = scene_numbers(&app).into_iter().map(|(_, number)| number).collect();
+    assert_eq!(numbers, [Some("1".to_string()), Some("2".to_string())]);
+
+    let _ = app.update(Message::ClickedAt(TextPosition::new(1, "Nora waits.".len())));
+    let _ = app.update(Message::EnterPressed);
+    type_text(&mut app, "int. cellar - night");
+    let _ = app.update(Message::EnterPressed);
+    let _ = app.update(Message::NumberScenes);
+    let _ = app.update(Message::RenumberScenes);
+    let numbers: Vec<_> = scene_numbers(&app).into_iter().map(|(_, number)| number).collect();
+    assert_eq!(numbers, [Some("1".to_string()), Some("1A".to_string()), Some("2".to_string())]);
+
+    let _ = app.update(Message::ClickedAt(TextPosition::new(1, 0)));
+    let _ = app.update(Message::OmitScene);
+    assert_eq!(scene_numbers(&app)[0], ("OMITTED".to_string(), Some("1".to_string())));
+    assert_eq!(app.screenplay.elements[1].content, "int. cellar - night");
+    assert_eq!(app.cursor_position, 0);
+
+    let _ = app.update(Message::Undo);
+    assert_eq!(scene_numbers(&app)[0], ("INT. HALL - DAY".to_string(), Some("1".to_string())));
+    assert_eq!(app.screenplay.elements[1].content, "Nora waits.");
+}
+
+#[test]
+fn test_locked_scenes_cut_and_pasted_keep_their_numbers() {
+    let mut app = App::default();
+    let script = "INT. HALL - DAY\nNora waits.\nEXT. YARD - NIGHT\nRain.";
+    let _ = app.update(Message::ClipboardRead(Some(script.to_string())));
+    let _ = app.update(Message::LockScenes(true));
+
+    let _ = app.update(Message::ClickedAt(TextPosition::new(1, "Nora waits.".len())));
+    let _ = app.update(Message::ModifiersChanged(Modifiers::SHIFT));
+    let _ = app.update(Message::ArrowDown);
+    let _ = app.update(Message::ArrowDown);
+    let _ = app.update(Message::ModifiersChanged(Modifiers::empty()));
+    let _ = app.update(Message::Cut);
+    assert_eq!(
+        scene_numbers(&app),
+        [
+            ("INT. HALL - DAY".to_string(), Some("1".to_string())),
+            ("OMITTED".to_string(), Some("2".to_string())),
+        ]
+    );
+
+    let text = app.clipboard.as_ref().unwrap().text.clone();
+    let _ = app.update(Message::ClipboardRead(Some(text)));
+    let numbers: Vec<_> = scene_numbers(&app).into_iter().map(|(_, number)| number).collect();
+    assert_eq!(numbers, [Some("1".to_string()), Some("1A".to_string()), Some("2".to_string())]);
+
+    let _ = app.update(Message::Undo);
+    let _ = app.update(Message::Undo);
+    assert_eq!(
+        scene_numbers(&app),
+        [
+            ("INT. HALL - DAY".to_string(), Some("1".to_string())),
+            ("EXT. YARD - NIGHT".to_string(), Some("2".to_string())),
+        ]
+    );
+}
+
+#[test]
+fn test_new_file_asks_before_discarding_changes() {
+    let mut app = App::default();
+    assert!(!app.screenplay.modified);
+    type_text(&mut app, "Unsaved.");
+
+    let _ = app.update(Message::NewFile);
+    assert_eq!(app.screenplay.elements[0].content, "Unsaved.");
+    let _ = app.update(Message::UnsavedChangesAnswered(PendingAction::New, UnsavedChoice::Cancel));
+    assert_eq!(app.screenplay.elements[0].content, "Unsaved.");
+
+    let _ = app.update(Message::UnsavedChangesAnswered(PendingAction::New, UnsavedChoice::Discard));
+    assert_eq!(app.screenplay.elements.len(), 1);
+    assert_eq!(app.screenplay.elements[0].content, "");
+    assert!(!app.screenplay.modified);
+}
+
+#[test]
+fn test_open_reads_upper_case_fountain_extension() {
+    let path = std::env::temp_dir().join(format!("quill-open-{}.FOUNTAIN", std::process::id()));
+    std::fs::write(&path, "INT. HALL - DAY\n\nShe waits.\n").unwrap();
+    let mut app = App::default();
+    let _ = app.update(Message::OpenPathSelected(Some(path.clone())));
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(app.status.as_deref(), Some("Opened Untitled"));
+    assert_eq!(app.screenplay.elements[0].element_type, ElementType::SceneHeading);
+    assert_eq!(app.screenplay.elements[1].content, "She waits.");
+}
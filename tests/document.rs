@@ -1,4 +1,8 @@
-use quill::document::{Element, ElementType, Screenplay};
+use quill::ScreenplayError;
+use quill::document::heading::Heading;
+use quill::document::layout::ElementFormat;
+use quill::document::template::{StyleSheet, Template};
+use quill::document::{Element, ElementType, Note, Screenplay, Style, StyleSpan, TextPosition, TextRange, note};
 
 #[test]
 fn test_element_is_empty() {
@@ -66,4 +70,92 @@ fn test_json_serialization() {
     assert_eq!(deserialized.title, "Test Script");
     assert_eq!(deserialized.author, Some("Test Author".to_string()));
     assert_eq!(deserialized.element_count(), 1);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_save_and_load_round_trip() {
+    let path = std::env::temp_dir().join(format!("quill-round-trip-{}.quill", std::process::id()));
+
+    let mut screenplay = Screenplay::new("Saved Script".to_string());
+    screenplay.add_element(Element::new(ElementType::Action, "She waits.".to_string()));
+    screenplay.save_as(&path).unwrap();
+    assert!(!screenplay.modified);
+
+    let loaded = Screenplay::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.title, "Saved Script");
+    assert_eq!(loaded.file_path.as_deref(), Some(path.as_path()));
+    assert!(!loaded.modified);
+}
+
+#[test]
+fn test_load_missing_file() {
+    let path = std::env::temp_dir().join("quill-does-not-exist.quill");
+    assert!(matches!(
+        Screenplay::load(&path),
+        Err(ScreenplayError::FileNotFound(_))
+    ));
+}
+
+#[test]
+fn test_emphasis_survives_save_and_load() {
+    let italic = Style {
+        italic: true,
+        ..Style::PLAIN
+    };
+    let mut screenplay = Screenplay::new("Styled".to_string());
+    screenplay.add_element(
+        Element::new(ElementType::Action, "She means it.".to_string()).with_styles(vec![StyleSpan::new(4..9, italic)]),
+    );
+    screenplay.add_element(Element::new(ElementType::Action, "Plain.".to_string()));
+
+    let json = screenplay.to_json().unwrap();
+    assert_eq!(json.matches("styles").count(), 1);
+    let loaded = Screenplay::from_json(&json).unwrap();
+    assert_eq!(loaded.elements, screenplay.elements);
+}
+
+#[test]
+fn test_style_sheet_survives_save_and_load() {
+    let mut screenplay = Screenplay::new("Play".to_string());
+    screenplay.add_element(Element::new(ElementType::Character, "ANNA".to_string()));
+    let mut style_sheet = StyleSheet::new(Template::StagePlay);
+    let character = style_sheet.format(ElementType::Character);
+    style_sheet.set_format(ElementType::Character, ElementFormat { space_before: 2, ..character });
+    screenplay.style_sheet = style_sheet;
+
+    let json = screenplay.to_json().unwrap();
+    let loaded = Screenplay::from_json(&json).unwrap();
+    assert_eq!(loaded.style_sheet, screenplay.style_sheet);
+    assert_eq!(loaded.style_sheet.format(ElementType::Character).space_before, 2);
+}
+
+#[test]
+fn test_notes_report() {
+    let mut screenplay = Screenplay::new("Heist".to_string());
+    screenplay.add_element(Element::new(ElementType::SceneHeading, "int. vault - night".to_string()));
+    screenplay.add_element(Element::new(ElementType::Action, "Alarms blare.".to_string()));
+    let anchor = TextRange::new(TextPosition::new(1, 0), TextPosition::new(1, 6));
+    let id = screenplay.next_note_id();
+    screenplay.insert_note(Note::new(id, anchor, "Sam".to_string(), 1_709_210_096));
+    screenplay.update_note(id, |note| note.text = "Louder?".to_string());
+
+    let json = screenplay.to_json().unwrap();
+    assert_eq!(Screenplay::from_json(&json).unwrap().notes, screenplay.notes);
+
+    assert_eq!(
+        note::report(&screenplay),
+        "Notes: Heist\n\n1. Page 1 - INT. VAULT - NIGHT\n   Yellow | Sam | 2024-02-29 12:34 UTC | Open\n   > Alarms\n   Louder?\n"
+    );
+}
+
+#[test]
+fn test_scene_heading_breakdown() {
+    let heading = Heading::parse("ext. farmhouse - porch - dawn");
+    assert_eq!(heading.prefix.as_deref(), Some("EXT."));
+    assert_eq!(heading.location, "FARMHOUSE");
+    assert_eq!(heading.sub_location.as_deref(), Some("PORCH"));
+    assert_eq!(heading.time.as_deref(), Some("DAWN"));
+    assert_eq!(heading.place(), "FARMHOUSE - PORCH");
+}
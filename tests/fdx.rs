@@ -0,0 +1,193 @@
+use quill::document::title_page::TitleField;
+use quill::document::{Element, ElementType, Screenplay, Style, StyleSpan, fdx};
+
+fn sample_screenplay() -> Screenplay {
+    let mut screenplay = Screenplay::new("The Heist".to_string());
+    screenplay.author = Some("Jane Writer".to_string());
+    screenplay.contact = Some("Agency & Co.\n555-0100".to_string());
+    screenplay.metadata.push(("Draft date".to_string(), "May 2026".to_string()));
+
+    for (element_type, content) in [
+        (ElementType::SceneHeading, "INT. VAULT - NIGHT"),
+        (ElementType::Action, "Alarms <blare>."),
+        (ElementType::Character, "MAX"),
+        (ElementType::Parenthetical, "whispering"),
+        (ElementType::Dialogue, "Go. Now."),
+        (ElementType::Transition, "CUT TO:"),
+    ] {
+        screenplay.add_element(Element::new(element_type, content.to_string()));
+    }
+    screenplay
+}
+
+#[test]
+fn test_export_reimports_identically() {
+    let original = sample_screenplay();
+    let xml = fdx::write(&original).unwrap();
+    let import = fdx::parse(&xml).unwrap();
+
+    assert!(import.warnings.is_empty());
+    assert_eq!(import.screenplay.title, original.title);
+    assert_eq!(import.screenplay.author, original.author);
+    assert_eq!(import.screenplay.contact, original.contact);
+    assert_eq!(import.screenplay.metadata, original.metadata);
+    assert_eq!(import.screenplay.elements, original.elements);
+}
+
+#[test]
+fn test_title_page_fields_reimport() {
+    let mut original = sample_screenplay();
+    original.set_title_field(TitleField::Credit, "Screenplay by");
+    original.set_title_field(TitleField::Source, "Based on a true story");
+    original.set_title_field(TitleField::Wga, "1234567");
+
+    let import = fdx::parse(&fdx::write(&original).unwrap()).unwrap();
+    for field in TitleField::ALL {
+        assert_eq!(import.screenplay.title_field(field), original.title_field(field));
+    }
+}
+
+#[test]
+fn test_unsupported_constructs_become_warnings() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="no" ?>
+<FinalDraft DocumentType="Script" Template="No" Version="5">
+  <Content>
+    <Paragraph Type="Cast List"><Text>CLOSE ON THE KEY</Text></Paragraph>
+    <Paragraph Type="Action"><Text Style="Underline">SIGN</Text><Text>: Keep out.</Text></Paragraph>
+    <Paragraph>
+      <DualDialogue>
+        <Paragraph Type="Character"><Text>ANN</Text></Paragraph>
+        <Paragraph Type="Dialogue"><Text>Now!</Text></Paragraph>
+        <Paragraph Type="Character"><Text>BEN</Text></Paragraph>
+        <Paragraph Type="Dialogue"><Text>Not yet!</Text></Paragraph>
+      </DualDialogue>
+    </Paragraph>
+  </Content>
+  <HeaderAndFooter>
+    <Header><Paragraph><Text>Ignored header</Text></Paragraph></Header>
+  </HeaderAndFooter>
+</FinalDraft>"#;
+
+    let import = fdx::parse(xml).unwrap();
+    let contents: Vec<_> = import
+        .screenplay
+        .elements
+        .iter()
+        .map(|e| (e.element_type, e.content.as_str()))
+        .collect();
+
+    assert_eq!(
+        contents,
+        vec![
+            (ElementType::Action, "CLOSE ON THE KEY"),
+            (ElementType::Action, "SIGN: Keep out."),
+            (ElementType::Character, "ANN"),
+            (ElementType::Dialogue, "Now!"),
+            (ElementType::Character, "BEN"),
+            (ElementType::Dialogue, "Not yet!"),
+        ]
+    );
+    // The dual dialogue is read as a pair, not warned about.
+    let dual: Vec<_> = import.screenplay.elements[2..].iter().map(|e| e.dual).collect();
+    assert_eq!(dual, [false, false, true, false]);
+    let underline = Style {
+        underline: true,
+        ..Style::PLAIN
+    };
+    assert_eq!(import.screenplay.elements[1].styles, vec![StyleSpan::new(0..4, underline)]);
+    assert_eq!(
+        import.warnings,
+        ["Paragraph type \"Cast List\" is not supported and was imported as Action"]
+    );
+}
+
+#[test]
+fn test_emphasis_round_trip() {
+    let bold = Style {
+        bold: true,
+        ..Style::PLAIN
+    };
+    let both = Style {
+        italic: true,
+        underline: true,
+        ..Style::PLAIN
+    };
+    let mut original = Screenplay::new("Styled".to_string());
+    original.add_element(
+        Element::new(ElementType::Action, "The door is LOCKED. Really locked.".to_string())
+            .with_styles(vec![StyleSpan::new(12..18, bold), StyleSpan::new(20..26, both)]),
+    );
+    original.add_element(
+        Element::new(ElementType::Parenthetical, "very quietly".to_string())
+            .with_styles(vec![StyleSpan::new(0..4, bold)]),
+    );
+
+    let xml = fdx::write(&original).unwrap();
+    assert!(xml.contains(r#"<Text Style="Italic+Underline">Really</Text>"#));
+
+    let import = fdx::parse(&xml).unwrap();
+    assert!(import.warnings.is_empty());
+    assert_eq!(import.screenplay.elements, original.elements);
+}
+
+#[test]
+fn test_rejects_non_fdx_xml() {
+    assert!(fdx::parse("<html></html>").is_err());
+    assert!(fdx::parse("<FinalDraft><Content>").is_err());
+}
+
+#[test]
+fn test_dual_dialogue_round_trip() {
+    let mut original = sample_screenplay();
+    original.elements.insert(5, Element::new(ElementType::Character, "ANN".to_string()));
+    original.elements.insert(6, Element::new(ElementType::Dialogue, "Go where?".to_string()));
+    original.elements[5].dual = true;
+
+    let xml = fdx::write(&original).unwrap();
+    assert_eq!(xml.matches("<DualDialogue>").count(), 1);
+    let import = fdx::parse(&xml).unwrap();
+    assert!(import.warnings.is_empty());
+    assert_eq!(import.screenplay.elements, original.elements);
+}
+
+#[test]
+fn test_extended_element_types_round_trip() {
+    let mut original = Screenplay::new("Extended".to_string());
+    for (element_type, content) in [
+        (ElementType::Act, "ACT ONE"),
+        (ElementType::Sequence, "The heist"),
+        (ElementType::Section, "Setup"),
+        (ElementType::Shot, "CLOSE ON THE KEY"),
+        (ElementType::PageBreak, ""),
+        (ElementType::Character, "MAX"),
+        (ElementType::Lyrics, "Happy birthday to you"),
+        (ElementType::Centered, "THE END"),
+        (ElementType::PageBreak, ""),
+    ] {
+        original.add_element(Element::new(element_type, content.to_string()));
+    }
+
+    let xml = fdx::write(&original).unwrap();
+    assert!(xml.contains(r#"<Paragraph Type="Action" Alignment="Center">"#));
+    assert_eq!(xml.matches(r#"StartsNewPage="Yes""#).count(), 2);
+
+    let import = fdx::parse(&xml).unwrap();
+    assert!(import.warnings.is_empty());
+    // A trailing page break comes back with the blank paragraph carrying it.
+    let mut expected = original.elements.clone();
+    expected.push(Element::empty(ElementType::Action));
+    assert_eq!(import.screenplay.elements, expected);
+}
+
+#[test]
+fn test_scene_numbers_round_trip() {
+    let mut original = sample_screenplay();
+    original.elements[0].scene_number = Some("4A".to_string());
+
+    let xml = fdx::write(&original).unwrap();
+    assert!(xml.contains(r#"<Paragraph Type="Scene Heading" Number="4A">"#));
+
+    let import = fdx::parse(&xml).unwrap();
+    assert_eq!(import.screenplay.elements[0].scene_number.as_deref(), Some("4A"));
+    assert_eq!(import.screenplay.elements[1].scene_number, None);
+}
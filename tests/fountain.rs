@@ -0,0 +1,265 @@
+use quill::document::title_page::TitleField;
+use quill::document::{Element, ElementType, Note, Screenplay, Style, StyleSpan, TextPosition, TextRange, fountain};
+
+fn assert_round_trip(source: &str) {
+    let screenplay = fountain::parse(source);
+    assert_eq!(fountain::write(&screenplay), source);
+}
+
+#[test]
+fn test_scene_headings() {
+    let screenplay = fountain::parse("INT. KITCHEN - DAY\n\n.SNIPER SCOPE POV\n");
+    let types: Vec<_> = screenplay.elements.iter().map(|e| e.element_type).collect();
+    assert_eq!(types, vec![ElementType::SceneHeading, ElementType::SceneHeading]);
+    assert_eq!(screenplay.elements[1].content, "SNIPER SCOPE POV");
+
+    assert_round_trip("INT. KITCHEN - DAY\n\n.SNIPER SCOPE POV\n");
+}
+
+#[test]
+fn test_dialogue_block() {
+    let source = "MARY (V.O.)\n(quietly)\nI never left.\nNot once.\n\n@McCLANE\nYippee ki-yay.\n";
+    let screenplay = fountain::parse(source);
+
+    let parsed: Vec<_> = screenplay
+        .elements
+        .iter()
+        .map(|e| (e.element_type, e.content.as_str()))
+        .collect();
+    assert_eq!(
+        parsed,
+        vec![
+            (ElementType::Character, "MARY (V.O.)"),
+            (ElementType::Parenthetical, "quietly"),
+            (ElementType::Dialogue, "I never left.\nNot once."),
+            (ElementType::Character, "McCLANE"),
+            (ElementType::Dialogue, "Yippee ki-yay."),
+        ]
+    );
+
+    assert_round_trip(source);
+}
+
+#[test]
+fn test_transitions() {
+    let screenplay = fountain::parse("CUT TO:\n\n> FADE OUT.\n");
+    assert!(screenplay
+        .elements
+        .iter()
+        .all(|e| e.element_type == ElementType::Transition));
+    assert_eq!(screenplay.elements[1].content, "FADE OUT.");
+
+    assert_round_trip("CUT TO:\n\n> FADE OUT.\n");
+}
+
+#[test]
+fn test_title_page() {
+    let source = "Title: Big Fish\nCredit: written by\nAuthor: John August\nDraft date: 2003\nContact:\n    Some Agency\n    555-0100\n\nINT. RIVER - DAY\n";
+    let screenplay = fountain::parse(source);
+
+    assert_eq!(screenplay.title, "Big Fish");
+    assert_eq!(screenplay.author.as_deref(), Some("John August"));
+    assert_eq!(screenplay.contact.as_deref(), Some("Some Agency\n555-0100"));
+    assert_eq!(screenplay.elements.len(), 1);
+
+    assert_round_trip(source);
+}
+
+#[test]
+fn test_title_page_fields() {
+    let source = "Title: Big Fish\nAuthor: John August\nSource: Based on the novel by Daniel Wallace\nDraft date: 2003\nRevision: Blue Revisions\nCopyright: (c) 2003 Columbia Pictures\nWGA: 1234567\nNotes: Shooting script\n\nINT. RIVER - DAY\n";
+    let screenplay = fountain::parse(source);
+
+    assert_eq!(
+        screenplay.title_field(TitleField::Source),
+        Some("Based on the novel by Daniel Wallace")
+    );
+    assert_eq!(screenplay.title_field(TitleField::Revision), Some("Blue Revisions"));
+    assert_eq!(screenplay.title_field(TitleField::Wga), Some("1234567"));
+    assert_round_trip(source);
+
+    let dated = fountain::parse("Title: Big Fish\nDate: 2003\n\nINT. RIVER - DAY\n");
+    assert_eq!(dated.title_field(TitleField::DraftDate), Some("2003"));
+}
+
+#[test]
+fn test_passthrough_constructs() {
+    let source = "# ACT ONE\n\n= Edward meets the witch.\n\n> THE END <\n\n/* Cut for time.\n\nOld scene. */\n";
+    let screenplay = fountain::parse(source);
+
+    let types: Vec<_> = screenplay.elements.iter().map(|e| e.element_type).collect();
+    assert_eq!(
+        types,
+        [ElementType::Act, ElementType::Action, ElementType::Centered, ElementType::Action]
+    );
+
+    assert_round_trip(source);
+}
+
+#[test]
+fn test_forced_action() {
+    let source = "!BOOM!\nThe house shakes.\n\n!INT. ACTION THAT LOOKS LIKE A HEADING\n";
+    let screenplay = fountain::parse(source);
+    assert!(screenplay
+        .elements
+        .iter()
+        .all(|e| e.element_type == ElementType::Action));
+
+    assert_round_trip(source);
+}
+
+#[test]
+fn test_full_script_round_trip() {
+    assert_round_trip(
+        "Title: The Test\n\nFADE IN:\n\nEXT. DESERT - NIGHT\n\nWind howls across the dunes.\n\nJACK\n(shouting)\nIs anyone out there?\n\nSMASH CUT TO:\n\nINT. TENT - CONTINUOUS\n\nJack bolts upright.\n",
+    );
+}
+
+#[test]
+fn test_emphasis() {
+    let italic = Style {
+        italic: true,
+        ..Style::PLAIN
+    };
+    let bold = Style {
+        bold: true,
+        ..Style::PLAIN
+    };
+    let underline = Style {
+        underline: true,
+        ..Style::PLAIN
+    };
+    let screenplay = fountain::parse("She *never* **ever** _stops_ at 5 * 3 or \\*stars\\*.\n");
+    let action = &screenplay.elements[0];
+    assert_eq!(action.content, "She never ever stops at 5 * 3 or *stars*.");
+    assert_eq!(
+        action.styles,
+        vec![
+            StyleSpan::new(4..9, italic),
+            StyleSpan::new(10..14, bold),
+            StyleSpan::new(15..20, underline),
+        ]
+    );
+
+    let nested = fountain::parse("***Run.*** **Hide *now***\n");
+    let both = Style { italic: true, ..bold };
+    assert_eq!(nested.elements[0].content, "Run. Hide now");
+    assert_eq!(
+        nested.elements[0].styles,
+        vec![
+            StyleSpan::new(0..4, both),
+            StyleSpan::new(5..10, bold),
+            StyleSpan::new(10..13, both),
+        ]
+    );
+
+    assert_round_trip("She *never* **ever** _stops_ at 5 \\* 3.\n");
+    assert_round_trip("INT. _SAFE_ HOUSE - NIGHT\n\nMARY\n(***softly***)\nIt's *mine*.\n");
+    assert_round_trip("# Act *One*\n\n/* snake_case */\n");
+}
+
+#[test]
+fn test_notes() {
+    let mut screenplay = Screenplay::empty();
+    for (element_type, content) in [
+        (ElementType::Action, "She runs fast."),
+        (ElementType::Character, "MARY"),
+        (ElementType::Dialogue, "Wait for me!"),
+        (ElementType::Transition, "CUT TO:"),
+    ] {
+        screenplay.add_element(Element::new(element_type, content.to_string()));
+    }
+    let range = |element, from, to| TextRange::new(TextPosition::new(element, from), TextPosition::new(element, to));
+    for (anchor, text) in [
+        (range(0, 4, 8), "Too slow?"),
+        (range(1, 0, 4), "New name"),
+        (range(3, 0, 7), "Dissolve"),
+        (range(2, 0, 4), "Already fixed"),
+    ] {
+        let id = screenplay.next_note_id();
+        screenplay.insert_note(Note::new(id, anchor, "Sam".to_string(), 0));
+        screenplay.update_note(id, |note| note.text = text.to_string());
+    }
+    screenplay.notes[3].resolved = true;
+
+    let written = fountain::write(&screenplay);
+    assert_eq!(
+        written,
+        "She runs[[Too slow?]] fast.\n\n[[New name]]\n\nMARY\nWait for me!\n\n> CUT TO:[[Dissolve]]\n"
+    );
+
+    let read = fountain::parse(&written);
+    assert_eq!(read.elements, screenplay.elements);
+    let notes: Vec<_> = read.notes.iter().map(|note| (note.anchor, note.text.as_str())).collect();
+    assert_eq!(
+        notes,
+        [(range(0, 0, 14), "Too slow?"), (range(1, 0, 4), "New name"), (range(3, 0, 7), "Dissolve")]
+    );
+}
+
+#[test]
+fn test_dual_dialogue() {
+    let source = "BRICK\nScrew retirement.\n\nSTEEL ^\n(at once)\nScrew retirement.\n\n@Ann ^\nMe too.\n";
+    let screenplay = fountain::parse(source);
+    let cues: Vec<_> = screenplay
+        .elements
+        .iter()
+        .filter(|e| e.element_type == ElementType::Character)
+        .map(|e| (e.content.as_str(), e.dual))
+        .collect();
+    assert_eq!(cues, [("BRICK", false), ("STEEL", true), ("Ann", true)]);
+
+    assert_round_trip(source);
+}
+
+#[test]
+fn test_extended_element_types() {
+    let source = "# ACT ONE\n\n## The heist\n\n### Setup\n\n!CLOSE ON THE KEY\n\nIt turns.\n\n~La la la\n\n===\n\nMAX\n~Happy birthday to you\n~Happy birthday\nSorry.\n\n> THE END <\n";
+    let screenplay = fountain::parse(source);
+    let parsed: Vec<_> = screenplay
+        .elements
+        .iter()
+        .map(|e| (e.element_type, e.content.as_str()))
+        .collect();
+    assert_eq!(
+        parsed,
+        [
+            (ElementType::Act, "ACT ONE"),
+            (ElementType::Sequence, "The heist"),
+            (ElementType::Section, "Setup"),
+            (ElementType::Shot, "CLOSE ON THE KEY"),
+            (ElementType::Action, "It turns."),
+            (ElementType::Lyrics, "La la la"),
+            (ElementType::PageBreak, ""),
+            (ElementType::Character, "MAX"),
+            (ElementType::Lyrics, "Happy birthday to you\nHappy birthday"),
+            (ElementType::Dialogue, "Sorry."),
+            (ElementType::Centered, "THE END"),
+        ]
+    );
+
+    assert_round_trip(source);
+
+    // Uppercase action from another app is not taken for a shot.
+    let plain = fountain::parse("INSERT COIN\n\nPOV\n");
+    assert!(plain.elements.iter().all(|e| e.element_type == ElementType::Action));
+    assert_round_trip("INSERT COIN\n\nPOV\n");
+}
+
+#[test]
+fn test_scene_numbers() {
+    let source = "INT. HALL - DAY #12#\n\nNora waits.\n\n.OMITTED #12A#\n\nEXT. YARD - NIGHT\n";
+    let screenplay = fountain::parse(source);
+    let headings: Vec<_> = screenplay
+        .elements
+        .iter()
+        .filter(|e| e.element_type == ElementType::SceneHeading)
+        .map(|e| (e.content.as_str(), e.scene_number.as_deref()))
+        .collect();
+    assert_eq!(
+        headings,
+        [("INT. HALL - DAY", Some("12")), ("OMITTED", Some("12A")), ("EXT. YARD - NIGHT", None)]
+    );
+
+    assert_round_trip(source);
+}
@@ -0,0 +1,241 @@
+use quill::document::layout::{LINES_PER_PAGE, Paper};
+use quill::document::pagination::{CONTINUED, LineKind, MORE, PageMap};
+use quill::document::template::{StyleSheet, Template};
+use quill::document::{Element, ElementType, Screenplay};
+
+fn element(element_type: ElementType, content: &str) -> Element {
+    Element::new(element_type, content.to_string())
+}
+
+/// An action paragraph that fills exactly `rows` rows of the first page.
+fn filler(rows: usize) -> Vec<Element> {
+    let lines: Vec<String> = (0..rows).map(|n| format!("Beat {}.", n)).collect();
+    vec![element(ElementType::Action, &lines.join("\n"))]
+}
+
+#[test]
+fn test_scene_heading_not_orphaned() {
+    let mut elements = filler(LINES_PER_PAGE - 2);
+    let heading = elements.len();
+    elements.push(element(ElementType::SceneHeading, "INT. ATTIC - NIGHT"));
+    elements.push(element(ElementType::Action, "Dust everywhere."));
+
+    let map = PageMap::new(&elements);
+    assert_eq!(map.page_of(heading), Some(2));
+    assert_eq!(map.page_of(heading + 1), Some(2));
+}
+
+#[test]
+fn test_character_cue_stays_with_dialogue() {
+    let mut elements = filler(LINES_PER_PAGE - 2);
+    let cue = elements.len();
+    elements.push(element(ElementType::Character, "NORA"));
+    elements.push(element(ElementType::Dialogue, "Hello?"));
+
+    let map = PageMap::new(&elements);
+    assert_eq!(map.page_of(cue), map.page_of(cue + 1));
+    assert_eq!(map.page_of(cue), Some(2));
+}
+
+#[test]
+fn test_long_speech_splits_with_more_and_contd() {
+    let mut elements = filler(LINES_PER_PAGE - 8);
+    let cue = elements.len();
+    elements.push(element(ElementType::Character, "nora"));
+    for n in 0..6 {
+        elements.push(element(ElementType::Dialogue, &format!("Line {} of a long speech.", n)));
+        elements.push(element(ElementType::Parenthetical, "beat"));
+    }
+    elements.push(element(ElementType::Dialogue, "And that is all."));
+
+    let map = PageMap::new(&elements);
+    assert_eq!(map.page_count(), 2);
+
+    let first = &map.pages()[0].lines;
+    let more = first.last().unwrap();
+    assert_eq!(more.kind, LineKind::More);
+    assert_eq!(more.text, MORE);
+
+    let before_more = &first[first.len() - 2];
+    let LineKind::Element { index, .. } = before_more.kind else {
+        panic!("expected an element line before (MORE)");
+    };
+    assert_eq!(elements[index].element_type, ElementType::Dialogue);
+
+    let top = &map.pages()[1].lines[0];
+    assert_eq!(top.kind, LineKind::Continued { index: cue });
+    assert_eq!(top.text, format!("NORA {}", CONTINUED));
+    assert_eq!(top.row, 0);
+}
+
+#[test]
+fn test_every_line_fits_on_its_page() {
+    let elements: Vec<Element> = (0..400)
+        .map(|n| match n % 4 {
+            0 => element(ElementType::SceneHeading, "EXT. FIELD - DAY"),
+            1 => element(ElementType::Action, &"Wind bends the grass. ".repeat(n % 7 + 1)),
+            2 => element(ElementType::Character, "SAM"),
+            _ => element(ElementType::Dialogue, &"Keep walking. ".repeat(n % 9 + 1)),
+        })
+        .collect();
+
+    let map = PageMap::new(&elements);
+    assert!(map.page_count() > 10);
+    for page in map.pages() {
+        assert!(page.lines.iter().all(|line| line.row < LINES_PER_PAGE));
+        let last = page.lines.last().unwrap();
+        if let LineKind::Element { index, .. } = last.kind {
+            assert_ne!(elements[index].element_type, ElementType::SceneHeading);
+            assert_ne!(elements[index].element_type, ElementType::Character);
+        }
+    }
+}
+
+#[test]
+fn test_a4_fits_more_lines_per_page() {
+    let elements = filler(LINES_PER_PAGE + 2);
+    assert_eq!(PageMap::new(&elements).page_count(), 2);
+    assert_eq!(PageMap::for_paper(&elements, Paper::A4).page_count(), 1);
+}
+
+#[test]
+fn test_blank_elements_take_no_room() {
+    let mut elements = filler(LINES_PER_PAGE - 2);
+    elements.push(element(ElementType::Action, ""));
+    let heading = elements.len();
+    elements.push(element(ElementType::SceneHeading, "INT. ATTIC - NIGHT"));
+    elements.push(element(ElementType::Action, " "));
+    elements.push(element(ElementType::Action, "Dust everywhere."));
+
+    let map = PageMap::new(&elements);
+    assert_eq!(map.page_of(heading), Some(2));
+    let rows: Vec<usize> = map.pages()[1].lines.iter().map(|line| line.row).collect();
+    assert_eq!(rows, vec![0, 2]);
+}
+
+fn dual_pair() -> Vec<Element> {
+    let mut right = element(ElementType::Character, "BOB");
+    right.dual = true;
+    vec![
+        element(ElementType::Character, "ANN"),
+        element(ElementType::Dialogue, "Stop."),
+        right,
+        element(ElementType::Parenthetical, "shouting"),
+        element(ElementType::Dialogue, &"No, you stop. ".repeat(5)),
+    ]
+}
+
+#[test]
+fn test_dual_dialogue_prints_side_by_side() {
+    let mut elements = filler(10);
+    let cue = elements.len();
+    elements.extend(dual_pair());
+    elements.push(element(ElementType::Action, "Silence."));
+
+    let map = PageMap::new(&elements);
+    let line = |index: usize| {
+        map.pages()[0]
+            .lines
+            .iter()
+            .find(|line| line.kind == LineKind::Element { index, line: 0 })
+            .unwrap()
+    };
+    assert_eq!(line(cue).row, 11);
+    assert_eq!(line(cue + 2).row, 11);
+    assert!(line(cue + 2).column > line(cue + 1).column + line(cue + 1).text.len());
+    // The taller right-hand speech decides where the next element goes.
+    assert_eq!(line(cue + 5).row, 11 + 5 + 1);
+}
+
+#[test]
+fn test_dual_dialogue_moves_to_the_next_page_whole() {
+    let mut elements = filler(LINES_PER_PAGE - 4);
+    let cue = elements.len();
+    elements.extend(dual_pair());
+
+    let map = PageMap::new(&elements);
+    assert!((cue..elements.len()).all(|index| map.page_of(index) == Some(2)));
+    assert!(map.pages()[1].lines.iter().all(|line| line.row < 5));
+}
+
+#[test]
+fn test_page_breaks_and_outline_headers() {
+    let elements = vec![
+        element(ElementType::Act, "ACT ONE"),
+        element(ElementType::Action, "Opening."),
+        element(ElementType::PageBreak, ""),
+        element(ElementType::Section, "Setup"),
+        element(ElementType::Centered, "THE END"),
+        element(ElementType::PageBreak, ""),
+    ];
+
+    let map = PageMap::new(&elements);
+    assert_eq!(map.page_count(), 2);
+    assert_eq!(map.page_of(4), Some(2));
+    // Outline headers print nothing, and a break on an empty page adds none.
+    let texts: Vec<&str> = map.pages().iter().flat_map(|page| &page.lines).map(|line| line.text.as_str()).collect();
+    assert_eq!(texts, ["Opening.", "THE END"]);
+    assert_eq!(map.pages()[1].lines[0].row, 0);
+}
+
+#[test]
+fn test_template_sets_case_and_line_spacing() {
+    let mut screenplay = Screenplay::new("Pilot".to_string());
+    screenplay.elements = vec![
+        element(ElementType::Action, "she sits."),
+        element(ElementType::Character, "DEB"),
+        element(ElementType::Dialogue, &"Well. ".repeat(12)),
+    ];
+    screenplay.style_sheet = StyleSheet::new(Template::MultiCamera);
+
+    let map = PageMap::for_screenplay(&screenplay);
+    let lines = &map.pages()[0].lines;
+    assert_eq!(lines[0].text, "SHE SITS.");
+    let rows: Vec<usize> = lines[2..].iter().map(|line| line.row).collect();
+    assert_eq!(rows, [3, 5, 7]);
+
+    // The same script in the standard format is single-spaced.
+    let map = PageMap::new(&screenplay.elements);
+    let rows: Vec<usize> = map.pages()[0].lines[2..].iter().map(|line| line.row).collect();
+    assert_eq!(rows, [3, 4, 5]);
+}
+
+#[test]
+fn test_update_matches_paginating_again() {
+    let mut screenplay = Screenplay::new("Draft".to_string());
+    for scene in 0..40 {
+        screenplay.add_element(element(ElementType::SceneHeading, &format!("INT. ROOM {} - DAY", scene)));
+        screenplay.add_element(element(ElementType::Action, &"Rain on the glass. ".repeat(scene % 7 + 1)));
+        screenplay.add_element(element(ElementType::Character, "NORA"));
+        screenplay.add_element(element(ElementType::Dialogue, &"Keep going. ".repeat(scene % 5 + 1)));
+        screenplay.add_element(element(ElementType::Character, "DEV"));
+        screenplay.add_element(element(ElementType::Dialogue, "No."));
+    }
+    let mut map = PageMap::for_screenplay(&screenplay);
+    screenplay.take_changes();
+
+    let check = |map: &mut PageMap, screenplay: &mut Screenplay| {
+        let changes = screenplay.take_changes();
+        map.update(screenplay, changes);
+        let again = PageMap::for_screenplay(screenplay);
+        assert_eq!(map.pages(), again.pages());
+        for index in 0..screenplay.element_count() {
+            assert_eq!(map.page_of(index), again.page_of(index));
+        }
+    };
+
+    screenplay.get_element_mut(100).unwrap().content.push_str(" And then some more rain.");
+    check(&mut map, &mut screenplay);
+    screenplay.insert_element(60, element(ElementType::Action, &"A long pause. ".repeat(40)));
+    check(&mut map, &mut screenplay);
+    screenplay.remove_element(30);
+    screenplay.remove_element(30);
+    check(&mut map, &mut screenplay);
+    screenplay.insert_element(90, Element::empty(ElementType::Action));
+    check(&mut map, &mut screenplay);
+    screenplay.get_element_mut(124).unwrap().dual = true;
+    check(&mut map, &mut screenplay);
+    screenplay.add_element(element(ElementType::Transition, "FADE OUT."));
+    check(&mut map, &mut screenplay);
+    assert!(map.page_count() > 3);
+}
@@ -0,0 +1,198 @@
+use lopdf::content::Content;
+use lopdf::{Document, Object};
+use quill::document::layout::Paper;
+use quill::document::template::{StyleSheet, Template};
+use quill::document::title_page::TitleField;
+use quill::document::{Element, ElementType, Screenplay, Style, StyleSpan, pdf};
+
+fn load(screenplay: &Screenplay) -> Document {
+    Document::load_mem(&pdf::write(screenplay)).unwrap()
+}
+
+fn page_text(document: &Document, page: u32) -> String {
+    document.extract_text(&[page]).unwrap()
+}
+
+fn scene(screenplay: &mut Screenplay, number: usize) {
+    screenplay.add_element(Element::new(
+        ElementType::SceneHeading,
+        format!("int. room {} - day", number),
+    ));
+    screenplay.add_element(Element::new(
+        ElementType::Action,
+        "Rain hammers the window while the kettle screams on the stove.".to_string(),
+    ));
+    screenplay.add_element(Element::new(ElementType::Character, "ada".to_string()));
+    screenplay.add_element(Element::new(ElementType::Parenthetical, "tired".to_string()));
+    screenplay.add_element(Element::new(
+        ElementType::Dialogue,
+        "Somebody answer that before it wakes the whole building.".to_string(),
+    ));
+}
+
+#[test]
+fn test_title_page_and_body() {
+    let mut screenplay = Screenplay::new("Night Shift".to_string());
+    screenplay.author = Some("Ada Byron".to_string());
+    scene(&mut screenplay, 1);
+
+    let document = load(&screenplay);
+    assert_eq!(document.get_pages().len(), 2);
+
+    let title = page_text(&document, 1);
+    assert!(title.contains("NIGHT SHIFT"));
+    assert!(title.contains("Written by"));
+    assert!(title.contains("Ada Byron"));
+
+    let body = page_text(&document, 2);
+    assert!(body.contains("INT. ROOM 1 - DAY"));
+    assert!(body.contains("ADA"));
+    assert!(body.contains("(tired)"));
+}
+
+fn media_box(document: &Document) -> Vec<f32> {
+    let (_, page_id) = document.get_pages().into_iter().next().unwrap();
+    let media_box = document.get_dictionary(page_id).unwrap().get(b"MediaBox").unwrap();
+    media_box
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n.as_float().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_title_page_fields() {
+    let mut screenplay = Screenplay::new("Night Shift".to_string());
+    screenplay.set_title_field(TitleField::Source, "Based on the play by Ada Byron");
+    screenplay.set_title_field(TitleField::Revision, "Pink Revisions");
+    screenplay.set_title_field(TitleField::Wga, "1234567");
+    scene(&mut screenplay, 1);
+
+    let title = page_text(&load(&screenplay), 1);
+    assert!(title.contains("Based on the play by Ada Byron"));
+    assert!(title.contains("Pink Revisions"));
+    assert!(title.contains("WGA Registration 1234567"));
+}
+
+#[test]
+fn test_courier_on_letter_paper() {
+    let mut screenplay = Screenplay::empty();
+    scene(&mut screenplay, 1);
+
+    let document = load(&screenplay);
+    let (_, page_id) = document.get_pages().into_iter().next().unwrap();
+    assert_eq!(media_box(&document), vec![0.0, 0.0, 612.0, 792.0]);
+
+    let fonts = document.get_page_fonts(page_id).unwrap();
+    let font = fonts.values().next().unwrap();
+    assert!(matches!(font.get(b"BaseFont"), Ok(Object::Name(name)) if name == b"Courier"));
+}
+
+#[test]
+fn test_a4_paper() {
+    let mut screenplay = Screenplay::empty();
+    screenplay.paper = Paper::A4;
+    scene(&mut screenplay, 1);
+
+    let size = media_box(&load(&screenplay));
+    assert_eq!(size[2].round(), 595.0);
+    assert_eq!(size[3].round(), 842.0);
+}
+
+#[test]
+fn test_page_breaks_and_numbers() {
+    let mut screenplay = Screenplay::empty();
+    for number in 1..=20 {
+        scene(&mut screenplay, number);
+    }
+
+    let document = load(&screenplay);
+    let pages = document.get_pages().len() as u32;
+    assert!(pages > 2);
+
+    assert!(!page_text(&document, 1).contains("1."));
+    assert!(page_text(&document, 2).contains("2."));
+    assert!(page_text(&document, pages).contains(&format!("{}.", pages)));
+    assert!(page_text(&document, pages).contains("INT. ROOM 20 - DAY"));
+}
+
+#[test]
+fn test_scene_numbers_print_in_both_margins() {
+    let mut screenplay = Screenplay::new("Numbered".to_string());
+    scene(&mut screenplay, 1);
+    screenplay.elements[0].scene_number = Some("7B".to_string());
+
+    let body = page_text(&load(&screenplay), 2);
+    assert_eq!(body.matches("7B").count(), 2);
+}
+
+/// The text shown on page `page`, each run with the Courier face it is set
+/// in, and how many rules are stroked.
+fn shown_runs(document: &Document, page: u32) -> (Vec<(String, String)>, usize) {
+    let page_id = document.get_pages()[&page];
+    let fonts = document.get_page_fonts(page_id).unwrap();
+    let content = Content::decode(&document.get_page_content(page_id).unwrap()).unwrap();
+    let mut face = String::new();
+    let mut runs = Vec::new();
+    let mut strokes = 0;
+    for operation in content.operations {
+        match operation.operator.as_str() {
+            "Tf" => {
+                let name = operation.operands[0].as_name().unwrap();
+                face = String::from_utf8(fonts[name].get(b"BaseFont").unwrap().as_name().unwrap().to_vec()).unwrap();
+            }
+            "Tj" => {
+                let text = String::from_utf8(operation.operands[0].as_str().unwrap().to_vec()).unwrap();
+                runs.push((text, face.clone()));
+            }
+            "S" => strokes += 1,
+            _ => {}
+        }
+    }
+    (runs, strokes)
+}
+
+#[test]
+fn test_emphasis_prints_in_courier_faces() {
+    let bold = Style {
+        bold: true,
+        ..Style::PLAIN
+    };
+    let underline = Style {
+        underline: true,
+        ..Style::PLAIN
+    };
+    let mut screenplay = Screenplay::empty();
+    screenplay.add_element(
+        Element::new(ElementType::Action, "She never stops.".to_string()).with_styles(vec![
+            StyleSpan::new(4..9, bold),
+            StyleSpan::new(10..15, underline),
+        ]),
+    );
+    screenplay.add_element(Element::new(ElementType::Character, "MAX".to_string()));
+    screenplay.add_element(Element::new(ElementType::Lyrics, "La la la".to_string()));
+
+    let (runs, strokes) = shown_runs(&load(&screenplay), 1);
+    let run = |text: &str, face: &str| (text.to_string(), face.to_string());
+    assert_eq!(
+        runs,
+        [
+            run("She ", "Courier"),
+            run("never", "Courier-Bold"),
+            run(" ", "Courier"),
+            run("stops", "Courier"),
+            run(".", "Courier"),
+            run("MAX", "Courier"),
+            run("La la la", "Courier-Oblique"),
+        ]
+    );
+    assert_eq!(strokes, 1);
+
+    // A template's emphasis applies to the whole element.
+    screenplay.style_sheet = StyleSheet::new(Template::ComicScript);
+    screenplay.elements.insert(0, Element::new(ElementType::SceneHeading, "PAGE ONE".to_string()));
+    let (runs, strokes) = shown_runs(&load(&screenplay), 1);
+    assert_eq!(runs[0], run("PAGE ONE", "Courier-Bold"));
+    assert_eq!(strokes, 2);
+}
@@ -0,0 +1,104 @@
+use proptest::prelude::*;
+use quill::{App, Message};
+use unicode_segmentation::UnicodeSegmentation;
+
+fn type_text(app: &mut App, text: &str) {
+    for c in text.chars() {
+        let _ = app.update(Message::CharacterTyped(c));
+    }
+}
+
+fn assert_cursor_on_boundary(app: &App) {
+    let content = &app.screenplay.elements[app.cursor_position].content;
+    let boundaries: Vec<usize> = content
+        .grapheme_indices(true)
+        .map(|(index, _)| index)
+        .chain([content.len()])
+        .collect();
+    assert!(
+        boundaries.contains(&app.cursor_offset),
+        "offset {} is not a grapheme boundary of {:?}",
+        app.cursor_offset,
+        content
+    );
+}
+
+/// Text mixing ASCII with accents, combining marks, dashes, CJK and emoji
+/// sequences.
+fn unicode_text() -> impl Strategy<Value = String> {
+    prop::collection::vec(
+        prop_oneof![
+            "[a-zA-Z ]",
+            Just("é".to_string()),
+            Just("e\u{301}".to_string()),
+            Just("\u{2014}".to_string()),
+            Just("日本".to_string()),
+            Just("\u{1F44D}\u{1F3FD}".to_string()),
+            Just("\u{1F1EB}\u{1F1F7}".to_string()),
+            Just("\u{1F469}\u{200D}\u{1F4BB}".to_string()),
+            any::<char>().prop_filter("printable", |c| !c.is_control()).prop_map(String::from),
+        ],
+        0..12,
+    )
+    .prop_map(|parts| parts.concat())
+}
+
+fn message() -> impl Strategy<Value = Message> {
+    prop_oneof![
+        unicode_text().prop_map(Message::TextTyped),
+        any::<char>()
+            .prop_filter("printable", |c| !c.is_control())
+            .prop_map(Message::CharacterTyped),
+        Just(Message::BackspacePressed),
+        Just(Message::DeletePressed),
+        Just(Message::ArrowLeft),
+        Just(Message::ArrowRight),
+        Just(Message::ArrowUp),
+        Just(Message::ArrowDown),
+        Just(Message::Home),
+        Just(Message::End),
+        Just(Message::EnterPressed),
+        Just(Message::Undo),
+        Just(Message::Redo),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn test_editing_unicode_never_panics(messages in prop::collection::vec(message(), 0..60)) {
+        let mut app = App::default();
+        for message in messages {
+            let _ = app.update(message);
+            assert_cursor_on_boundary(&app);
+        }
+    }
+
+    #[test]
+    fn test_typing_then_backspacing_empties_element(text in unicode_text()) {
+        let mut app = App::default();
+        type_text(&mut app, &text);
+        prop_assert_eq!(&app.screenplay.elements[0].content, &text);
+
+        let clusters = text.graphemes(true).count();
+        for _ in 0..clusters {
+            let _ = app.update(Message::BackspacePressed);
+            assert_cursor_on_boundary(&app);
+        }
+        prop_assert_eq!(app.screenplay.elements[0].content.as_str(), "");
+    }
+}
+
+#[test]
+fn test_arrows_and_delete_step_over_clusters() {
+    let mut app = App::default();
+    type_text(&mut app, "Zoe\u{301} \u{1F44D}\u{1F3FD}!");
+
+    let _ = app.update(Message::ArrowLeft);
+    let _ = app.update(Message::ArrowLeft);
+    let _ = app.update(Message::BackspacePressed);
+    assert_eq!(app.screenplay.elements[0].content, "Zoe\u{301}\u{1F44D}\u{1F3FD}!");
+
+    let _ = app.update(Message::ArrowLeft);
+    let _ = app.update(Message::DeletePressed);
+    assert_eq!(app.screenplay.elements[0].content, "Zo\u{1F44D}\u{1F3FD}!");
+}